@@ -0,0 +1,658 @@
+//! Rust code emitted for lexicon definitions
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+
+use crate::lexicon::{
+  method_name, pascal, resolve_ref, snake, type_name, type_tag, Body, Lexicons, Schema,
+};
+
+/// Methods written by hand in api.rs
+///
+/// refreshSession is authorized by the refresh token instead of the access token,
+/// and stores the returned tokens in the client.
+const MANUAL_METHODS: &[&str] = &["com.atproto.server.refreshSession"];
+
+/// Record used as the default of Record enum
+///
+/// `Record::default()` was a post before api.rs was generated, keep it so.
+const DEFAULT_RECORD: &str = "app.bsky.feed.post";
+
+/// Rust types of "unknown" properties by property name
+///
+/// Lexicons leave the shape of "unknown" open. DID documents of sessions have no "$type"
+/// and are parsed as DidDoc, all the other ones are parsed as Record.
+const UNKNOWN_TYPES: &[(&str, &str)] = &[("didDoc", "DidDoc")];
+
+/// Generated code for each region of api.rs
+#[derive(Debug, Clone, Default)]
+pub struct Generated {
+  pub defs: String,
+  pub outputs: String,
+  pub methods: String,
+  pub errors: String,
+}
+
+struct Union {
+  name: String,
+  variants: Vec<(String, String)>,
+  closed: bool,
+}
+
+enum Output {
+  None,
+  Json(String),
+  Car,
+  Bytes,
+}
+
+/// Emitter of structs, unions, Record enum and Client methods
+pub struct Generator<'a> {
+  lexicons: &'a Lexicons,
+  unions: Vec<Union>,
+  union_names: HashSet<String>,
+}
+
+impl<'a> Generator<'a> {
+  pub fn new(lexicons: &'a Lexicons) -> Self {
+    Self {
+      lexicons,
+      unions: Vec::new(),
+      union_names: HashSet::new(),
+    }
+  }
+
+  /// Generate code of all regions
+  pub fn generate(mut self) -> Result<Generated> {
+    let defs = self.defs()?;
+    let mut outputs = self.outputs()?;
+    let methods = self.methods()?;
+    let errors = self.errors();
+    for union in self.unions.iter() {
+      outputs.push_str(&emit_union(union));
+    }
+    Ok(Generated {
+      defs,
+      outputs,
+      methods,
+      errors,
+    })
+  }
+
+  fn defs(&mut self) -> Result<String> {
+    let lexicons = self.lexicons;
+    let mut aliases = String::new();
+    let mut arrays = String::new();
+    let mut objects = String::new();
+    let mut records = String::new();
+    let mut record_types = Vec::new();
+    for (nsid, doc) in lexicons.docs.iter() {
+      for (def, schema) in doc.defs.iter() {
+        let name = type_name(nsid, def);
+        match schema.kind.as_str() {
+          "string" => aliases.push_str(&format!(
+            "{}#[derive(Debug, Clone, Serialize, Deserialize, Default)]\npub struct {}(String);\n\n",
+            doc_comment(schema.description.as_deref(), ""),
+            name
+          )),
+          "array" => {
+            let Some(items) = &schema.items else {
+              bail!("no items in {}", type_tag(nsid, def));
+            };
+            let item = self.field_type(nsid, &format!("{}Item", name), "", items)?;
+            arrays.push_str(&format!(
+              "{}#[derive(Debug, Clone, Serialize, Deserialize, Default)]\npub struct {}(Vec<{}>);\n\n",
+              doc_comment(schema.description.as_deref(), ""),
+              name,
+              item
+            ));
+          }
+          "object" => {
+            let prefix = union_prefix(nsid, def);
+            objects.push_str(&self.emit_struct(&name, &prefix, nsid, schema, true, true)?);
+          }
+          "record" => {
+            let Some(record) = &schema.record else {
+              bail!("no record in {}", nsid);
+            };
+            let prefix = union_prefix(nsid, def);
+            let mut record = record.clone();
+            if record.description.is_none() {
+              record.description = schema.description.clone();
+            }
+            records.push_str(&self.emit_struct(&name, &prefix, nsid, &record, true, false)?);
+            record_types.push((nsid.clone(), name, record));
+          }
+          _ => (),
+        }
+      }
+    }
+    Ok(format!(
+      "{}{}{}{}{}",
+      aliases,
+      arrays,
+      objects,
+      records,
+      emit_record_enum(&record_types)
+    ))
+  }
+
+  fn outputs(&mut self) -> Result<String> {
+    let lexicons = self.lexicons;
+    let mut ret = String::new();
+    for (nsid, doc) in lexicons.docs.iter() {
+      let Some(schema) = doc.defs.get("main") else {
+        continue;
+      };
+      if !matches!(schema.kind.as_str(), "query" | "procedure") {
+        continue;
+      }
+      let Some(output) = schema.output.as_ref().and_then(|o| o.schema.as_ref()) else {
+        continue;
+      };
+      if output.kind == "object" {
+        let prefix = format!("{}Output", union_prefix(nsid, "main"));
+        ret.push_str(&self.emit_struct(
+          &type_name(nsid, "main"),
+          &prefix,
+          nsid,
+          output,
+          false,
+          false,
+        )?);
+      }
+    }
+    Ok(ret)
+  }
+
+  fn methods(&mut self) -> Result<String> {
+    let lexicons = self.lexicons;
+    let mut ret = String::new();
+    let mut subscriptions = String::new();
+    for (nsid, doc) in lexicons.docs.iter() {
+      let Some(schema) = doc.defs.get("main") else {
+        continue;
+      };
+      if MANUAL_METHODS.contains(&nsid.as_str()) {
+        continue;
+      }
+      match schema.kind.as_str() {
+        "query" | "procedure" => ret.push_str(&self.emit_method(nsid, schema)?),
+        "subscription" => subscriptions.push_str(&self.emit_subscription(nsid, schema)?),
+        _ => (),
+      }
+    }
+    ret.push_str(&subscriptions);
+    Ok(ret)
+  }
+
+  /// known_errors function matching methods to the errors of their lexicons
+  fn errors(&self) -> String {
+    let mut ret = String::from(
+      "/// Returns error names defined by the lexicon of the method\npub fn known_errors(method: &str) -> &'static [&'static str] {\n  match method {\n",
+    );
+    for (nsid, doc) in self.lexicons.docs.iter() {
+      let Some(schema) = doc.defs.get("main") else {
+        continue;
+      };
+      if schema.errors.is_empty() {
+        continue;
+      }
+      let names = schema
+        .errors
+        .iter()
+        .map(|e| format!("\"{}\"", e.name))
+        .collect::<Vec<_>>();
+      ret.push_str(&format!("    \"{}\" => &[{}],\n", nsid, names.join(", ")));
+    }
+    ret.push_str("    _ => &[],\n  }\n}\n");
+    ret
+  }
+
+  /// Returns Rust type of the schema, inline unions are named by the prefix
+  fn field_type(
+    &mut self,
+    nsid: &str,
+    prefix: &str,
+    name: &str,
+    schema: &Schema,
+  ) -> Result<String> {
+    Ok(match schema.kind.as_str() {
+      "string" => match schema.format.as_deref() {
+        Some("datetime") => String::from("DateTime<Utc>"),
+        Some("cid") => String::from("CidString"),
+        _ => String::from("String"),
+      },
+      "integer" => String::from("i64"),
+      "boolean" => String::from("bool"),
+      "bytes" => String::from("Vec<u8>"),
+      "cid-link" => String::from("String"),
+      "blob" => String::from("Blob"),
+      "unknown" => UNKNOWN_TYPES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, ty)| ty.to_string())
+        .unwrap_or_else(|| String::from("Record")),
+      "array" => {
+        let Some(items) = &schema.items else {
+          bail!("no items in {}", prefix);
+        };
+        format!(
+          "Vec<{}>",
+          self.field_type(nsid, &format!("{}Item", prefix), name, items)?
+        )
+      }
+      "ref" => {
+        let Some(reference) = &schema.reference else {
+          bail!("no ref in {}", prefix);
+        };
+        self.ref_type(nsid, reference)?
+      }
+      "union" => {
+        let variants = schema
+          .refs
+          .iter()
+          .map(|r| {
+            let (ref_nsid, def) = resolve_ref(nsid, r);
+            (type_name(&ref_nsid, &def), type_tag(&ref_nsid, &def))
+          })
+          .collect();
+        if self.union_names.insert(prefix.to_string()) {
+          self.unions.push(Union {
+            name: prefix.to_string(),
+            variants,
+            closed: schema.closed.unwrap_or_default(),
+          });
+        }
+        prefix.to_string()
+      }
+      _ => String::from("Value"),
+    })
+  }
+
+  fn ref_type(&self, nsid: &str, reference: &str) -> Result<String> {
+    let (ref_nsid, def) = resolve_ref(nsid, reference);
+    let Some(target) = self.lexicons.get(&ref_nsid, &def) else {
+      bail!("unresolved reference {}", type_tag(&ref_nsid, &def));
+    };
+    Ok(match target.kind.as_str() {
+      "token" => String::from("String"),
+      _ => type_name(&ref_nsid, &def),
+    })
+  }
+
+  fn emit_struct(
+    &mut self,
+    name: &str,
+    prefix: &str,
+    nsid: &str,
+    schema: &Schema,
+    default: bool,
+    extra: bool,
+  ) -> Result<String> {
+    let mut fields = String::new();
+    for (key, property, required) in schema.ordered_properties() {
+      let ty = self.field_type(nsid, &format!("{}{}", prefix, pascal(key)), key, property)?;
+      let field = snake(key);
+      if &field != key {
+        fields.push_str(&format!("  #[serde(rename = \"{}\")]\n", key));
+      }
+      match required {
+        true => fields.push_str(&format!("  pub {}: {},\n", field, ty)),
+        false => fields.push_str(&format!("  pub {}: Option<{}>,\n", field, ty)),
+      }
+    }
+    if extra {
+      fields.push_str("\n  #[serde(flatten)]\n  pub extra: HashMap<String, Value>,\n");
+    }
+    Ok(format!(
+      "{}#[skip_serializing_none]\n#[derive(Debug, Clone, Serialize, Deserialize{})]\npub struct {} {{\n{}}}\n\n",
+      doc_comment(schema.description.as_deref(), ""),
+      if default { ", Default" } else { "" },
+      name,
+      fields
+    ))
+  }
+
+  /// Returns argument type of a query parameter or an input property
+  fn arg_type(&mut self, nsid: &str, prefix: &str, name: &str, schema: &Schema) -> Result<String> {
+    Ok(match schema.kind.as_str() {
+      "string" | "ref" => match self.field_type(nsid, prefix, name, schema)?.as_str() {
+        "String" => String::from("&str"),
+        ty => format!("&{}", ty),
+      },
+      "integer" | "boolean" | "union" => self.field_type(nsid, prefix, name, schema)?,
+      "bytes" => String::from("&[u8]"),
+      "array" => {
+        let Some(items) = &schema.items else {
+          bail!("no items in {}", prefix);
+        };
+        match self
+          .arg_type(nsid, &format!("{}Item", prefix), name, items)?
+          .as_str()
+        {
+          "&str" => String::from("&[&str]"),
+          _ => format!(
+            "&[&{}]",
+            self.field_type(nsid, &format!("{}Item", prefix), name, items)?
+          ),
+        }
+      }
+      _ => format!("&{}", self.field_type(nsid, prefix, name, schema)?),
+    })
+  }
+
+  fn emit_params(
+    &mut self,
+    nsid: &str,
+    schema: &Schema,
+    args: &mut Vec<String>,
+    body: &mut Vec<String>,
+  ) -> Result<bool> {
+    let Some(params) = schema.parameters.as_ref() else {
+      return Ok(false);
+    };
+    if params.properties.is_empty() {
+      return Ok(false);
+    }
+    let prefix = format!("{}Params", union_prefix(nsid, "main"));
+    body.push(String::from("let mut _q = Vec::new();"));
+    for (key, property, required) in params.ordered_properties() {
+      let name = snake(key);
+      let ty = self.arg_type(nsid, &format!("{}{}", prefix, pascal(key)), key, property)?;
+      let value = match property.kind.as_str() {
+        "array" => to_query_string(property.items.as_deref().unwrap_or(property)),
+        _ => to_query_string(property),
+      };
+      body.push(match (ty.as_str(), required) {
+        ("&str", true) => format!("_q.push((\"{}\", {}));", key, name),
+        ("&str", false) => format!("if let Some(v) = {} {{\n_q.push((\"{}\", v));\n}}", name, key),
+        ("&[&str]", true) => format!("_q.extend({}.iter().map(|v| (\"{}\", *v)));", name, key),
+        ("&[&str]", false) => format!(
+          "if let Some(v) = {} {{\n_q.extend(v.iter().map(|v| (\"{}\", *v)));\n}}",
+          name, key
+        ),
+        (_, true) if property.kind == "array" => format!(
+          "let {}_value = {}.iter().map(|v| {}).collect::<Vec<_>>();\n\n_q.extend({}_value.iter().map(|v| (\"{}\", v.as_str())));",
+          name, name, value, name, key
+        ),
+        (_, false) if property.kind == "array" => format!(
+          "let {}_value = {}\n.map(|a| a.iter().map(|v| {}).collect::<Vec<_>>())\n.unwrap_or_default();\n\n_q.extend({}_value.iter().map(|v| (\"{}\", v.as_str())));",
+          name, name, value, name, key
+        ),
+        (_, true) => format!(
+          "let {}_value = {};\n\n_q.push((\"{}\", {}_value.as_str()));",
+          name,
+          value.replacen('v', &name, 1),
+          key,
+          name
+        ),
+        (_, false) => format!(
+          "let {}_value = {}.map(|v| {});\n\nif let Some(v) = &{}_value {{\n_q.push((\"{}\", v.as_str()));\n}}",
+          name, name, value, name, key
+        ),
+      });
+      args.push(match required {
+        true => format!("{}: {}", name, ty),
+        false => format!("{}: Option<{}>", name, ty),
+      });
+    }
+    Ok(true)
+  }
+
+  fn emit_method(&mut self, nsid: &str, schema: &Schema) -> Result<String> {
+    let mut args = Vec::new();
+    let mut body = Vec::new();
+    let has_query = self.emit_params(nsid, schema, &mut args, &mut body)?;
+    let req = match has_query {
+      true => "req.query_pairs(_q)",
+      false => "req",
+    };
+    let mut mutable = false;
+    let send = match schema.input.as_ref() {
+      Some(input) if input.encoding == "application/json" => match input.schema.as_deref() {
+        Some(s) if s.kind == "object" => {
+          let prefix = format!("{}Input", union_prefix(nsid, "main"));
+          body.push(String::from("let mut input = serde_json::Map::new();"));
+          for (key, property, required) in s.ordered_properties() {
+            let name = snake(key);
+            let ty = self.arg_type(nsid, &format!("{}{}", prefix, pascal(key)), key, property)?;
+            body.push(match required {
+              true => format!("input.insert(String::from(\"{}\"), json!({}));", key, name),
+              false => format!(
+                "if let Some(v) = &{} {{\ninput.insert(String::from(\"{}\"), json!(v));\n}}",
+                name, key
+              ),
+            });
+            args.push(match required {
+              true => format!("{}: {}", name, ty),
+              false => format!("{}: Option<{}>", name, ty),
+            });
+          }
+          format!("self.send_json({}, json!(input))", req)
+        }
+        Some(s) => {
+          let prefix = format!("{}Input", union_prefix(nsid, "main"));
+          args.push(format!(
+            "input: &{}",
+            self.field_type(nsid, &prefix, "", s)?
+          ));
+          format!("self.send_json({}, serde_json::to_value(input)?)", req)
+        }
+        None => format!("self.send_json({}, json!({{}}))", req),
+      },
+      Some(input) => {
+        mutable = true;
+        let bytes = match input.encoding.as_str() {
+          "application/vnd.ipld.car" => "car",
+          _ => "bytes",
+        };
+        args.push(format!("{}: &[u8]", bytes));
+        match input.encoding.as_str() {
+          "*/*" | "" => {
+            args.push(String::from("content_type: &str"));
+            body.push(String::from(
+              "req = req.set(\"Content-Type\", content_type);",
+            ));
+          }
+          encoding => body.push(format!(
+            "req = req.set(\"Content-Type\", \"{}\");",
+            encoding
+          )),
+        }
+        format!("self.send_bytes({}, {})", req, bytes)
+      }
+      None => format!("self.call({})", req),
+    };
+    let (ret, tail) = match self.output(nsid, schema.output.as_ref())? {
+      Output::None => (String::from("HttpResponse"), send),
+      Output::Json(ty) => (ty, format!("{}?.into_json()", send)),
+      Output::Car => (
+        String::from("Blocks"),
+        format!(
+          "let ret = {}?.body;\n\nBlocks::from_car(ret.as_slice(), self.verify_blocks)",
+          send
+        ),
+      ),
+      Output::Bytes => (String::from("Vec<u8>"), format!("Ok({}?.body)", send)),
+    };
+    let method = match schema.kind.as_str() {
+      "procedure" => "post",
+      _ => "get",
+    };
+    let mut code = doc_comment(schema.description.as_deref(), "  ");
+    code.push_str(&format!(
+      "  pub fn {}(&self, {}) -> Result<{}> {{\n",
+      method_name(nsid),
+      args.join(", "),
+      ret
+    ));
+    code.push_str(&format!(
+      "let {}req = HttpRequest::{}(format!(\"https://{{}}/xrpc/{}\", self.host));\n\n",
+      if mutable { "mut " } else { "" },
+      method,
+      nsid
+    ));
+    for line in body {
+      code.push_str(&line);
+      code.push_str("\n\n");
+    }
+    code.push_str(&tail);
+    code.push_str("\n}\n\n");
+    Ok(code)
+  }
+
+  fn output(&mut self, nsid: &str, output: Option<&Body>) -> Result<Output> {
+    let Some(output) = output else {
+      return Ok(Output::None);
+    };
+    Ok(match (output.encoding.as_str(), output.schema.as_deref()) {
+      ("application/json", Some(s)) if s.kind == "object" => Output::Json(type_name(nsid, "main")),
+      ("application/json", Some(s)) => {
+        let prefix = format!("{}Output", union_prefix(nsid, "main"));
+        Output::Json(self.field_type(nsid, &prefix, "", s)?)
+      }
+      ("application/json", None) | ("", _) => Output::None,
+      ("application/vnd.ipld.car", _) => Output::Car,
+      _ => Output::Bytes,
+    })
+  }
+
+  fn emit_subscription(&mut self, nsid: &str, schema: &Schema) -> Result<String> {
+    if let Some(message) = schema.message.as_ref().and_then(|m| m.schema.as_deref()) {
+      let prefix = format!("{}Message", union_prefix(nsid, "main"));
+      self.field_type(nsid, &prefix, "", message)?;
+    }
+    let mut args = Vec::new();
+    let mut body = Vec::new();
+    let has_query = self.emit_params(nsid, schema, &mut args, &mut body)?;
+    let mut code = doc_comment(schema.description.as_deref(), "  ");
+    code.push_str(&format!(
+      "  pub fn {}(&self, {}) -> Result<WebSocket<MaybeTlsStream<TcpStream>>> {{\n",
+      method_name(nsid),
+      args.join(", ")
+    ));
+    code.push_str(&format!(
+      "let {}url = Url::parse(&format!(\"wss://{{}}/xrpc/{}\", self.bgs_host))?;\n\n",
+      if has_query { "mut " } else { "" },
+      nsid
+    ));
+    for line in body {
+      code.push_str(&line);
+      code.push_str("\n\n");
+    }
+    if has_query {
+      code.push_str("url.query_pairs_mut().extend_pairs(_q);\n\n");
+    }
+    code.push_str("Ok(tungstenite::connect(&url)?.0)\n}\n\n");
+    Ok(code)
+  }
+}
+
+/// Prefix of inline union names like "AppBskyFeedPostMain"
+fn union_prefix(nsid: &str, def: &str) -> String {
+  format!("{}{}", type_name(nsid, "main"), pascal(def))
+}
+
+/// Returns expression converting `v` of the parameter to String
+fn to_query_string(schema: &Schema) -> &'static str {
+  match schema.format.as_deref() {
+    Some("datetime") if schema.kind == "string" => "v.to_rfc3339()",
+    _ => "v.to_string()",
+  }
+}
+
+fn doc_comment(description: Option<&str>, indent: &str) -> String {
+  match description {
+    Some(d) if !d.trim().is_empty() => format!(
+      "{}/// {}\n",
+      indent,
+      d.split_whitespace().collect::<Vec<_>>().join(" ")
+    ),
+    _ => String::new(),
+  }
+}
+
+fn emit_union(union: &Union) -> String {
+  let mut ret = format!(
+    "#[derive(Debug, Clone, Serialize, Deserialize)]\n#[serde(tag = \"$type\")]\npub enum {} {{\n",
+    union.name
+  );
+  for (variant, tag) in union.variants.iter() {
+    ret.push_str(&format!(
+      "  #[serde(rename = \"{}\")]\n  {}(Box<{}>),\n",
+      tag, variant, variant
+    ));
+  }
+  // open unions may have types added later
+  if !union.closed {
+    ret.push_str("\n  #[serde(other)]\n  Other,\n");
+  }
+  ret.push_str("}\n\n");
+  let default = match union.variants.first() {
+    Some((variant, _)) => format!("Self::{}(Box::default())", variant),
+    None => String::from("Self::Other"),
+  };
+  ret.push_str(&format!(
+    "impl Default for {} {{\n  fn default() -> Self {{\n    {}\n  }}\n}}\n\n",
+    union.name, default
+  ));
+  ret
+}
+
+fn emit_record_enum(records: &[(String, String, Box<Schema>)]) -> String {
+  let mut ret = String::from(
+    "#[skip_serializing_none]\n#[derive(Debug, Clone, Serialize, Deserialize)]\n#[serde(tag = \"$type\")]\npub enum Record {\n",
+  );
+  for (nsid, name, _) in records.iter() {
+    ret.push_str(&format!(
+      "  #[serde(rename = \"{}\")]\n  {}({}),\n\n",
+      nsid, name, name
+    ));
+  }
+  ret.push_str("  #[serde(other)]\n  Other,\n}\n\n");
+  let default = records
+    .iter()
+    .find(|(nsid, _, _)| nsid == DEFAULT_RECORD)
+    .or(records.first())
+    .map(|(_, name, _)| format!("Self::{}({}::default())", name, name))
+    .unwrap_or_else(|| String::from("Self::Other"));
+  ret.push_str(&format!(
+    "impl Default for Record {{\n  fn default() -> Self {{\n    {}\n  }}\n}}\n\n",
+    default
+  ));
+  ret.push_str("impl Record {\n");
+  for (nsid, name, _) in records.iter() {
+    ret.push_str(&format!(
+      "  pub fn as_{}(&self) -> Option<&{}> {{\n    match self {{\n      Self::{}(v) => Some(v),\n      _ => None,\n    }}\n  }}\n\n",
+      method_name(nsid),
+      name,
+      name
+    ));
+  }
+  ret.push_str(
+    "  pub fn to_ipld(&self) -> Result<Ipld> {\n    Ok(json_to_ipld(&serde_json::to_value(self)?))\n  }\n\n",
+  );
+  ret.push_str("  pub fn get_created_at(&self) -> Option<DateTime<Utc>> {\n    match self {\n");
+  for (_, name, schema) in records.iter() {
+    let created_at = match schema.properties.get("createdAt") {
+      Some(p) if p.format.as_deref() == Some("datetime") => {
+        match schema.required.iter().any(|r| r == "createdAt") {
+          true => "Some(v.created_at)",
+          false => "v.created_at",
+        }
+      }
+      _ => "None",
+    };
+    let binding = match created_at {
+      "None" => "_",
+      _ => "v",
+    };
+    ret.push_str(&format!(
+      "      Self::{}({}) => {},\n",
+      name, binding, created_at
+    ));
+  }
+  ret.push_str("      _ => None,\n    }\n  }\n}\n\n");
+  ret
+}
@@ -0,0 +1,171 @@
+//! Lexicon documents and naming of the generated types
+//!
+//! Lexicon files are kept as they are in atproto. Names of the generated code are derived here,
+//! definition and method names are lowercased after the first letter of each NSID segment
+//! (`app.bsky.actor.defs#profileViewBasic` becomes `AppBskyActorDefsProfileviewbasic`)
+//! to keep the names api.rs had before it was generated.
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use serde::Deserialize;
+
+/// Lexicon document of a NSID
+#[derive(Debug, Clone, Deserialize)]
+pub struct LexiconDoc {
+  pub id: String,
+  pub defs: IndexMap<String, Schema>,
+}
+
+/// Any schema node of a lexicon
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+  #[serde(rename = "type")]
+  pub kind: String,
+  pub description: Option<String>,
+  pub format: Option<String>,
+  #[serde(rename = "ref")]
+  pub reference: Option<String>,
+  #[serde(default)]
+  pub refs: Vec<String>,
+  pub closed: Option<bool>,
+  pub items: Option<Box<Schema>>,
+  #[serde(default)]
+  pub properties: IndexMap<String, Schema>,
+  #[serde(default)]
+  pub required: Vec<String>,
+  pub record: Option<Box<Schema>>,
+  pub parameters: Option<Box<Schema>>,
+  pub input: Option<Body>,
+  pub output: Option<Body>,
+  pub message: Option<Body>,
+  #[serde(default)]
+  pub errors: Vec<ErrorDef>,
+}
+
+impl Schema {
+  /// Returns properties with required ones first
+  pub fn ordered_properties(&self) -> Vec<(&String, &Schema, bool)> {
+    let required = self
+      .required
+      .iter()
+      .filter_map(|r| self.properties.get_key_value(r))
+      .map(|(k, v)| (k, v, true));
+    let optional = self
+      .properties
+      .iter()
+      .filter(|(k, _)| !self.required.contains(k))
+      .map(|(k, v)| (k, v, false));
+    required.chain(optional).collect()
+  }
+}
+
+/// Input, output or message of XRPC methods
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Body {
+  #[serde(default)]
+  pub encoding: String,
+  pub schema: Option<Box<Schema>>,
+}
+
+/// Error a XRPC method may return
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ErrorDef {
+  pub name: String,
+}
+
+/// All lexicon documents keyed by NSID
+#[derive(Debug, Clone, Default)]
+pub struct Lexicons {
+  pub docs: BTreeMap<String, LexiconDoc>,
+}
+
+impl Lexicons {
+  /// Read every JSON file under the directory
+  pub fn load<P: AsRef<Path>>(dir: P) -> Result<Self> {
+    let mut lexicons = Self::default();
+    lexicons.load_dir(dir.as_ref())?;
+    Ok(lexicons)
+  }
+
+  fn load_dir(&mut self, dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+      let path = entry?.path();
+      if path.is_dir() {
+        self.load_dir(&path)?;
+      } else if path.extension().map(|e| e == "json").unwrap_or_default() {
+        let doc: LexiconDoc = serde_json::from_str(&fs::read_to_string(&path)?)
+          .map_err(|e| anyhow!("{} : {}", path.display(), e))?;
+        self.docs.insert(doc.id.clone(), doc);
+      }
+    }
+    Ok(())
+  }
+
+  /// Returns the definition of the reference
+  pub fn get(&self, nsid: &str, def: &str) -> Option<&Schema> {
+    self.docs.get(nsid)?.defs.get(def)
+  }
+}
+
+/// Split reference like "#view" or "app.bsky.feed.defs#view" into NSID and definition name
+pub fn resolve_ref(nsid: &str, reference: &str) -> (String, String) {
+  match reference.split_once('#') {
+    Some(("", def)) => (nsid.to_string(), def.to_string()),
+    Some((ref_nsid, def)) => (ref_nsid.to_string(), def.to_string()),
+    None => (reference.to_string(), String::from("main")),
+  }
+}
+
+/// Returns $type of the definition, "main" is omitted
+pub fn type_tag(nsid: &str, def: &str) -> String {
+  match def {
+    "main" => nsid.to_string(),
+    _ => format!("{}#{}", nsid, def),
+  }
+}
+
+/// Capitalize only the first letter like "Profileview"
+pub fn pascal(s: &str) -> String {
+  let lower = s.to_lowercase();
+  let mut chars = lower.chars();
+  match chars.next() {
+    Some(c) => c.to_uppercase().chain(chars).collect(),
+    None => String::new(),
+  }
+}
+
+/// Returns type name like "AppBskyActorDefsProfileview"
+pub fn type_name(nsid: &str, def: &str) -> String {
+  let name = nsid.split('.').map(pascal).collect::<String>();
+  match def {
+    "main" => name,
+    _ => format!("{}{}", name, pascal(def)),
+  }
+}
+
+/// Returns method name like "app_bsky_actor_getprofile"
+pub fn method_name(nsid: &str) -> String {
+  nsid.replace(['.', '-'], "_").to_lowercase()
+}
+
+/// Returns field name like "created_at" from "createdAt"
+///
+/// "type" becomes "value_type" as api.rs named it by hand, other keywords get "r_" prefix.
+pub fn snake(s: &str) -> String {
+  let mut ret = String::new();
+  for (i, c) in s.chars().enumerate() {
+    if c.is_uppercase() && i > 0 {
+      ret.push('_');
+    }
+    ret.extend(c.to_lowercase());
+  }
+  match ret.as_str() {
+    "type" => String::from("value_type"),
+    "ref" | "self" | "match" | "move" | "use" | "mod" | "impl" | "fn" | "in" | "loop" => {
+      format!("r_{}", ret)
+    }
+    _ => ret,
+  }
+}
@@ -0,0 +1,101 @@
+//! Regenerate src/api.rs from lexicon files
+//!
+//! cargo run -p aerostream-codegen -- codegen/lexicons src/api.rs
+//!
+//! Code between "// codegen:begin <region>" and "// codegen:end <region>" is replaced,
+//! the rest of the file is written by hand and kept as is.
+mod generate;
+mod lexicon;
+
+use std::{
+  fs,
+  io::Write,
+  process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{generate::Generator, lexicon::Lexicons};
+
+/// Replace code between the markers of the region
+fn splice(source: &str, region: &str, code: &str) -> Result<String> {
+  let begin = format!("// codegen:begin {}\n", region);
+  let end = format!("// codegen:end {}", region);
+  let (Some(start), Some(stop)) = (source.find(&begin), source.find(&end)) else {
+    bail!("no markers of {} region", region);
+  };
+  let start = start + begin.len();
+  if stop < start {
+    bail!("broken markers of {} region", region);
+  }
+  Ok(format!("{}{}{}", &source[..start], code, &source[stop..]))
+}
+
+/// Format the source with rustfmt
+fn format(source: &str) -> Result<String> {
+  let mut child = Command::new("rustfmt")
+    .args([
+      "--edition",
+      "2021",
+      "--config",
+      "tab_spaces=2",
+      "--emit",
+      "stdout",
+    ])
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .spawn()?;
+  child
+    .stdin
+    .take()
+    .ok_or_else(|| anyhow!("no stdin of rustfmt"))?
+    .write_all(source.as_bytes())?;
+  let output = child.wait_with_output()?;
+  if !output.status.success() {
+    bail!("rustfmt failed : {}", output.status);
+  }
+  Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Returns the source with generated regions replaced by the code of lexicons
+fn regenerate(lexicons: &Lexicons, source: &str) -> Result<String> {
+  let generated = Generator::new(lexicons).generate()?;
+  let mut source = splice(source, "defs", &generated.defs)?;
+  source = splice(&source, "outputs", &generated.outputs)?;
+  source = splice(&source, "methods", &generated.methods)?;
+  source = splice(&source, "errors", &generated.errors)?;
+  format(&source)
+}
+
+fn main() -> Result<()> {
+  let args = std::env::args().collect::<Vec<_>>();
+  let [_, lexicons, target] = args.as_slice() else {
+    bail!("usage : aerostream-codegen <lexicons directory> <api.rs>");
+  };
+  let lexicons = Lexicons::load(lexicons)?;
+  let source = regenerate(&lexicons, &fs::read_to_string(target)?)?;
+  fs::write(target, source)?;
+  println!("generated {} lexicons into {}", lexicons.docs.len(), target);
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use super::*;
+
+  #[test]
+  fn api_is_up_to_date() -> Result<()> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lexicons = Lexicons::load(root.join("lexicons"))?;
+    let target = root.join("../src/api.rs");
+    let source = fs::read_to_string(&target)?;
+    assert!(
+      regenerate(&lexicons, &source)? == source,
+      "{} is not generated from lexicons, run aerostream-codegen",
+      target.display()
+    );
+    Ok(())
+  }
+}
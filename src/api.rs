@@ -1,21 +1,33 @@
 use std::{
-  collections::HashMap,
+  collections::{HashMap, HashSet},
   fmt::{Debug, Display},
   io::{Cursor, Seek},
   net::TcpStream,
   str::FromStr,
+  sync::{Arc, Mutex, RwLock},
+  time::{Duration, Instant},
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use chrono::{DateTime, Utc};
-use libipld::{cbor::DagCborCodec, prelude::Codec, Cid, Ipld};
+use libipld::{cbor::DagCborCodec, multibase::Base, prelude::Codec, Cid, Ipld};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use serde_with::skip_serializing_none;
+use sha2::{Digest, Sha256};
 use tungstenite::{stream::MaybeTlsStream, WebSocket};
 use ureq::{Agent, AgentBuilder, Proxy};
 use url::Url;
 
+use crate::{
+  chat::CHAT_SERVICE,
+  crypto::{KeyAlgorithm, PublicKey},
+  oauth::OAuthAgent,
+  transport::{HttpRequest, HttpResponse, HttpTransport},
+  xrpc::{method_of, RateLimit, RateLimiter, RetryPolicy, XrpcError, XrpcErrorName},
+};
+
+// codegen:begin defs
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppBskyActorDefsMutedwordtarget(String);
 
@@ -89,7 +101,7 @@ pub struct AppBskyActorDefsProfileviewdetailed {
   pub extra: HashMap<String, Value>,
 }
 
-/// Metadata about the requesting account&#39;s relationship with the subject account. Only has meaningful content for authed requests.
+/// Metadata about the requesting account's relationship with the subject account. Only has meaningful content for authed requests.
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppBskyActorDefsViewerstate {
@@ -218,7 +230,7 @@ pub struct AppBskyActorDefsHiddenpostspref {
   pub extra: HashMap<String, Value>,
 }
 
-/// A representation of some externally linked content (eg, a URL and &#39;card&#39;), embedded in a Bluesky record (eg, a post).
+/// A representation of some externally linked content (eg, a URL and 'card'), embedded in a Bluesky record (eg, a post).
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppBskyEmbedExternal {
@@ -415,7 +427,7 @@ pub struct AppBskyFeedDefsPostview {
   pub extra: HashMap<String, Value>,
 }
 
-/// Metadata about the requesting account&#39;s relationship with the subject content. Only has meaningful content for authed requests.
+/// Metadata about the requesting account's relationship with the subject content. Only has meaningful content for authed requests.
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppBskyFeedDefsViewerstate {
@@ -772,7 +784,7 @@ pub struct AppBskyRichtextFacet {
   pub extra: HashMap<String, Value>,
 }
 
-/// Facet feature for mention of another account. The text is usually a handle, including a &#39;@&#39; prefix, but the facet reference is a DID.
+/// Facet feature for mention of another account. The text is usually a handle, including a '@' prefix, but the facet reference is a DID.
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppBskyRichtextFacetMention {
@@ -792,7 +804,7 @@ pub struct AppBskyRichtextFacetLink {
   pub extra: HashMap<String, Value>,
 }
 
-/// Facet feature for a hashtag. The text usually includes a &#39;#&#39; prefix, but the facet reference should not (except in the case of &#39;double hash tags&#39;).
+/// Facet feature for a hashtag. The text usually includes a '#' prefix, but the facet reference should not (except in the case of 'double hash tags').
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppBskyRichtextFacetTag {
@@ -845,11 +857,175 @@ pub struct AppBskyUnspeccedGettaggedsuggestionsSuggestion {
   pub extra: HashMap<String, Value>,
 }
 
+/// Profile of a member of a conversation
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatBskyActorDefsProfileviewbasic {
+  pub did: String,
+  pub handle: String,
+  #[serde(rename = "displayName")]
+  pub display_name: Option<String>,
+  pub avatar: Option<String>,
+  pub viewer: Option<AppBskyActorDefsViewerstate>,
+  pub labels: Option<Vec<ComAtprotoLabelDefsLabel>>,
+  #[serde(rename = "chatDisabled")]
+  pub chat_disabled: Option<bool>,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+/// Reference to a message in a conversation
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatBskyConvoDefsMessageref {
+  pub did: String,
+  #[serde(rename = "messageId")]
+  pub message_id: String,
+  #[serde(rename = "convoId")]
+  pub convo_id: String,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+/// Message to send to a conversation
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatBskyConvoDefsMessageinput {
+  pub text: String,
+  pub facets: Option<Vec<AppBskyRichtextFacet>>,
+  pub embed: Option<ChatBskyConvoDefsMessageinputEmbed>,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+/// Message sent to a conversation
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatBskyConvoDefsMessageview {
+  pub id: String,
+  pub rev: String,
+  pub text: String,
+  pub sender: ChatBskyConvoDefsMessageviewsender,
+  #[serde(rename = "sentAt")]
+  pub sent_at: DateTime<Utc>,
+  pub facets: Option<Vec<AppBskyRichtextFacet>>,
+  pub embed: Option<ChatBskyConvoDefsMessageviewEmbed>,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+/// Message deleted from a conversation, without its content
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatBskyConvoDefsDeletedmessageview {
+  pub id: String,
+  pub rev: String,
+  pub sender: ChatBskyConvoDefsMessageviewsender,
+  #[serde(rename = "sentAt")]
+  pub sent_at: DateTime<Utc>,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+/// Sender of a message
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatBskyConvoDefsMessageviewsender {
+  pub did: String,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+/// Conversation the account is a member of
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatBskyConvoDefsConvoview {
+  pub id: String,
+  pub rev: String,
+  pub members: Vec<ChatBskyActorDefsProfileviewbasic>,
+  pub muted: bool,
+  #[serde(rename = "unreadCount")]
+  pub unread_count: i64,
+  #[serde(rename = "lastMessage")]
+  pub last_message: Option<ChatBskyConvoDefsConvoviewLastmessage>,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+/// Log of a conversation the account joined
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatBskyConvoDefsLogbeginconvo {
+  pub rev: String,
+  #[serde(rename = "convoId")]
+  pub convo_id: String,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+/// Log of a conversation the account left
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatBskyConvoDefsLogleaveconvo {
+  pub rev: String,
+  #[serde(rename = "convoId")]
+  pub convo_id: String,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+/// Log of a message sent to a conversation
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatBskyConvoDefsLogcreatemessage {
+  pub rev: String,
+  #[serde(rename = "convoId")]
+  pub convo_id: String,
+  pub message: ChatBskyConvoDefsLogcreatemessageMessage,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+/// Log of a message deleted from a conversation
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatBskyConvoDefsLogdeletemessage {
+  pub rev: String,
+  #[serde(rename = "convoId")]
+  pub convo_id: String,
+  pub message: ChatBskyConvoDefsLogdeletemessageMessage,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+/// Message to send to one of the conversations
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatBskyConvoSendmessagebatchBatchitem {
+  #[serde(rename = "convoId")]
+  pub convo_id: String,
+  pub message: ChatBskyConvoDefsMessageinput,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ComAtprotoAdminDefsStatusattr {
   pub applied: bool,
-  #[serde(rename = "r#ref")]
+  #[serde(rename = "ref")]
   pub r_ref: Option<String>,
 
   #[serde(flatten)]
@@ -1557,19 +1733,20 @@ pub struct ComAtprotoSyncSubscribereposCommit {
   pub extra: HashMap<String, Value>,
 }
 
-/// Represents a change to an account&#39;s identity. Could be an updated handle, signing key, or pds hosting endpoint. Serves as a prod to all downstream services to refresh their identity cache.
+/// Represents a change to an account's identity. Could be an updated handle, signing key, or pds hosting endpoint. Serves as a prod to all downstream services to refresh their identity cache.
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ComAtprotoSyncSubscribereposIdentity {
   pub seq: i64,
   pub did: String,
   pub time: DateTime<Utc>,
+  pub handle: Option<String>,
 
   #[serde(flatten)]
   pub extra: HashMap<String, Value>,
 }
 
-/// Represents an update of the account&#39;s handle, or transition to/from invalid state. NOTE: Will be deprecated in favor of #identity.
+/// Represents an update of the account's handle, or transition to/from invalid state. NOTE: Will be deprecated in favor of #identity.
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ComAtprotoSyncSubscribereposHandle {
@@ -1658,7 +1835,7 @@ pub struct AppBskyFeedGenerator {
   pub labels: Option<AppBskyFeedGeneratorMainLabels>,
 }
 
-/// Record declaring a &#39;like&#39; of a piece of subject content.
+/// Record declaring a 'like' of a piece of subject content.
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppBskyFeedLike {
@@ -1683,7 +1860,7 @@ pub struct AppBskyFeedPost {
   pub tags: Option<Vec<String>>,
 }
 
-/// Record representing a &#39;repost&#39; of an existing Bluesky post.
+/// Record representing a 'repost' of an existing Bluesky post.
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppBskyFeedRepost {
@@ -1692,7 +1869,7 @@ pub struct AppBskyFeedRepost {
   pub created_at: DateTime<Utc>,
 }
 
-/// Record defining interaction gating rules for a thread (aka, reply controls). The record key (rkey) of the threadgate record must match the record key of the thread&#39;s root post, and that record must be in the same repository..
+/// Record defining interaction gating rules for a thread (aka, reply controls). The record key (rkey) of the threadgate record must match the record key of the thread's root post, and that record must be in the same repository..
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppBskyFeedThreadgate {
@@ -1702,7 +1879,7 @@ pub struct AppBskyFeedThreadgate {
   pub allow: Option<Vec<AppBskyFeedThreadgateMainAllowItem>>,
 }
 
-/// Record declaring a &#39;block&#39; relationship against another account. NOTE: blocks are public in Bluesky; see blog posts for details.
+/// Record declaring a 'block' relationship against another account. NOTE: blocks are public in Bluesky; see blog posts for details.
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppBskyGraphBlock {
@@ -1711,7 +1888,7 @@ pub struct AppBskyGraphBlock {
   pub created_at: DateTime<Utc>,
 }
 
-/// Record declaring a social &#39;follow&#39; relationship of another account. Duplicate follows will be ignored by the AppView.
+/// Record declaring a social 'follow' relationship of another account. Duplicate follows will be ignored by the AppView.
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppBskyGraphFollow {
@@ -1744,7 +1921,7 @@ pub struct AppBskyGraphListblock {
   pub created_at: DateTime<Utc>,
 }
 
-/// Record representing an account&#39;s inclusion on a specific list. The AppView will ignore duplicate listitem records.
+/// Record representing an account's inclusion on a specific list. The AppView will ignore duplicate listitem records.
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppBskyGraphListitem {
@@ -1801,50 +1978,6 @@ impl Default for Record {
   }
 }
 
-pub fn ipld_to_string(ipld: &Ipld) -> String {
-  match ipld {
-    Ipld::Bool(b) => b.to_string(),
-    Ipld::Bytes(b) => format!(
-      "[{}]",
-      b.iter()
-        .map(|v| v.to_string())
-        .collect::<Vec<_>>()
-        .join(",")
-    ),
-    Ipld::Float(f) => f.to_string(),
-    Ipld::Integer(i) => i.to_string(),
-    Ipld::Link(l) => format!("{{\"$link\": \"{}\"}}", l.to_string()),
-    Ipld::List(l) => format!(
-      "[{}]",
-      l.iter()
-        .map(|v| ipld_to_string(v))
-        .collect::<Vec<_>>()
-        .join(",")
-    ),
-    Ipld::Map(m) => format!(
-      "{{{}}}",
-      m.iter()
-        .map(|(k, v)| format!("\"{}\":{}", k, ipld_to_string(v)))
-        .collect::<Vec<_>>()
-        .join(",")
-    ),
-    Ipld::Null => String::from("null"),
-    Ipld::String(s) => format!(
-      "\"{}\"",
-      s.replace(r"\", r"\\")
-        .replace('"', "\\\"")
-        .replace("\n", r"\n")
-    ),
-  }
-}
-
-impl TryFrom<&Ipld> for Record {
-  type Error = anyhow::Error;
-  fn try_from(value: &Ipld) -> std::result::Result<Self, Self::Error> {
-    Ok(serde_json::from_str(&ipld_to_string(value))?)
-  }
-}
-
 impl Record {
   pub fn as_app_bsky_actor_profile(&self) -> Option<&AppBskyActorProfile> {
     match self {
@@ -1923,6 +2056,10 @@ impl Record {
     }
   }
 
+  pub fn to_ipld(&self) -> Result<Ipld> {
+    Ok(json_to_ipld(&serde_json::to_value(self)?))
+  }
+
   pub fn get_created_at(&self) -> Option<DateTime<Utc>> {
     match self {
       Self::AppBskyActorProfile(_) => None,
@@ -1941,11 +2078,105 @@ impl Record {
   }
 }
 
+// codegen:end defs
+
+pub fn ipld_to_string(ipld: &Ipld) -> String {
+  match ipld {
+    Ipld::Bool(b) => b.to_string(),
+    Ipld::Bytes(b) => format!(
+      "[{}]",
+      b.iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+    ),
+    Ipld::Float(f) => f.to_string(),
+    Ipld::Integer(i) => i.to_string(),
+    Ipld::Link(l) => format!("{{\"$link\": \"{}\"}}", l.to_string()),
+    Ipld::List(l) => format!(
+      "[{}]",
+      l.iter()
+        .map(|v| ipld_to_string(v))
+        .collect::<Vec<_>>()
+        .join(",")
+    ),
+    Ipld::Map(m) => format!(
+      "{{{}}}",
+      m.iter()
+        .map(|(k, v)| format!("\"{}\":{}", k, ipld_to_string(v)))
+        .collect::<Vec<_>>()
+        .join(",")
+    ),
+    Ipld::Null => String::from("null"),
+    Ipld::String(s) => format!(
+      "\"{}\"",
+      s.replace(r"\", r"\\")
+        .replace('"', "\\\"")
+        .replace("\n", r"\n")
+    ),
+  }
+}
+
+/// Returns all CIDs linked from the IPLD
+pub fn ipld_links(ipld: &Ipld) -> Vec<Cid> {
+  match ipld {
+    Ipld::Link(l) => vec![*l],
+    Ipld::List(l) => l.iter().flat_map(ipld_links).collect(),
+    Ipld::Map(m) => m.values().flat_map(ipld_links).collect(),
+    _ => Vec::new(),
+  }
+}
+
+impl TryFrom<&Ipld> for Record {
+  type Error = anyhow::Error;
+  fn try_from(value: &Ipld) -> std::result::Result<Self, Self::Error> {
+    Ok(serde_json::from_str(&ipld_to_string(value))?)
+  }
+}
+
+/// Convert JSON to IPLD, reading $link and $bytes objects as links and bytes
+pub fn json_to_ipld(value: &Value) -> Ipld {
+  match value {
+    Value::Null => Ipld::Null,
+    Value::Bool(b) => Ipld::Bool(*b),
+    Value::Number(n) => match n.as_i64() {
+      Some(i) => Ipld::Integer(i as i128),
+      None => Ipld::Float(n.as_f64().unwrap_or_default()),
+    },
+    Value::String(s) => Ipld::String(s.clone()),
+    Value::Array(a) => Ipld::List(a.iter().map(json_to_ipld).collect()),
+    Value::Object(o) => {
+      if o.len() == 1 {
+        if let Some(cid) = o
+          .get("$link")
+          .and_then(|l| l.as_str())
+          .and_then(|l| Cid::from_str(l).ok())
+        {
+          return Ipld::Link(cid);
+        }
+        if let Some(bytes) = o
+          .get("$bytes")
+          .and_then(|b| b.as_str())
+          .and_then(|b| Base::Base64.decode(b).ok())
+        {
+          return Ipld::Bytes(bytes);
+        }
+      }
+      Ipld::Map(
+        o.iter()
+          .map(|(k, v)| (k.clone(), json_to_ipld(v)))
+          .collect(),
+      )
+    }
+  }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct NodeEntry {
   pub p: i64,
   pub k: Vec<u8>,
   pub v: Link,
+  pub t: Option<Link>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -1967,6 +2198,7 @@ pub struct Commit {
   pub version: i64,
   pub prev: Option<Link>,
   pub data: Link,
+  pub rev: Option<String>,
   pub sig: Vec<u8>,
 }
 
@@ -1986,6 +2218,12 @@ impl CidString {
   }
 }
 
+impl From<Cid> for CidString {
+  fn from(value: Cid) -> Self {
+    Self(value.to_string())
+  }
+}
+
 impl Display for CidString {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     f.write_str(&self.0)
@@ -1993,10 +2231,45 @@ impl Display for CidString {
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum Block {
   Commit(Commit),
   Node(Node),
   Record(Record),
+  Unknown { type_: Option<String>, ipld: Ipld },
+}
+
+impl From<&Ipld> for Block {
+  fn from(value: &Ipld) -> Self {
+    let unknown = |type_: Option<String>| Self::Unknown {
+      type_,
+      ipld: value.clone(),
+    };
+    let Ipld::Map(map) = value else {
+      return unknown(None);
+    };
+    if let Some(type_) = map.get("$type") {
+      let type_ = match type_ {
+        Ipld::String(t) => Some(t.clone()),
+        _ => None,
+      };
+      return match Record::try_from(value) {
+        Ok(Record::Other) | Err(_) => unknown(type_),
+        Ok(r) => Self::Record(r),
+      };
+    }
+    if map.contains_key("did") && map.contains_key("data") && map.contains_key("sig") {
+      if let Ok(c) = Commit::try_from(value) {
+        return Self::Commit(c);
+      }
+    }
+    if map.contains_key("e") && map.contains_key("l") {
+      if let Ok(n) = Node::try_from(value) {
+        return Self::Node(n);
+      }
+    }
+    unknown(None)
+  }
 }
 
 impl Block {
@@ -2020,6 +2293,25 @@ impl Block {
       _ => None,
     }
   }
+
+  pub fn as_unknown(&self) -> Option<&Ipld> {
+    match self {
+      Self::Unknown { ipld, .. } => Some(ipld),
+      _ => None,
+    }
+  }
+
+  /// Returns $type of the record
+  pub fn get_type(&self) -> Option<String> {
+    match self {
+      Self::Record(r) => serde_json::to_value(r).ok().and_then(|v| {
+        v.get("$type")
+          .and_then(|t| t.as_str().map(|t| t.to_string()))
+      }),
+      Self::Unknown { type_, .. } => type_.clone(),
+      _ => None,
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -2053,38 +2345,85 @@ fn get_cid(data: &[u8]) -> Result<(Cid, usize)> {
 
 impl From<&[u8]> for Blocks {
   fn from(data: &[u8]) -> Self {
+    Self::from_car(data, false).unwrap_or_default()
+  }
+}
+
+pub const DAG_CBOR_CODEC: u64 = 0x71;
+pub const RAW_CODEC: u64 = 0x55;
+pub const SHA2_256: u64 = 0x12;
+
+/// Check that the data hashes to the CID
+pub fn verify_cid(cid: &Cid, data: &[u8]) -> Result<()> {
+  if cid.codec() != DAG_CBOR_CODEC && cid.codec() != RAW_CODEC {
+    bail!("unsupported codec {:#x} of {}", cid.codec(), cid);
+  }
+  if cid.hash().code() != SHA2_256 {
+    bail!("unsupported hash {:#x} of {}", cid.hash().code(), cid);
+  }
+  if cid.hash().digest() != Sha256::digest(data).as_slice() {
+    bail!("block {} does not match its content", cid);
+  }
+  Ok(())
+}
+
+impl Blocks {
+  /// Parse CAR file, with verify every block must hash to its CID and decode
+  ///
+  /// Without verify, a broken header returns empty blocks and broken blocks are skipped.
+  /// Raw (0x55) blocks are kept as Ipld::Bytes.
+  pub fn from_car(data: &[u8], verify: bool) -> Result<Self> {
     let mut ret = HashMap::new();
-    let (header, len) = match get_block(data) {
-      Ok(b) => b,
-      _ => return Self::default(),
-    };
-    let header = match DagCborCodec.decode::<Ipld>(header.as_slice()) {
+    let header = get_block(data).and_then(|(header, len)| {
+      let header = DagCborCodec.decode::<Ipld>(header.as_slice())?;
+      Ok((header, len))
+    });
+    let (header, len) = match header {
       Ok(h) => h,
-      _ => return Self::default(),
+      Err(e) if verify => return Err(e),
+      Err(_) => return Ok(Self::default()),
     };
     let mut data = data.get(len..).unwrap_or_default();
     while !data.is_empty() {
-      let Ok((block, len)) = get_block(data) else {
-        log::warn!("cannot decode CAR block");
-        break;
+      let (block, len) = match get_block(data) {
+        Ok((b, l)) if !verify || l <= data.len() => (b, l),
+        _ => {
+          if verify {
+            bail!("cannot decode CAR block");
+          }
+          log::warn!("cannot decode CAR block");
+          break;
+        }
       };
       data = data.get(len..).unwrap_or_default();
       let Ok((cid, len)) = get_cid(block.as_slice()) else {
+        if verify {
+          bail!("cannot decode CAR cid");
+        }
         log::warn!("cannot decode CAR cid");
         continue;
       };
       let block = block.get(len..).unwrap_or_default();
-      let Ok(data) = DagCborCodec.decode::<Ipld>(block) else {
-        log::warn!("cannot decode CAR ipld");
+      if verify {
+        verify_cid(&cid, block)?;
+      }
+      if cid.codec() == RAW_CODEC {
+        ret.insert(cid, Ipld::Bytes(block.to_vec()));
         continue;
+      }
+      let data = match DagCborCodec.decode::<Ipld>(block) {
+        Ok(d) => d,
+        Err(e) if verify => bail!("cannot decode CAR ipld {} : {}", cid, e),
+        Err(_) => {
+          log::warn!("cannot decode CAR ipld");
+          continue;
+        }
       };
       ret.insert(cid, data);
     }
-    Self { header, data: ret }
+    Ok(Self { header, data: ret })
   }
-}
 
-impl Blocks {
   pub fn iter(&self) -> std::collections::hash_map::Iter<Cid, Ipld> {
     self.data.iter()
   }
@@ -2093,24 +2432,77 @@ impl Blocks {
     self.data.get(cid).cloned()
   }
 
-  pub fn get_blocks(&self) -> HashMap<Cid, Block> {
-    self
-      .data
-      .iter()
-      .filter_map(|(cid, i)| match Commit::try_from(i) {
-        Ok(c) => Some((*cid, Block::Commit(c))),
-        Err(_) => match Node::try_from(i) {
-          Ok(n) => Some((*cid, Block::Node(n))),
-          Err(_) => match Record::try_from(i) {
-            Ok(r) => Some((*cid, Block::Record(r))),
-            Err(_) => {
-              log::warn!("unknown IPLD {}", ipld_to_string(i));
-              None
-            }
-          },
-        },
-      })
-      .collect()
+  /// Returns the first root CID written in the CAR header
+  pub fn root(&self) -> Option<Cid> {
+    let Ipld::Map(header) = &self.header else {
+      return None;
+    };
+    let Some(Ipld::List(roots)) = header.get("roots") else {
+      return None;
+    };
+    roots.iter().find_map(|r| match r {
+      Ipld::Link(cid) => Some(*cid),
+      _ => None,
+    })
+  }
+
+  /// Returns the commit pointed to by the CAR root
+  pub fn root_commit(&self) -> Option<Commit> {
+    self
+      .root()
+      .and_then(|cid| self.data.get(&cid))
+      .and_then(|ipld| Commit::try_from(ipld).ok())
+  }
+
+  /// Add the blocks of a diff and take over its header
+  pub fn merge(&mut self, other: Blocks) {
+    self.header = other.header;
+    self.data.extend(other.data);
+  }
+
+  /// Drop the blocks that cannot be reached from the CAR root
+  pub fn retain_reachable(&mut self) {
+    let mut reachable = HashSet::new();
+    let mut stack = self.root().into_iter().collect::<Vec<_>>();
+    while let Some(cid) = stack.pop() {
+      if !reachable.insert(cid) {
+        continue;
+      }
+      if let Some(ipld) = self.data.get(&cid) {
+        stack.extend(ipld_links(ipld));
+      }
+    }
+    self.data.retain(|cid, _| reachable.contains(cid));
+  }
+
+  /// Serialize to a CAR v1 file, root block first
+  pub fn to_car(&self) -> Result<Vec<u8>> {
+    let mut ret = Vec::new();
+    let header = DagCborCodec.encode(&self.header)?;
+    leb128::write::unsigned(&mut ret, header.len() as u64)?;
+    ret.extend(header);
+    let root = self.root();
+    let mut cids = self.data.keys().collect::<Vec<_>>();
+    cids.sort_by_key(|cid| (Some(**cid) != root, **cid));
+    for cid in cids {
+      let cid_bytes = cid.to_bytes();
+      let block = match &self.data[cid] {
+        Ipld::Bytes(bytes) if cid.codec() == RAW_CODEC => bytes.clone(),
+        ipld => DagCborCodec.encode(ipld)?,
+      };
+      leb128::write::unsigned(&mut ret, (cid_bytes.len() + block.len()) as u64)?;
+      ret.extend(cid_bytes);
+      ret.extend(block);
+    }
+    Ok(ret)
+  }
+
+  pub fn get_blocks(&self) -> HashMap<Cid, Block> {
+    self
+      .data
+      .iter()
+      .map(|(cid, i)| (*cid, Block::from(i)))
+      .collect()
   }
 }
 
@@ -2141,10 +2533,82 @@ pub struct DidDoc {
   pub context: Vec<String>,
   pub id: String,
   pub also_known_as: Vec<String>,
-  pub verification_method: Vec<HashMap<String, String>>,
+  pub verification_method: Vec<VerificationMethod>,
   pub service: Vec<AtprotoService>,
 }
 
+impl DidDoc {
+  /// Returns verification method of the fragment like "atproto"
+  pub fn get_verification_method(&self, fragment: &str) -> Option<&VerificationMethod> {
+    self
+      .verification_method
+      .iter()
+      .find(|v| v.id.split('#').nth(1) == Some(fragment))
+  }
+
+  /// Returns public key to verify repository commits
+  pub fn atproto_signing_key(&self) -> Result<PublicKey> {
+    match self.get_verification_method("atproto") {
+      Some(v) => v.public_key(),
+      None => bail!("no atproto verification method in {}", self.id),
+    }
+  }
+
+  /// Returns URL of the PDS hosting the repository
+  pub fn pds_endpoint(&self) -> Option<String> {
+    self.service.iter().find_map(|s| match s {
+      AtprotoService::AtprotoPds(pds) if pds.pds_type == "AtprotoPersonalDataServer" => {
+        Some(pds.service_endpoint.clone())
+      }
+      _ => None,
+    })
+  }
+
+  /// Returns handles claimed in alsoKnownAs
+  pub fn handles(&self) -> Vec<String> {
+    self
+      .also_known_as
+      .iter()
+      .filter_map(|a| a.strip_prefix("at://").map(|h| h.to_string()))
+      .collect()
+  }
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationMethod {
+  pub id: String,
+  #[serde(rename = "type")]
+  pub method_type: String,
+  pub controller: String,
+  pub public_key_multibase: Option<String>,
+
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+impl VerificationMethod {
+  /// Decode publicKeyMultibase of Multikey or legacy secp256k1/P-256 verification key
+  pub fn public_key(&self) -> Result<PublicKey> {
+    let Some(multibase) = &self.public_key_multibase else {
+      bail!("no publicKeyMultibase in {}", self.id);
+    };
+    match self.method_type.as_str() {
+      "Multikey" => PublicKey::from_multibase(multibase),
+      "EcdsaSecp256k1VerificationKey2019" => PublicKey::from_sec1(
+        KeyAlgorithm::Secp256k1,
+        &libipld::multibase::decode(multibase)?.1,
+      ),
+      "EcdsaSecp256r1VerificationKey2019" => PublicKey::from_sec1(
+        KeyAlgorithm::P256,
+        &libipld::multibase::decode(multibase)?.1,
+      ),
+      t => bail!("unsupported verification method type {}", t),
+    }
+  }
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Link {
@@ -2177,6 +2641,7 @@ impl Default for Blob {
   }
 }
 
+// codegen:begin outputs
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppBskyActorGetpreferences {
@@ -2447,6 +2912,45 @@ pub struct AppBskyUnspeccedSearchpostsskeleton {
   pub hits_total: Option<i64>,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatBskyConvoGetconvo {
+  pub convo: ChatBskyConvoDefsConvoview,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatBskyConvoGetlog {
+  pub logs: Vec<ChatBskyConvoGetlogMainOutputLogsItem>,
+  pub cursor: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatBskyConvoGetmessages {
+  pub messages: Vec<ChatBskyConvoGetmessagesMainOutputMessagesItem>,
+  pub cursor: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatBskyConvoListconvos {
+  pub convos: Vec<ChatBskyConvoDefsConvoview>,
+  pub cursor: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatBskyConvoMuteconvo {
+  pub convo: ChatBskyConvoDefsConvoview,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatBskyConvoSendmessagebatch {
+  pub items: Vec<ChatBskyConvoDefsMessageview>,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComAtprotoAdminGetaccountinfos {
@@ -2496,6 +3000,19 @@ pub struct ComAtprotoAdminSearchrepos {
   pub cursor: Option<String>,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoAdminSendemail {
+  pub sent: bool,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoAdminUpdatesubjectstatus {
+  pub subject: ComAtprotoAdminUpdatesubjectstatusMainOutputSubject,
+  pub takedown: Option<ComAtprotoAdminDefsStatusattr>,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComAtprotoIdentityGetrecommendeddidcredentials {
@@ -2514,6 +3031,12 @@ pub struct ComAtprotoIdentityResolvehandle {
   pub did: String,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoIdentitySignplcoperation {
+  pub operation: Record,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComAtprotoLabelQuerylabels {
@@ -2521,6 +3044,27 @@ pub struct ComAtprotoLabelQuerylabels {
   pub cursor: Option<String>,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoModerationCreatereport {
+  pub id: i64,
+  #[serde(rename = "reasonType")]
+  pub reason_type: ComAtprotoModerationDefsReasontype,
+  pub subject: ComAtprotoModerationCreatereportMainOutputSubject,
+  #[serde(rename = "reportedBy")]
+  pub reported_by: String,
+  #[serde(rename = "createdAt")]
+  pub created_at: DateTime<Utc>,
+  pub reason: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoRepoCreaterecord {
+  pub uri: String,
+  pub cid: CidString,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComAtprotoRepoDescriberepo {
@@ -2555,6 +3099,19 @@ pub struct ComAtprotoRepoListrecords {
   pub cursor: Option<String>,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoRepoPutrecord {
+  pub uri: String,
+  pub cid: CidString,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoRepoUploadblob {
+  pub blob: Blob,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComAtprotoServerCheckaccountstatus {
@@ -2577,6 +3134,47 @@ pub struct ComAtprotoServerCheckaccountstatus {
   pub imported_blobs: i64,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoServerCreateaccount {
+  #[serde(rename = "accessJwt")]
+  pub access_jwt: String,
+  #[serde(rename = "refreshJwt")]
+  pub refresh_jwt: String,
+  pub handle: String,
+  pub did: String,
+  #[serde(rename = "didDoc")]
+  pub did_doc: Option<DidDoc>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoServerCreateinvitecode {
+  pub code: String,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoServerCreateinvitecodes {
+  pub codes: Vec<ComAtprotoServerCreateinvitecodesAccountcodes>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoServerCreatesession {
+  #[serde(rename = "accessJwt")]
+  pub access_jwt: String,
+  #[serde(rename = "refreshJwt")]
+  pub refresh_jwt: String,
+  pub handle: String,
+  pub did: String,
+  #[serde(rename = "didDoc")]
+  pub did_doc: Option<DidDoc>,
+  pub email: Option<String>,
+  #[serde(rename = "emailConfirmed")]
+  pub email_confirmed: Option<bool>,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComAtprotoServerDescribeserver {
@@ -2620,6 +3218,33 @@ pub struct ComAtprotoServerListapppasswords {
   pub passwords: Vec<ComAtprotoServerListapppasswordsApppassword>,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoServerRefreshsession {
+  #[serde(rename = "accessJwt")]
+  pub access_jwt: String,
+  #[serde(rename = "refreshJwt")]
+  pub refresh_jwt: String,
+  pub handle: String,
+  pub did: String,
+  #[serde(rename = "didDoc")]
+  pub did_doc: Option<DidDoc>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoServerRequestemailupdate {
+  #[serde(rename = "tokenRequired")]
+  pub token_required: bool,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoServerReservesigningkey {
+  #[serde(rename = "signingKey")]
+  pub signing_key: String,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComAtprotoSyncGethead {
@@ -2633,6 +3258,15 @@ pub struct ComAtprotoSyncGetlatestcommit {
   pub rev: String,
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComAtprotoSyncGetrepostatus {
+  pub did: String,
+  pub active: bool,
+  pub status: Option<String>,
+  pub rev: Option<String>,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComAtprotoSyncListblobs {
@@ -2663,173 +3297,52 @@ pub struct ComAtprotoTempFetchlabels {
   pub labels: Vec<ComAtprotoLabelDefsLabel>,
 }
 
-#[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoAdminSendemail {
-  pub sent: bool,
+#[serde(tag = "$type")]
+pub enum AppBskyActorDefsPreferencesItem {
+  #[serde(rename = "app.bsky.actor.defs#adultContentPref")]
+  AppBskyActorDefsAdultcontentpref(Box<AppBskyActorDefsAdultcontentpref>),
+  #[serde(rename = "app.bsky.actor.defs#contentLabelPref")]
+  AppBskyActorDefsContentlabelpref(Box<AppBskyActorDefsContentlabelpref>),
+  #[serde(rename = "app.bsky.actor.defs#savedFeedsPref")]
+  AppBskyActorDefsSavedfeedspref(Box<AppBskyActorDefsSavedfeedspref>),
+  #[serde(rename = "app.bsky.actor.defs#personalDetailsPref")]
+  AppBskyActorDefsPersonaldetailspref(Box<AppBskyActorDefsPersonaldetailspref>),
+  #[serde(rename = "app.bsky.actor.defs#feedViewPref")]
+  AppBskyActorDefsFeedviewpref(Box<AppBskyActorDefsFeedviewpref>),
+  #[serde(rename = "app.bsky.actor.defs#threadViewPref")]
+  AppBskyActorDefsThreadviewpref(Box<AppBskyActorDefsThreadviewpref>),
+  #[serde(rename = "app.bsky.actor.defs#interestsPref")]
+  AppBskyActorDefsInterestspref(Box<AppBskyActorDefsInterestspref>),
+  #[serde(rename = "app.bsky.actor.defs#mutedWordsPref")]
+  AppBskyActorDefsMutedwordspref(Box<AppBskyActorDefsMutedwordspref>),
+  #[serde(rename = "app.bsky.actor.defs#hiddenPostsPref")]
+  AppBskyActorDefsHiddenpostspref(Box<AppBskyActorDefsHiddenpostspref>),
+
+  #[serde(other)]
+  Other,
 }
 
-#[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoAdminUpdatesubjectstatus {
-  pub subject: ComAtprotoAdminUpdatesubjectstatusMainOutputSubject,
-  pub takedown: Option<ComAtprotoAdminDefsStatusattr>,
+impl Default for AppBskyActorDefsPreferencesItem {
+  fn default() -> Self {
+    Self::AppBskyActorDefsAdultcontentpref(Box::default())
+  }
 }
 
-#[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoIdentitySignplcoperation {
-  pub operation: Record,
-}
-
-#[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoModerationCreatereport {
-  pub id: i64,
-  #[serde(rename = "reasonType")]
-  pub reason_type: ComAtprotoModerationDefsReasontype,
-  pub subject: ComAtprotoModerationCreatereportMainOutputSubject,
-  #[serde(rename = "reportedBy")]
-  pub reported_by: String,
-  #[serde(rename = "createdAt")]
-  pub created_at: DateTime<Utc>,
-  pub reason: Option<String>,
-}
-
-#[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoRepoCreaterecord {
-  pub uri: String,
-  pub cid: CidString,
-}
-
-#[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoRepoPutrecord {
-  pub uri: String,
-  pub cid: CidString,
-}
-
-#[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoRepoUploadblob {
-  pub blob: Blob,
-}
-
-#[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoServerCreateaccount {
-  #[serde(rename = "accessJwt")]
-  pub access_jwt: String,
-  #[serde(rename = "refreshJwt")]
-  pub refresh_jwt: String,
-  pub handle: String,
-  pub did: String,
-  #[serde(rename = "didDoc")]
-  pub did_doc: Option<DidDoc>,
-}
-
-#[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoServerCreateinvitecode {
-  pub code: String,
-}
-
-#[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoServerCreateinvitecodes {
-  pub codes: Vec<ComAtprotoServerCreateinvitecodesAccountcodes>,
-}
-
-#[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoServerCreatesession {
-  #[serde(rename = "accessJwt")]
-  pub access_jwt: String,
-  #[serde(rename = "refreshJwt")]
-  pub refresh_jwt: String,
-  pub handle: String,
-  pub did: String,
-  #[serde(rename = "didDoc")]
-  pub did_doc: Option<DidDoc>,
-  pub email: Option<String>,
-  #[serde(rename = "emailConfirmed")]
-  pub email_confirmed: Option<bool>,
-}
-
-#[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoServerRefreshsession {
-  #[serde(rename = "accessJwt")]
-  pub access_jwt: String,
-  #[serde(rename = "refreshJwt")]
-  pub refresh_jwt: String,
-  pub handle: String,
-  pub did: String,
-  #[serde(rename = "didDoc")]
-  pub did_doc: Option<DidDoc>,
-}
-
-#[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoServerRequestemailupdate {
-  #[serde(rename = "tokenRequired")]
-  pub token_required: bool,
-}
-
-#[skip_serializing_none]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComAtprotoServerReservesigningkey {
-  #[serde(rename = "signingKey")]
-  pub signing_key: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "$type")]
-pub enum AppBskyActorDefsPreferencesItem {
-  #[serde(rename = "app.bsky.actor.defs#adultContentPref")]
-  AppBskyActorDefsAdultcontentpref(Box<AppBskyActorDefsAdultcontentpref>),
-  #[serde(rename = "app.bsky.actor.defs#contentLabelPref")]
-  AppBskyActorDefsContentlabelpref(Box<AppBskyActorDefsContentlabelpref>),
-  #[serde(rename = "app.bsky.actor.defs#savedFeedsPref")]
-  AppBskyActorDefsSavedfeedspref(Box<AppBskyActorDefsSavedfeedspref>),
-  #[serde(rename = "app.bsky.actor.defs#personalDetailsPref")]
-  AppBskyActorDefsPersonaldetailspref(Box<AppBskyActorDefsPersonaldetailspref>),
-  #[serde(rename = "app.bsky.actor.defs#feedViewPref")]
-  AppBskyActorDefsFeedviewpref(Box<AppBskyActorDefsFeedviewpref>),
-  #[serde(rename = "app.bsky.actor.defs#threadViewPref")]
-  AppBskyActorDefsThreadviewpref(Box<AppBskyActorDefsThreadviewpref>),
-  #[serde(rename = "app.bsky.actor.defs#interestsPref")]
-  AppBskyActorDefsInterestspref(Box<AppBskyActorDefsInterestspref>),
-  #[serde(rename = "app.bsky.actor.defs#mutedWordsPref")]
-  AppBskyActorDefsMutedwordspref(Box<AppBskyActorDefsMutedwordspref>),
-  #[serde(rename = "app.bsky.actor.defs#hiddenPostsPref")]
-  AppBskyActorDefsHiddenpostspref(Box<AppBskyActorDefsHiddenpostspref>),
-
-  #[serde(other)]
-  Other,
-}
-
-impl Default for AppBskyActorDefsPreferencesItem {
-  fn default() -> Self {
-    Self::AppBskyActorDefsAdultcontentpref(Box::new(AppBskyActorDefsAdultcontentpref::default()))
-  }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "$type")]
-pub enum AppBskyActorProfileMainLabels {
-  #[serde(rename = "com.atproto.label.defs#selfLabels")]
-  ComAtprotoLabelDefsSelflabels(Box<ComAtprotoLabelDefsSelflabels>),
-
-  #[serde(other)]
-  Other,
-}
-
-impl Default for AppBskyActorProfileMainLabels {
-  fn default() -> Self {
-    Self::ComAtprotoLabelDefsSelflabels(Box::new(ComAtprotoLabelDefsSelflabels::default()))
-  }
+#[serde(tag = "$type")]
+pub enum AppBskyActorProfileMainLabels {
+  #[serde(rename = "com.atproto.label.defs#selfLabels")]
+  ComAtprotoLabelDefsSelflabels(Box<ComAtprotoLabelDefsSelflabels>),
+
+  #[serde(other)]
+  Other,
+}
+
+impl Default for AppBskyActorProfileMainLabels {
+  fn default() -> Self {
+    Self::ComAtprotoLabelDefsSelflabels(Box::default())
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -2852,7 +3365,7 @@ pub enum AppBskyEmbedRecordViewRecord {
 
 impl Default for AppBskyEmbedRecordViewRecord {
   fn default() -> Self {
-    Self::AppBskyEmbedRecordViewrecord(Box::new(AppBskyEmbedRecordViewrecord::default()))
+    Self::AppBskyEmbedRecordViewrecord(Box::default())
   }
 }
 
@@ -2874,7 +3387,7 @@ pub enum AppBskyEmbedRecordViewrecordEmbedsItem {
 
 impl Default for AppBskyEmbedRecordViewrecordEmbedsItem {
   fn default() -> Self {
-    Self::AppBskyEmbedImagesView(Box::new(AppBskyEmbedImagesView::default()))
+    Self::AppBskyEmbedImagesView(Box::default())
   }
 }
 
@@ -2892,7 +3405,7 @@ pub enum AppBskyEmbedRecordwithmediaMainMedia {
 
 impl Default for AppBskyEmbedRecordwithmediaMainMedia {
   fn default() -> Self {
-    Self::AppBskyEmbedImages(Box::new(AppBskyEmbedImages::default()))
+    Self::AppBskyEmbedImages(Box::default())
   }
 }
 
@@ -2910,7 +3423,7 @@ pub enum AppBskyEmbedRecordwithmediaViewMedia {
 
 impl Default for AppBskyEmbedRecordwithmediaViewMedia {
   fn default() -> Self {
-    Self::AppBskyEmbedImagesView(Box::new(AppBskyEmbedImagesView::default()))
+    Self::AppBskyEmbedImagesView(Box::default())
   }
 }
 
@@ -2932,7 +3445,7 @@ pub enum AppBskyFeedDefsPostviewEmbed {
 
 impl Default for AppBskyFeedDefsPostviewEmbed {
   fn default() -> Self {
-    Self::AppBskyEmbedImagesView(Box::new(AppBskyEmbedImagesView::default()))
+    Self::AppBskyEmbedImagesView(Box::default())
   }
 }
 
@@ -2948,7 +3461,7 @@ pub enum AppBskyFeedDefsFeedviewpostReason {
 
 impl Default for AppBskyFeedDefsFeedviewpostReason {
   fn default() -> Self {
-    Self::AppBskyFeedDefsReasonrepost(Box::new(AppBskyFeedDefsReasonrepost::default()))
+    Self::AppBskyFeedDefsReasonrepost(Box::default())
   }
 }
 
@@ -2968,7 +3481,7 @@ pub enum AppBskyFeedDefsReplyrefRoot {
 
 impl Default for AppBskyFeedDefsReplyrefRoot {
   fn default() -> Self {
-    Self::AppBskyFeedDefsPostview(Box::new(AppBskyFeedDefsPostview::default()))
+    Self::AppBskyFeedDefsPostview(Box::default())
   }
 }
 
@@ -2988,7 +3501,7 @@ pub enum AppBskyFeedDefsReplyrefParent {
 
 impl Default for AppBskyFeedDefsReplyrefParent {
   fn default() -> Self {
-    Self::AppBskyFeedDefsPostview(Box::new(AppBskyFeedDefsPostview::default()))
+    Self::AppBskyFeedDefsPostview(Box::default())
   }
 }
 
@@ -3008,7 +3521,7 @@ pub enum AppBskyFeedDefsThreadviewpostParent {
 
 impl Default for AppBskyFeedDefsThreadviewpostParent {
   fn default() -> Self {
-    Self::AppBskyFeedDefsThreadviewpost(Box::new(AppBskyFeedDefsThreadviewpost::default()))
+    Self::AppBskyFeedDefsThreadviewpost(Box::default())
   }
 }
 
@@ -3028,7 +3541,7 @@ pub enum AppBskyFeedDefsThreadviewpostRepliesItem {
 
 impl Default for AppBskyFeedDefsThreadviewpostRepliesItem {
   fn default() -> Self {
-    Self::AppBskyFeedDefsThreadviewpost(Box::new(AppBskyFeedDefsThreadviewpost::default()))
+    Self::AppBskyFeedDefsThreadviewpost(Box::default())
   }
 }
 
@@ -3044,9 +3557,7 @@ pub enum AppBskyFeedDefsSkeletonfeedpostReason {
 
 impl Default for AppBskyFeedDefsSkeletonfeedpostReason {
   fn default() -> Self {
-    Self::AppBskyFeedDefsSkeletonreasonrepost(Box::new(
-      AppBskyFeedDefsSkeletonreasonrepost::default(),
-    ))
+    Self::AppBskyFeedDefsSkeletonreasonrepost(Box::default())
   }
 }
 
@@ -3062,27 +3573,7 @@ pub enum AppBskyFeedGeneratorMainLabels {
 
 impl Default for AppBskyFeedGeneratorMainLabels {
   fn default() -> Self {
-    Self::ComAtprotoLabelDefsSelflabels(Box::new(ComAtprotoLabelDefsSelflabels::default()))
-  }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "$type")]
-pub enum AppBskyFeedGetpostthreadMainOutputThread {
-  #[serde(rename = "app.bsky.feed.defs#threadViewPost")]
-  AppBskyFeedDefsThreadviewpost(Box<AppBskyFeedDefsThreadviewpost>),
-  #[serde(rename = "app.bsky.feed.defs#notFoundPost")]
-  AppBskyFeedDefsNotfoundpost(Box<AppBskyFeedDefsNotfoundpost>),
-  #[serde(rename = "app.bsky.feed.defs#blockedPost")]
-  AppBskyFeedDefsBlockedpost(Box<AppBskyFeedDefsBlockedpost>),
-
-  #[serde(other)]
-  Other,
-}
-
-impl Default for AppBskyFeedGetpostthreadMainOutputThread {
-  fn default() -> Self {
-    Self::AppBskyFeedDefsThreadviewpost(Box::new(AppBskyFeedDefsThreadviewpost::default()))
+    Self::ComAtprotoLabelDefsSelflabels(Box::default())
   }
 }
 
@@ -3104,7 +3595,7 @@ pub enum AppBskyFeedPostMainEmbed {
 
 impl Default for AppBskyFeedPostMainEmbed {
   fn default() -> Self {
-    Self::AppBskyEmbedImages(Box::new(AppBskyEmbedImages::default()))
+    Self::AppBskyEmbedImages(Box::default())
   }
 }
 
@@ -3120,7 +3611,7 @@ pub enum AppBskyFeedPostMainLabels {
 
 impl Default for AppBskyFeedPostMainLabels {
   fn default() -> Self {
-    Self::ComAtprotoLabelDefsSelflabels(Box::new(ComAtprotoLabelDefsSelflabels::default()))
+    Self::ComAtprotoLabelDefsSelflabels(Box::default())
   }
 }
 
@@ -3140,25 +3631,7 @@ pub enum AppBskyFeedThreadgateMainAllowItem {
 
 impl Default for AppBskyFeedThreadgateMainAllowItem {
   fn default() -> Self {
-    Self::AppBskyFeedThreadgateMentionrule(Box::new(AppBskyFeedThreadgateMentionrule::default()))
-  }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "$type")]
-pub enum AppBskyGraphGetrelationshipsMainOutputRelationshipsItem {
-  #[serde(rename = "app.bsky.graph.defs#relationship")]
-  AppBskyGraphDefsRelationship(Box<AppBskyGraphDefsRelationship>),
-  #[serde(rename = "app.bsky.graph.defs#notFoundActor")]
-  AppBskyGraphDefsNotfoundactor(Box<AppBskyGraphDefsNotfoundactor>),
-
-  #[serde(other)]
-  Other,
-}
-
-impl Default for AppBskyGraphGetrelationshipsMainOutputRelationshipsItem {
-  fn default() -> Self {
-    Self::AppBskyGraphDefsRelationship(Box::new(AppBskyGraphDefsRelationship::default()))
+    Self::AppBskyFeedThreadgateMentionrule(Box::default())
   }
 }
 
@@ -3174,7 +3647,7 @@ pub enum AppBskyGraphListMainLabels {
 
 impl Default for AppBskyGraphListMainLabels {
   fn default() -> Self {
-    Self::ComAtprotoLabelDefsSelflabels(Box::new(ComAtprotoLabelDefsSelflabels::default()))
+    Self::ComAtprotoLabelDefsSelflabels(Box::default())
   }
 }
 
@@ -3194,7 +3667,93 @@ pub enum AppBskyRichtextFacetMainFeaturesItem {
 
 impl Default for AppBskyRichtextFacetMainFeaturesItem {
   fn default() -> Self {
-    Self::AppBskyRichtextFacetMention(Box::new(AppBskyRichtextFacetMention::default()))
+    Self::AppBskyRichtextFacetMention(Box::default())
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "$type")]
+pub enum ChatBskyConvoDefsMessageinputEmbed {
+  #[serde(rename = "app.bsky.embed.record")]
+  AppBskyEmbedRecord(Box<AppBskyEmbedRecord>),
+
+  #[serde(other)]
+  Other,
+}
+
+impl Default for ChatBskyConvoDefsMessageinputEmbed {
+  fn default() -> Self {
+    Self::AppBskyEmbedRecord(Box::default())
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "$type")]
+pub enum ChatBskyConvoDefsMessageviewEmbed {
+  #[serde(rename = "app.bsky.embed.record#view")]
+  AppBskyEmbedRecordView(Box<AppBskyEmbedRecordView>),
+
+  #[serde(other)]
+  Other,
+}
+
+impl Default for ChatBskyConvoDefsMessageviewEmbed {
+  fn default() -> Self {
+    Self::AppBskyEmbedRecordView(Box::default())
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "$type")]
+pub enum ChatBskyConvoDefsConvoviewLastmessage {
+  #[serde(rename = "chat.bsky.convo.defs#messageView")]
+  ChatBskyConvoDefsMessageview(Box<ChatBskyConvoDefsMessageview>),
+  #[serde(rename = "chat.bsky.convo.defs#deletedMessageView")]
+  ChatBskyConvoDefsDeletedmessageview(Box<ChatBskyConvoDefsDeletedmessageview>),
+
+  #[serde(other)]
+  Other,
+}
+
+impl Default for ChatBskyConvoDefsConvoviewLastmessage {
+  fn default() -> Self {
+    Self::ChatBskyConvoDefsMessageview(Box::default())
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "$type")]
+pub enum ChatBskyConvoDefsLogcreatemessageMessage {
+  #[serde(rename = "chat.bsky.convo.defs#messageView")]
+  ChatBskyConvoDefsMessageview(Box<ChatBskyConvoDefsMessageview>),
+  #[serde(rename = "chat.bsky.convo.defs#deletedMessageView")]
+  ChatBskyConvoDefsDeletedmessageview(Box<ChatBskyConvoDefsDeletedmessageview>),
+
+  #[serde(other)]
+  Other,
+}
+
+impl Default for ChatBskyConvoDefsLogcreatemessageMessage {
+  fn default() -> Self {
+    Self::ChatBskyConvoDefsMessageview(Box::default())
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "$type")]
+pub enum ChatBskyConvoDefsLogdeletemessageMessage {
+  #[serde(rename = "chat.bsky.convo.defs#messageView")]
+  ChatBskyConvoDefsMessageview(Box<ChatBskyConvoDefsMessageview>),
+  #[serde(rename = "chat.bsky.convo.defs#deletedMessageView")]
+  ChatBskyConvoDefsDeletedmessageview(Box<ChatBskyConvoDefsDeletedmessageview>),
+
+  #[serde(other)]
+  Other,
+}
+
+impl Default for ChatBskyConvoDefsLogdeletemessageMessage {
+  fn default() -> Self {
+    Self::ChatBskyConvoDefsMessageview(Box::default())
   }
 }
 
@@ -3228,9 +3787,7 @@ pub enum ComAtprotoAdminDefsModeventviewEvent {
 
 impl Default for ComAtprotoAdminDefsModeventviewEvent {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsModeventtakedown(Box::new(
-      ComAtprotoAdminDefsModeventtakedown::default(),
-    ))
+    Self::ComAtprotoAdminDefsModeventtakedown(Box::default())
   }
 }
 
@@ -3248,7 +3805,7 @@ pub enum ComAtprotoAdminDefsModeventviewSubject {
 
 impl Default for ComAtprotoAdminDefsModeventviewSubject {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsReporef(Box::new(ComAtprotoAdminDefsReporef::default()))
+    Self::ComAtprotoAdminDefsReporef(Box::default())
   }
 }
 
@@ -3282,9 +3839,7 @@ pub enum ComAtprotoAdminDefsModeventviewdetailEvent {
 
 impl Default for ComAtprotoAdminDefsModeventviewdetailEvent {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsModeventtakedown(Box::new(
-      ComAtprotoAdminDefsModeventtakedown::default(),
-    ))
+    Self::ComAtprotoAdminDefsModeventtakedown(Box::default())
   }
 }
 
@@ -3306,7 +3861,7 @@ pub enum ComAtprotoAdminDefsModeventviewdetailSubject {
 
 impl Default for ComAtprotoAdminDefsModeventviewdetailSubject {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsRepoview(Box::new(ComAtprotoAdminDefsRepoview::default()))
+    Self::ComAtprotoAdminDefsRepoview(Box::default())
   }
 }
 
@@ -3324,7 +3879,7 @@ pub enum ComAtprotoAdminDefsReportviewSubject {
 
 impl Default for ComAtprotoAdminDefsReportviewSubject {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsReporef(Box::new(ComAtprotoAdminDefsReporef::default()))
+    Self::ComAtprotoAdminDefsReporef(Box::default())
   }
 }
 
@@ -3342,7 +3897,7 @@ pub enum ComAtprotoAdminDefsSubjectstatusviewSubject {
 
 impl Default for ComAtprotoAdminDefsSubjectstatusviewSubject {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsReporef(Box::new(ComAtprotoAdminDefsReporef::default()))
+    Self::ComAtprotoAdminDefsReporef(Box::default())
   }
 }
 
@@ -3364,7 +3919,7 @@ pub enum ComAtprotoAdminDefsReportviewdetailSubject {
 
 impl Default for ComAtprotoAdminDefsReportviewdetailSubject {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsRepoview(Box::new(ComAtprotoAdminDefsRepoview::default()))
+    Self::ComAtprotoAdminDefsRepoview(Box::default())
   }
 }
 
@@ -3382,63 +3937,85 @@ pub enum ComAtprotoAdminDefsBlobviewDetails {
 
 impl Default for ComAtprotoAdminDefsBlobviewDetails {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsImagedetails(Box::new(ComAtprotoAdminDefsImagedetails::default()))
+    Self::ComAtprotoAdminDefsImagedetails(Box::default())
   }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "$type")]
-pub enum ComAtprotoAdminEmitmoderationeventMainInputEvent {
-  #[serde(rename = "com.atproto.admin.defs#modEventTakedown")]
-  ComAtprotoAdminDefsModeventtakedown(Box<ComAtprotoAdminDefsModeventtakedown>),
-  #[serde(rename = "com.atproto.admin.defs#modEventAcknowledge")]
-  ComAtprotoAdminDefsModeventacknowledge(Box<ComAtprotoAdminDefsModeventacknowledge>),
-  #[serde(rename = "com.atproto.admin.defs#modEventEscalate")]
-  ComAtprotoAdminDefsModeventescalate(Box<ComAtprotoAdminDefsModeventescalate>),
-  #[serde(rename = "com.atproto.admin.defs#modEventComment")]
-  ComAtprotoAdminDefsModeventcomment(Box<ComAtprotoAdminDefsModeventcomment>),
-  #[serde(rename = "com.atproto.admin.defs#modEventLabel")]
-  ComAtprotoAdminDefsModeventlabel(Box<ComAtprotoAdminDefsModeventlabel>),
-  #[serde(rename = "com.atproto.admin.defs#modEventReport")]
-  ComAtprotoAdminDefsModeventreport(Box<ComAtprotoAdminDefsModeventreport>),
-  #[serde(rename = "com.atproto.admin.defs#modEventMute")]
-  ComAtprotoAdminDefsModeventmute(Box<ComAtprotoAdminDefsModeventmute>),
-  #[serde(rename = "com.atproto.admin.defs#modEventReverseTakedown")]
-  ComAtprotoAdminDefsModeventreversetakedown(Box<ComAtprotoAdminDefsModeventreversetakedown>),
-  #[serde(rename = "com.atproto.admin.defs#modEventUnmute")]
-  ComAtprotoAdminDefsModeventunmute(Box<ComAtprotoAdminDefsModeventunmute>),
-  #[serde(rename = "com.atproto.admin.defs#modEventEmail")]
-  ComAtprotoAdminDefsModeventemail(Box<ComAtprotoAdminDefsModeventemail>),
-  #[serde(rename = "com.atproto.admin.defs#modEventTag")]
-  ComAtprotoAdminDefsModeventtag(Box<ComAtprotoAdminDefsModeventtag>),
+pub enum AppBskyFeedGetpostthreadMainOutputThread {
+  #[serde(rename = "app.bsky.feed.defs#threadViewPost")]
+  AppBskyFeedDefsThreadviewpost(Box<AppBskyFeedDefsThreadviewpost>),
+  #[serde(rename = "app.bsky.feed.defs#notFoundPost")]
+  AppBskyFeedDefsNotfoundpost(Box<AppBskyFeedDefsNotfoundpost>),
+  #[serde(rename = "app.bsky.feed.defs#blockedPost")]
+  AppBskyFeedDefsBlockedpost(Box<AppBskyFeedDefsBlockedpost>),
 
   #[serde(other)]
   Other,
 }
 
-impl Default for ComAtprotoAdminEmitmoderationeventMainInputEvent {
+impl Default for AppBskyFeedGetpostthreadMainOutputThread {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsModeventtakedown(Box::new(
-      ComAtprotoAdminDefsModeventtakedown::default(),
-    ))
+    Self::AppBskyFeedDefsThreadviewpost(Box::default())
   }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "$type")]
-pub enum ComAtprotoAdminEmitmoderationeventMainInputSubject {
-  #[serde(rename = "com.atproto.admin.defs#repoRef")]
-  ComAtprotoAdminDefsReporef(Box<ComAtprotoAdminDefsReporef>),
-  #[serde(rename = "com.atproto.repo.strongRef")]
-  ComAtprotoRepoStrongref(Box<ComAtprotoRepoStrongref>),
+pub enum AppBskyGraphGetrelationshipsMainOutputRelationshipsItem {
+  #[serde(rename = "app.bsky.graph.defs#relationship")]
+  AppBskyGraphDefsRelationship(Box<AppBskyGraphDefsRelationship>),
+  #[serde(rename = "app.bsky.graph.defs#notFoundActor")]
+  AppBskyGraphDefsNotfoundactor(Box<AppBskyGraphDefsNotfoundactor>),
 
   #[serde(other)]
   Other,
 }
 
-impl Default for ComAtprotoAdminEmitmoderationeventMainInputSubject {
+impl Default for AppBskyGraphGetrelationshipsMainOutputRelationshipsItem {
+  fn default() -> Self {
+    Self::AppBskyGraphDefsRelationship(Box::default())
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "$type")]
+pub enum ChatBskyConvoGetlogMainOutputLogsItem {
+  #[serde(rename = "chat.bsky.convo.defs#logBeginConvo")]
+  ChatBskyConvoDefsLogbeginconvo(Box<ChatBskyConvoDefsLogbeginconvo>),
+  #[serde(rename = "chat.bsky.convo.defs#logLeaveConvo")]
+  ChatBskyConvoDefsLogleaveconvo(Box<ChatBskyConvoDefsLogleaveconvo>),
+  #[serde(rename = "chat.bsky.convo.defs#logCreateMessage")]
+  ChatBskyConvoDefsLogcreatemessage(Box<ChatBskyConvoDefsLogcreatemessage>),
+  #[serde(rename = "chat.bsky.convo.defs#logDeleteMessage")]
+  ChatBskyConvoDefsLogdeletemessage(Box<ChatBskyConvoDefsLogdeletemessage>),
+
+  #[serde(other)]
+  Other,
+}
+
+impl Default for ChatBskyConvoGetlogMainOutputLogsItem {
+  fn default() -> Self {
+    Self::ChatBskyConvoDefsLogbeginconvo(Box::default())
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "$type")]
+pub enum ChatBskyConvoGetmessagesMainOutputMessagesItem {
+  #[serde(rename = "chat.bsky.convo.defs#messageView")]
+  ChatBskyConvoDefsMessageview(Box<ChatBskyConvoDefsMessageview>),
+  #[serde(rename = "chat.bsky.convo.defs#deletedMessageView")]
+  ChatBskyConvoDefsDeletedmessageview(Box<ChatBskyConvoDefsDeletedmessageview>),
+
+  #[serde(other)]
+  Other,
+}
+
+impl Default for ChatBskyConvoGetmessagesMainOutputMessagesItem {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsReporef(Box::new(ComAtprotoAdminDefsReporef::default()))
+    Self::ChatBskyConvoDefsMessageview(Box::default())
   }
 }
 
@@ -3458,13 +4035,13 @@ pub enum ComAtprotoAdminGetsubjectstatusMainOutputSubject {
 
 impl Default for ComAtprotoAdminGetsubjectstatusMainOutputSubject {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsReporef(Box::new(ComAtprotoAdminDefsReporef::default()))
+    Self::ComAtprotoAdminDefsReporef(Box::default())
   }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "$type")]
-pub enum ComAtprotoAdminUpdatesubjectstatusMainInputSubject {
+pub enum ComAtprotoAdminUpdatesubjectstatusMainOutputSubject {
   #[serde(rename = "com.atproto.admin.defs#repoRef")]
   ComAtprotoAdminDefsReporef(Box<ComAtprotoAdminDefsReporef>),
   #[serde(rename = "com.atproto.repo.strongRef")]
@@ -3476,55 +4053,69 @@ pub enum ComAtprotoAdminUpdatesubjectstatusMainInputSubject {
   Other,
 }
 
-impl Default for ComAtprotoAdminUpdatesubjectstatusMainInputSubject {
+impl Default for ComAtprotoAdminUpdatesubjectstatusMainOutputSubject {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsReporef(Box::new(ComAtprotoAdminDefsReporef::default()))
+    Self::ComAtprotoAdminDefsReporef(Box::default())
   }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "$type")]
-pub enum ComAtprotoAdminUpdatesubjectstatusMainOutputSubject {
+pub enum ComAtprotoModerationCreatereportMainOutputSubject {
   #[serde(rename = "com.atproto.admin.defs#repoRef")]
   ComAtprotoAdminDefsReporef(Box<ComAtprotoAdminDefsReporef>),
   #[serde(rename = "com.atproto.repo.strongRef")]
   ComAtprotoRepoStrongref(Box<ComAtprotoRepoStrongref>),
-  #[serde(rename = "com.atproto.admin.defs#repoBlobRef")]
-  ComAtprotoAdminDefsRepoblobref(Box<ComAtprotoAdminDefsRepoblobref>),
 
   #[serde(other)]
   Other,
 }
 
-impl Default for ComAtprotoAdminUpdatesubjectstatusMainOutputSubject {
+impl Default for ComAtprotoModerationCreatereportMainOutputSubject {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsReporef(Box::new(ComAtprotoAdminDefsReporef::default()))
+    Self::ComAtprotoAdminDefsReporef(Box::default())
   }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "$type")]
-pub enum ComAtprotoLabelSubscribelabelsMainMessage {
-  #[serde(rename = "com.atproto.label.subscribeLabels#labels")]
-  ComAtprotoLabelSubscribelabelsLabels(Box<ComAtprotoLabelSubscribelabelsLabels>),
-  #[serde(rename = "com.atproto.label.subscribeLabels#info")]
-  ComAtprotoLabelSubscribelabelsInfo(Box<ComAtprotoLabelSubscribelabelsInfo>),
+pub enum ComAtprotoAdminEmitmoderationeventMainInputEvent {
+  #[serde(rename = "com.atproto.admin.defs#modEventTakedown")]
+  ComAtprotoAdminDefsModeventtakedown(Box<ComAtprotoAdminDefsModeventtakedown>),
+  #[serde(rename = "com.atproto.admin.defs#modEventAcknowledge")]
+  ComAtprotoAdminDefsModeventacknowledge(Box<ComAtprotoAdminDefsModeventacknowledge>),
+  #[serde(rename = "com.atproto.admin.defs#modEventEscalate")]
+  ComAtprotoAdminDefsModeventescalate(Box<ComAtprotoAdminDefsModeventescalate>),
+  #[serde(rename = "com.atproto.admin.defs#modEventComment")]
+  ComAtprotoAdminDefsModeventcomment(Box<ComAtprotoAdminDefsModeventcomment>),
+  #[serde(rename = "com.atproto.admin.defs#modEventLabel")]
+  ComAtprotoAdminDefsModeventlabel(Box<ComAtprotoAdminDefsModeventlabel>),
+  #[serde(rename = "com.atproto.admin.defs#modEventReport")]
+  ComAtprotoAdminDefsModeventreport(Box<ComAtprotoAdminDefsModeventreport>),
+  #[serde(rename = "com.atproto.admin.defs#modEventMute")]
+  ComAtprotoAdminDefsModeventmute(Box<ComAtprotoAdminDefsModeventmute>),
+  #[serde(rename = "com.atproto.admin.defs#modEventReverseTakedown")]
+  ComAtprotoAdminDefsModeventreversetakedown(Box<ComAtprotoAdminDefsModeventreversetakedown>),
+  #[serde(rename = "com.atproto.admin.defs#modEventUnmute")]
+  ComAtprotoAdminDefsModeventunmute(Box<ComAtprotoAdminDefsModeventunmute>),
+  #[serde(rename = "com.atproto.admin.defs#modEventEmail")]
+  ComAtprotoAdminDefsModeventemail(Box<ComAtprotoAdminDefsModeventemail>),
+  #[serde(rename = "com.atproto.admin.defs#modEventTag")]
+  ComAtprotoAdminDefsModeventtag(Box<ComAtprotoAdminDefsModeventtag>),
 
   #[serde(other)]
   Other,
 }
 
-impl Default for ComAtprotoLabelSubscribelabelsMainMessage {
+impl Default for ComAtprotoAdminEmitmoderationeventMainInputEvent {
   fn default() -> Self {
-    Self::ComAtprotoLabelSubscribelabelsLabels(Box::new(
-      ComAtprotoLabelSubscribelabelsLabels::default(),
-    ))
+    Self::ComAtprotoAdminDefsModeventtakedown(Box::default())
   }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "$type")]
-pub enum ComAtprotoModerationCreatereportMainInputSubject {
+pub enum ComAtprotoAdminEmitmoderationeventMainInputSubject {
   #[serde(rename = "com.atproto.admin.defs#repoRef")]
   ComAtprotoAdminDefsReporef(Box<ComAtprotoAdminDefsReporef>),
   #[serde(rename = "com.atproto.repo.strongRef")]
@@ -3534,27 +4125,65 @@ pub enum ComAtprotoModerationCreatereportMainInputSubject {
   Other,
 }
 
-impl Default for ComAtprotoModerationCreatereportMainInputSubject {
+impl Default for ComAtprotoAdminEmitmoderationeventMainInputSubject {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsReporef(Box::new(ComAtprotoAdminDefsReporef::default()))
+    Self::ComAtprotoAdminDefsReporef(Box::default())
   }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "$type")]
-pub enum ComAtprotoModerationCreatereportMainOutputSubject {
+pub enum ComAtprotoAdminUpdatesubjectstatusMainInputSubject {
   #[serde(rename = "com.atproto.admin.defs#repoRef")]
   ComAtprotoAdminDefsReporef(Box<ComAtprotoAdminDefsReporef>),
   #[serde(rename = "com.atproto.repo.strongRef")]
   ComAtprotoRepoStrongref(Box<ComAtprotoRepoStrongref>),
+  #[serde(rename = "com.atproto.admin.defs#repoBlobRef")]
+  ComAtprotoAdminDefsRepoblobref(Box<ComAtprotoAdminDefsRepoblobref>),
 
   #[serde(other)]
   Other,
 }
 
-impl Default for ComAtprotoModerationCreatereportMainOutputSubject {
+impl Default for ComAtprotoAdminUpdatesubjectstatusMainInputSubject {
+  fn default() -> Self {
+    Self::ComAtprotoAdminDefsReporef(Box::default())
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "$type")]
+pub enum ComAtprotoLabelSubscribelabelsMainMessage {
+  #[serde(rename = "com.atproto.label.subscribeLabels#labels")]
+  ComAtprotoLabelSubscribelabelsLabels(Box<ComAtprotoLabelSubscribelabelsLabels>),
+  #[serde(rename = "com.atproto.label.subscribeLabels#info")]
+  ComAtprotoLabelSubscribelabelsInfo(Box<ComAtprotoLabelSubscribelabelsInfo>),
+
+  #[serde(other)]
+  Other,
+}
+
+impl Default for ComAtprotoLabelSubscribelabelsMainMessage {
+  fn default() -> Self {
+    Self::ComAtprotoLabelSubscribelabelsLabels(Box::default())
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "$type")]
+pub enum ComAtprotoModerationCreatereportMainInputSubject {
+  #[serde(rename = "com.atproto.admin.defs#repoRef")]
+  ComAtprotoAdminDefsReporef(Box<ComAtprotoAdminDefsReporef>),
+  #[serde(rename = "com.atproto.repo.strongRef")]
+  ComAtprotoRepoStrongref(Box<ComAtprotoRepoStrongref>),
+
+  #[serde(other)]
+  Other,
+}
+
+impl Default for ComAtprotoModerationCreatereportMainInputSubject {
   fn default() -> Self {
-    Self::ComAtprotoAdminDefsReporef(Box::new(ComAtprotoAdminDefsReporef::default()))
+    Self::ComAtprotoAdminDefsReporef(Box::default())
   }
 }
 
@@ -3574,7 +4203,7 @@ pub enum ComAtprotoRepoApplywritesMainInputWritesItem {
 
 impl Default for ComAtprotoRepoApplywritesMainInputWritesItem {
   fn default() -> Self {
-    Self::ComAtprotoRepoApplywritesCreate(Box::new(ComAtprotoRepoApplywritesCreate::default()))
+    Self::ComAtprotoRepoApplywritesCreate(Box::default())
   }
 }
 
@@ -3600,18 +4229,48 @@ pub enum ComAtprotoSyncSubscribereposMainMessage {
 
 impl Default for ComAtprotoSyncSubscribereposMainMessage {
   fn default() -> Self {
-    Self::ComAtprotoSyncSubscribereposCommit(
-      Box::new(ComAtprotoSyncSubscribereposCommit::default()),
-    )
+    Self::ComAtprotoSyncSubscribereposCommit(Box::default())
   }
 }
 
+// codegen:end outputs
+
+/// Callback to receive tokens issued by automatic session refresh
+pub type RefreshCallback = Arc<dyn Fn(&ComAtprotoServerRefreshsession) + Send + Sync>;
+
+/// Seconds before expiration to refresh access token
+pub const JWT_REFRESH_MARGIN_SECONDS: i64 = 60;
+
+/// Seconds to wait before refreshing again after the refresh failed transiently
+pub const REFRESH_BACKOFF_SECONDS: u64 = 30;
+
+/// Returns exp claim of the JWT
+pub fn jwt_expiration(jwt: &str) -> Option<DateTime<Utc>> {
+  let payload = Base::Base64Url.decode(jwt.split('.').nth(1)?).ok()?;
+  let exp = serde_json::from_slice::<Value>(&payload)
+    .ok()?
+    .get("exp")?
+    .as_i64()?;
+  DateTime::from_timestamp(exp, 0)
+}
+
+#[derive(Clone)]
 pub struct Client {
   host: String,
   bgs_host: String,
   proxy: Option<String>,
-  jwt: Option<String>,
-  agent: Agent,
+  jwt: Arc<RwLock<Option<String>>>,
+  refresh_jwt: Arc<RwLock<Option<String>>>,
+  refresh_callback: Option<RefreshCallback>,
+  refresh_lock: Arc<Mutex<Option<Instant>>>,
+  oauth: Option<Arc<OAuthAgent>>,
+  rate_limiter: RateLimiter,
+  retry_policy: RetryPolicy,
+  verify_blocks: bool,
+  transport: Arc<dyn HttpTransport>,
+  service_proxy: Option<String>,
+  accept_labelers: Vec<String>,
+  content_labelers: Arc<RwLock<Vec<String>>>,
 }
 
 impl Client {
@@ -3624,23 +4283,357 @@ impl Client {
       host: host.to_string(),
       bgs_host: bgs_host.to_string(),
       proxy: proxy.as_ref().map(|p| p.to_string()),
-      jwt: None,
-      agent: match proxy {
+      jwt: Arc::new(RwLock::new(None)),
+      refresh_jwt: Arc::new(RwLock::new(None)),
+      refresh_callback: None,
+      refresh_lock: Arc::new(Mutex::new(None)),
+      oauth: None,
+      rate_limiter: RateLimiter::default(),
+      retry_policy: RetryPolicy::default(),
+      verify_blocks: false,
+      service_proxy: None,
+      accept_labelers: Vec::new(),
+      content_labelers: Arc::new(RwLock::new(Vec::new())),
+      transport: Arc::new(match proxy {
         Some(p) => match Proxy::new(p.to_string()) {
           Ok(pr) => AgentBuilder::new().proxy(pr).build(),
           _ => Agent::new(),
         },
         _ => Agent::new(),
-      },
+      }),
     }
   }
 
+  /// Set transport to send HTTP requests instead of ureq
+  pub fn set_transport<T: HttpTransport + 'static>(&mut self, transport: T) {
+    self.transport = Arc::new(transport);
+  }
+
   pub fn set_jwt(&mut self, jwt: Option<String>) {
-    self.jwt = jwt;
+    if let Ok(mut write) = self.jwt.write() {
+      *write = jwt;
+    }
   }
 
   pub fn get_jwt(&self) -> Option<String> {
-    self.jwt.clone()
+    self.jwt.read().ok().and_then(|j| j.clone())
+  }
+
+  /// Set refresh token used to renew expired access token
+  pub fn set_refresh_jwt(&mut self, jwt: Option<String>) {
+    if let Ok(mut write) = self.refresh_jwt.write() {
+      *write = jwt;
+    }
+  }
+
+  pub fn get_refresh_jwt(&self) -> Option<String> {
+    self.refresh_jwt.read().ok().and_then(|j| j.clone())
+  }
+
+  /// Set callback called with new tokens after automatic refresh
+  pub fn set_refresh_callback<F: Fn(&ComAtprotoServerRefreshsession) + Send + Sync + 'static>(
+    &mut self,
+    callback: F,
+  ) {
+    self.refresh_callback = Some(Arc::new(callback));
+  }
+
+  /// Change host, the tokens of the previous host are discarded
+  pub fn set_host<T: ToString>(&mut self, host: T) {
+    self.host = host.to_string();
+    self.jwt = Arc::new(RwLock::new(None));
+    self.refresh_jwt = Arc::new(RwLock::new(None));
+    self.refresh_lock = Arc::new(Mutex::new(None));
+    self.oauth = None;
+  }
+
+  /// Use DPoP bound OAuth tokens instead of the session tokens, host is set to the PDS
+  pub fn set_oauth(&mut self, oauth: OAuthAgent) -> Result<()> {
+    let session = oauth
+      .get_session()
+      .ok_or_else(|| anyhow!("no OAuth session"))?;
+    self.set_host(session.get_host()?);
+    self.oauth = Some(Arc::new(oauth));
+    Ok(())
+  }
+
+  pub fn get_oauth(&self) -> Option<Arc<OAuthAgent>> {
+    self.oauth.clone()
+  }
+
+  fn authorize(&self, req: HttpRequest) -> Result<HttpRequest> {
+    if let Some(oauth) = &self.oauth {
+      return oauth.authorize(req);
+    }
+    Ok(match self.get_jwt() {
+      Some(jwt) => req.set("Authorization", &format!("Bearer {}", jwt)),
+      None => req,
+    })
+  }
+
+  fn update_nonce(&self, res: &HttpResponse) -> bool {
+    match &self.oauth {
+      Some(oauth) => oauth.update_nonce(res),
+      None => false,
+    }
+  }
+
+  fn is_jwt_expiring(&self) -> bool {
+    if let Some(oauth) = &self.oauth {
+      return oauth.is_expiring();
+    }
+    self
+      .get_jwt()
+      .and_then(|jwt| jwt_expiration(&jwt))
+      .map(|exp| exp - Utc::now() < chrono::Duration::seconds(JWT_REFRESH_MARGIN_SECONDS))
+      .unwrap_or_default()
+  }
+
+  fn access_token(&self) -> Option<String> {
+    match &self.oauth {
+      Some(oauth) => oauth.get_session().map(|s| s.access_token),
+      None => self.get_jwt(),
+    }
+  }
+
+  /// Refresh tokens once even if requests with the same expired token call it concurrently
+  fn try_refresh(&self, used_token: Option<&str>) -> bool {
+    // holds the time until which refresh is suspended after a transient failure
+    let Ok(mut backoff) = self.refresh_lock.lock() else {
+      return false;
+    };
+    // another request refreshed the token while waiting for the lock
+    if self.access_token().as_deref() != used_token {
+      return true;
+    }
+    if let Some(oauth) = &self.oauth {
+      return match oauth.refresh() {
+        Ok(_) => true,
+        Err(e) => {
+          log::warn!("OAuth refresh error : {}", e);
+          false
+        }
+      };
+    }
+    if self.get_refresh_jwt().is_none() || backoff.map(|b| Instant::now() < b).unwrap_or_default() {
+      return false;
+    }
+    match self.com_atproto_server_refreshsession() {
+      Ok(_) => {
+        *backoff = None;
+        true
+      }
+      Err(e) => {
+        log::warn!("session refresh error : {}", e);
+        match e.downcast_ref::<XrpcError>().and_then(|e| e.status()) {
+          // the refresh token is expired or revoked, login is required
+          Some(400 | 401) => {
+            if let Ok(mut write) = self.refresh_jwt.write() {
+              *write = None;
+            }
+          }
+          _ => *backoff = Some(Instant::now() + Duration::from_secs(REFRESH_BACKOFF_SECONDS)),
+        }
+        false
+      }
+    }
+  }
+
+  /// Set retry of GET requests
+  pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+    self.retry_policy = policy;
+  }
+
+  pub fn get_retry_policy(&self) -> RetryPolicy {
+    self.retry_policy.clone()
+  }
+
+  /// Share rate limit states with other clients
+  pub fn set_rate_limiter(&mut self, rate_limiter: RateLimiter) {
+    self.rate_limiter = rate_limiter;
+  }
+
+  pub fn get_rate_limiter(&self) -> RateLimiter {
+    self.rate_limiter.clone()
+  }
+
+  /// Returns last rate limit of the host
+  pub fn get_rate_limit(&self) -> Option<RateLimit> {
+    self
+      .rate_limiter
+      .get(self.host.split(':').next().unwrap_or_default())
+  }
+
+  /// Set service like "did:web:api.bsky.chat#bsky_chat" the PDS proxies the calls to, chat.bsky.* calls use CHAT_SERVICE if not set
+  pub fn set_service_proxy<T: ToString>(&mut self, service: Option<T>) {
+    self.service_proxy = service.map(|s| s.to_string());
+  }
+
+  pub fn get_service_proxy(&self) -> Option<String> {
+    self.service_proxy.clone()
+  }
+
+  /// Returns client sharing the session whose calls are proxied to the service
+  pub fn with_service_proxy<T: ToString>(&self, service: T) -> Self {
+    let mut client = self.clone();
+    client.set_service_proxy(Some(service));
+    client
+  }
+
+  /// Set labelers applied to responses, a DID can be followed by ";redact"
+  pub fn set_accept_labelers<T: ToString>(&mut self, labelers: &[T]) {
+    self.accept_labelers = labelers.iter().map(|l| l.to_string()).collect();
+  }
+
+  pub fn get_accept_labelers(&self) -> Vec<String> {
+    self.accept_labelers.clone()
+  }
+
+  /// Returns client sharing the session whose responses are labeled by the labelers
+  pub fn with_accept_labelers<T: ToString>(&self, labelers: &[T]) -> Self {
+    let mut client = self.clone();
+    client.set_accept_labelers(labelers);
+    client
+  }
+
+  /// Returns labelers which labeled the last response
+  pub fn get_content_labelers(&self) -> Vec<String> {
+    self
+      .content_labelers
+      .read()
+      .map(|l| l.clone())
+      .unwrap_or_default()
+  }
+
+  fn set_service_headers(&self, mut req: HttpRequest) -> HttpRequest {
+    // relay and other hosts do not route the calls
+    if !req.url().starts_with(&format!("https://{}/", self.host)) {
+      return req;
+    }
+    let service = match &self.service_proxy {
+      Some(service) => Some(service.as_str()),
+      None if method_of(req.url()).starts_with("chat.bsky.") => Some(CHAT_SERVICE),
+      None => None,
+    };
+    if let (Some(service), None) = (service, req.header("atproto-proxy")) {
+      req = req.set("atproto-proxy", service);
+    }
+    if !self.accept_labelers.is_empty() && req.header("atproto-accept-labelers").is_none() {
+      req = req.set("atproto-accept-labelers", &self.accept_labelers.join(", "));
+    }
+    req
+  }
+
+  fn update_content_labelers(&self, res: &HttpResponse) {
+    let labelers = res
+      .header("atproto-content-labelers")
+      .map(|h| {
+        h.split(',')
+          .map(|l| l.trim().to_string())
+          .filter(|l| !l.is_empty())
+          .collect()
+      })
+      .unwrap_or_default();
+    if let Ok(mut write) = self.content_labelers.write() {
+      *write = labelers;
+    }
+  }
+
+  fn request(&self, req: HttpRequest) -> Result<HttpResponse> {
+    let req = self.set_service_headers(req);
+    let idempotent = req.method() == "GET";
+    let mut retry = 0;
+    loop {
+      if let Some(wait) = self.rate_limiter.wait_time(req.url()) {
+        log::info!("rate limit of {} is exhausted, wait {:?}", req.url(), wait);
+        std::thread::sleep(wait.min(self.retry_policy.max_wait));
+      }
+      let result = self.request_once(req.clone());
+      let error = match &result {
+        Ok(res) => {
+          self
+            .rate_limiter
+            .update(req.url(), RateLimit::from_response(res).as_ref());
+          self.update_content_labelers(res);
+          return result;
+        }
+        Err(e) => match e.downcast_ref::<XrpcError>() {
+          Some(e) => e,
+          None => return result,
+        },
+      };
+      self.rate_limiter.update(req.url(), error.rate_limit());
+      let wait = match idempotent {
+        true => self.retry_policy.retry_wait(error, retry),
+        false => None,
+      };
+      let Some(wait) = wait else {
+        return result;
+      };
+      log::info!("retry after {:?} : {}", wait, error);
+      std::thread::sleep(wait);
+      retry += 1;
+    }
+  }
+
+  fn send(&self, req: HttpRequest) -> Result<HttpResponse> {
+    let method = method_of(req.url());
+    let res = self.transport.send(req).map_err(|e| XrpcError::Transport {
+      method,
+      message: e.to_string(),
+    })?;
+    self.update_nonce(&res);
+    Ok(res)
+  }
+
+  fn request_once(&self, req: HttpRequest) -> Result<HttpResponse> {
+    if self.is_jwt_expiring() {
+      self.try_refresh(self.access_token().as_deref());
+    }
+    let used_token = self.access_token();
+    let res = self.send(self.authorize(req.clone())?)?;
+    let status = res.status();
+    if status < 400 {
+      return Ok(res);
+    }
+    let use_nonce = self.oauth.is_some()
+      && res
+        .header("WWW-Authenticate")
+        .map(|h| h.contains("use_dpop_nonce"))
+        .unwrap_or_default();
+    let error = XrpcError::from_response(res);
+    let expired = match error.error() {
+      Some(XrpcErrorName::ExpiredToken) => true,
+      Some(XrpcErrorName::Other(e)) if e == "invalid_token" => self.oauth.is_some(),
+      _ => false,
+    };
+    if matches!(status, 400 | 401)
+      && (use_nonce || (expired && self.try_refresh(used_token.as_deref())))
+    {
+      return self.send(self.authorize(req)?)?.error_for_status();
+    }
+    Err(error.into())
+  }
+
+  fn call(&self, req: HttpRequest) -> Result<HttpResponse> {
+    self.request(req)
+  }
+
+  fn send_json(&self, req: HttpRequest, data: Value) -> Result<HttpResponse> {
+    self.request(req.json(&data)?)
+  }
+
+  fn send_bytes(&self, req: HttpRequest, data: &[u8]) -> Result<HttpResponse> {
+    self.request(req.bytes(data.to_vec()))
+  }
+
+  /// Verify CIDs of the blocks in downloaded CAR files
+  pub fn set_verify_blocks(&mut self, verify: bool) {
+    self.verify_blocks = verify;
+  }
+
+  pub fn get_verify_blocks(&self) -> bool {
+    self.verify_blocks
   }
 
   pub fn get_host(&self) -> String {
@@ -3655,93 +4648,116 @@ impl Client {
     self.proxy.clone()
   }
 
-  /// Get private preferences attached to the current account. Expected use is synchronization between multiple devices, and import/export during account migration. Requires auth.
+  /// Refresh an authentication session. Requires auth using the &#39;refreshJwt&#39; (not the &#39;accessJwt&#39;).
+  pub fn com_atproto_server_refreshsession(&self) -> Result<ComAtprotoServerRefreshsession> {
+    let mut req = HttpRequest::post(format!(
+      "https://{}/xrpc/com.atproto.server.refreshSession",
+      self.host
+    ));
+    if let Some(jwt) = self.get_refresh_jwt().or_else(|| self.get_jwt()) {
+      req = req.set("Authorization", &format!("Bearer {}", jwt));
+    }
+
+    let session: ComAtprotoServerRefreshsession =
+      self.send(req)?.error_for_status()?.into_json()?;
+    if let Ok(mut write) = self.jwt.write() {
+      *write = Some(session.access_jwt.clone());
+    }
+    if let Ok(mut write) = self.refresh_jwt.write() {
+      *write = Some(session.refresh_jwt.clone());
+    }
+    if let Some(callback) = &self.refresh_callback {
+      callback(&session);
+    }
+    Ok(session)
+  }
 
+  // codegen:begin methods
+  /// Get private preferences attached to the current account. Expected use is synchronization between multiple devices, and import/export during account migration. Requires auth.
   pub fn app_bsky_actor_getpreferences(&self) -> Result<AppBskyActorGetpreferences> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.actor.getPreferences",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
-    Ok(req.call()?.into_json()?)
+    self.call(req)?.into_json()
   }
 
   /// Get detailed profile view of an actor. Does not require auth, but contains relevant metadata with auth.
-
   pub fn app_bsky_actor_getprofile(
     &self,
     actor: &str,
   ) -> Result<AppBskyActorDefsProfileviewdetailed> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.actor.getProfile",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("actor", actor));
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get detailed profile views of multiple actors.
-
   pub fn app_bsky_actor_getprofiles(&self, actors: &[&str]) -> Result<AppBskyActorGetprofiles> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.actor.getProfiles",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let mut actors_value = actors.iter().map(|i| ("actors", *i)).collect::<Vec<_>>();
-
-    _q.append(&mut actors_value);
+    _q.extend(actors.iter().map(|v| ("actors", *v)));
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get a list of suggested actors. Expected use is discovery of accounts to follow during new account onboarding.
-
   pub fn app_bsky_actor_getsuggestions(
     &self,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyActorGetsuggestions> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.actor.getSuggestions",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// Find actors (profiles) matching search criteria. Does not require auth.
+  /// Set the private preferences attached to the account.
+  pub fn app_bsky_actor_putpreferences(
+    &self,
+    preferences: &AppBskyActorDefsPreferences,
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/app.bsky.actor.putPreferences",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("preferences"), json!(preferences));
+
+    self.send_json(req, json!(input))
+  }
 
+  /// Find actors (profiles) matching search criteria. Does not require auth.
   pub fn app_bsky_actor_searchactors(
     &self,
     term: Option<&str>,
@@ -3749,154 +4765,134 @@ impl Client {
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyActorSearchactors> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.actor.searchActors",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    if term.is_some() {
-      _q.push(("term", term.unwrap_or_default()));
-    };
+    if let Some(v) = term {
+      _q.push(("term", v));
+    }
 
-    if q.is_some() {
-      _q.push(("q", q.unwrap_or_default()));
+    if let Some(v) = q {
+      _q.push(("q", v));
     }
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Find actor suggestions for a prefix search term. Expected use is for auto-completion during text field entry. Does not require auth.
-
   pub fn app_bsky_actor_searchactorstypeahead(
     &self,
     term: Option<&str>,
     q: Option<&str>,
     limit: Option<i64>,
   ) -> Result<AppBskyActorSearchactorstypeahead> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.actor.searchActorsTypeahead",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    if term.is_some() {
-      _q.push(("term", term.unwrap_or_default()));
-    };
+    if let Some(v) = term {
+      _q.push(("term", v));
+    }
 
-    if q.is_some() {
-      _q.push(("q", q.unwrap_or_default()));
+    if let Some(v) = q {
+      _q.push(("q", v));
     }
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get information about a feed generator, including policies and offered feed URIs. Does not require auth; implemented by Feed Generator services (not App View).
-
   pub fn app_bsky_feed_describefeedgenerator(&self) -> Result<AppBskyFeedDescribefeedgenerator> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.feed.describeFeedGenerator",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
-    Ok(req.call()?.into_json()?)
+    self.call(req)?.into_json()
   }
 
-  /// Get a list of feeds (feed generator records) created by the actor (in the actor&#39;s repo).
-
+  /// Get a list of feeds (feed generator records) created by the actor (in the actor's repo).
   pub fn app_bsky_feed_getactorfeeds(
     &self,
     actor: &str,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyFeedGetactorfeeds> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.feed.getActorFeeds",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("actor", actor));
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get a list of posts liked by an actor. Does not require auth.
-
   pub fn app_bsky_feed_getactorlikes(
     &self,
     actor: &str,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyFeedGetactorlikes> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.feed.getActorLikes",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("actor", actor));
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// Get a view of an actor&#39;s &#39;author feed&#39; (post and reposts by the author). Does not require auth.
-
+  /// Get a view of an actor's 'author feed' (post and reposts by the author). Does not require auth.
   pub fn app_bsky_feed_getauthorfeed(
     &self,
     actor: &str,
@@ -3904,143 +4900,119 @@ impl Client {
     cursor: Option<&str>,
     filter: Option<&str>,
   ) -> Result<AppBskyFeedGetauthorfeed> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.feed.getAuthorFeed",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("actor", actor));
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
-    };
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
+    }
 
-    if filter.is_some() {
-      _q.push(("filter", filter.unwrap_or_default()));
+    if let Some(v) = filter {
+      _q.push(("filter", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// Get a hydrated feed from an actor&#39;s selected feed generator. Implemented by App View.
-
+  /// Get a hydrated feed from an actor's selected feed generator. Implemented by App View.
   pub fn app_bsky_feed_getfeed(
     &self,
     feed: &str,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyFeedGetfeed> {
-    let mut req = self
-      .agent
-      .get(&format!("https://{}/xrpc/app.bsky.feed.getFeed", self.host));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
+    let req = HttpRequest::get(format!("https://{}/xrpc/app.bsky.feed.getFeed", self.host));
 
     let mut _q = Vec::new();
 
     _q.push(("feed", feed));
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get information about a feed generator. Implemented by AppView.
-
   pub fn app_bsky_feed_getfeedgenerator(&self, feed: &str) -> Result<AppBskyFeedGetfeedgenerator> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.feed.getFeedGenerator",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("feed", feed));
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get information about a list of feed generators.
-
   pub fn app_bsky_feed_getfeedgenerators(
     &self,
     feeds: &[&str],
   ) -> Result<AppBskyFeedGetfeedgenerators> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.feed.getFeedGenerators",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let mut feeds_value = feeds.iter().map(|i| ("feeds", *i)).collect::<Vec<_>>();
+    _q.extend(feeds.iter().map(|v| ("feeds", *v)));
 
-    _q.append(&mut feeds_value);
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get a skeleton of a feed provided by a feed generator. Auth is optional, depending on provider requirements, and provides the DID of the requester. Implemented by Feed Generator Service.
-
   pub fn app_bsky_feed_getfeedskeleton(
     &self,
     feed: &str,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyFeedGetfeedskeleton> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.feed.getFeedSkeleton",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("feed", feed));
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get like records which reference a subject (by AT-URI and CID).
-
   pub fn app_bsky_feed_getlikes(
     &self,
     uri: &str,
@@ -4048,127 +5020,103 @@ impl Client {
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyFeedGetlikes> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/app.bsky.feed.getLikes",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
+    let req = HttpRequest::get(format!("https://{}/xrpc/app.bsky.feed.getLikes", self.host));
 
     let mut _q = Vec::new();
 
     _q.push(("uri", uri));
 
-    let cid_value = serde_json::to_string(&cid)?;
+    let cid_value = cid.map(|v| v.to_string());
 
-    if cid.is_some() {
-      _q.push(("cid", cid_value.as_str()));
+    if let Some(v) = &cid_value {
+      _q.push(("cid", v.as_str()));
     }
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get a feed of recent posts from a list (posts and reposts from any actors on the list). Does not require auth.
-
   pub fn app_bsky_feed_getlistfeed(
     &self,
     list: &str,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyFeedGetlistfeed> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.feed.getListFeed",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("list", list));
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get posts in a thread. Does not require auth, but additional metadata and filtering will be applied for authed requests.
-
   pub fn app_bsky_feed_getpostthread(
     &self,
     uri: &str,
     depth: Option<i64>,
     parent_height: Option<i64>,
   ) -> Result<AppBskyFeedGetpostthread> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.feed.getPostThread",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("uri", uri));
 
-    let depth_value = serde_json::to_string(&depth)?;
+    let depth_value = depth.map(|v| v.to_string());
 
-    if depth.is_some() {
-      _q.push(("depth", depth_value.as_str()));
+    if let Some(v) = &depth_value {
+      _q.push(("depth", v.as_str()));
     }
 
-    let parent_height_value = serde_json::to_string(&parent_height)?;
+    let parent_height_value = parent_height.map(|v| v.to_string());
 
-    if parent_height.is_some() {
-      _q.push(("parent_height", parent_height_value.as_str()));
+    if let Some(v) = &parent_height_value {
+      _q.push(("parentHeight", v.as_str()));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// Gets post views for a specified list of posts (by AT-URI). This is sometimes referred to as &#39;hydrating&#39; a &#39;feed skeleton&#39;.
-
+  /// Gets post views for a specified list of posts (by AT-URI). This is sometimes referred to as 'hydrating' a 'feed skeleton'.
   pub fn app_bsky_feed_getposts(&self, uris: &[&str]) -> Result<AppBskyFeedGetposts> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/app.bsky.feed.getPosts",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
+    let req = HttpRequest::get(format!("https://{}/xrpc/app.bsky.feed.getPosts", self.host));
 
     let mut _q = Vec::new();
 
-    let mut uris_value = uris.iter().map(|i| ("uris", *i)).collect::<Vec<_>>();
+    _q.extend(uris.iter().map(|v| ("uris", *v)));
 
-    _q.append(&mut uris_value);
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get a list of reposts for a given post.
-
   pub fn app_bsky_feed_getrepostedby(
     &self,
     uri: &str,
@@ -4176,551 +5124,570 @@ impl Client {
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyFeedGetrepostedby> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.feed.getRepostedBy",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("uri", uri));
 
-    let cid_value = serde_json::to_string(&cid)?;
+    let cid_value = cid.map(|v| v.to_string());
 
-    if cid.is_some() {
-      _q.push(("cid", cid_value.as_str()));
+    if let Some(v) = &cid_value {
+      _q.push(("cid", v.as_str()));
     }
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get a list of suggested feeds (feed generators) for the requesting account.
-
   pub fn app_bsky_feed_getsuggestedfeeds(
     &self,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyFeedGetsuggestedfeeds> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.feed.getSuggestedFeeds",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// Get a view of the requesting account&#39;s home timeline. This is expected to be some form of reverse-chronological feed.
-
+  /// Get a view of the requesting account's home timeline. This is expected to be some form of reverse-chronological feed.
   pub fn app_bsky_feed_gettimeline(
     &self,
     algorithm: Option<&str>,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyFeedGettimeline> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.feed.getTimeline",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    if algorithm.is_some() {
-      _q.push(("algorithm", algorithm.unwrap_or_default()));
+    if let Some(v) = algorithm {
+      _q.push(("algorithm", v));
     }
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Find posts matching search criteria, returning views of those posts.
-
   pub fn app_bsky_feed_searchposts(
     &self,
     q: &str,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyFeedSearchposts> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.feed.searchPosts",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("q", q));
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Enumerates which accounts the requesting account is currently blocking. Requires auth.
-
   pub fn app_bsky_graph_getblocks(
     &self,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyGraphGetblocks> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.graph.getBlocks",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Enumerates accounts which follow a specified account (actor).
-
   pub fn app_bsky_graph_getfollowers(
     &self,
     actor: &str,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyGraphGetfollowers> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.graph.getFollowers",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("actor", actor));
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Enumerates accounts which a specified account (actor) follows.
-
   pub fn app_bsky_graph_getfollows(
     &self,
     actor: &str,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyGraphGetfollows> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.graph.getFollows",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("actor", actor));
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// Gets a &#39;view&#39; (with additional context) of a specified list.
-
+  /// Gets a 'view' (with additional context) of a specified list.
   pub fn app_bsky_graph_getlist(
     &self,
     list: &str,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyGraphGetlist> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/app.bsky.graph.getList",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
+    let req = HttpRequest::get(format!("https://{}/xrpc/app.bsky.graph.getList", self.host));
 
     let mut _q = Vec::new();
 
     _q.push(("list", list));
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get mod lists that the requesting account (actor) is blocking. Requires auth.
-
   pub fn app_bsky_graph_getlistblocks(
     &self,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyGraphGetlistblocks> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.graph.getListBlocks",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Enumerates mod lists that the requesting account (actor) currently has muted. Requires auth.
-
   pub fn app_bsky_graph_getlistmutes(
     &self,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyGraphGetlistmutes> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.graph.getListMutes",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Enumerates the lists created by a specified account (actor).
-
   pub fn app_bsky_graph_getlists(
     &self,
     actor: &str,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyGraphGetlists> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.graph.getLists",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("actor", actor));
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Enumerates accounts that the requesting account (actor) currently has muted. Requires auth.
-
   pub fn app_bsky_graph_getmutes(
     &self,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyGraphGetmutes> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.graph.getMutes",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Enumerates public relationships between one account, and a list of other accounts. Does not require auth.
-
   pub fn app_bsky_graph_getrelationships(
     &self,
     actor: &str,
     others: Option<&[&str]>,
   ) -> Result<AppBskyGraphGetrelationships> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.graph.getRelationships",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("actor", actor));
 
-    let others_value = serde_json::to_string(&others)?;
-
-    if others.is_some() {
-      _q.push(("others", others_value.as_str()));
+    if let Some(v) = others {
+      _q.extend(v.iter().map(|v| ("others", *v)));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Enumerates follows similar to a given account (actor). Expected use is to recommend additional accounts immediately after following one account.
-
   pub fn app_bsky_graph_getsuggestedfollowsbyactor(
     &self,
     actor: &str,
   ) -> Result<AppBskyGraphGetsuggestedfollowsbyactor> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.graph.getSuggestedFollowsByActor",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("actor", actor));
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// Count the number of unread notifications for the requesting account. Requires auth.
+  /// Creates a mute relationship for the specified account. Mutes are private in Bluesky. Requires auth.
+  pub fn app_bsky_graph_muteactor(&self, actor: &str) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/app.bsky.graph.muteActor",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("actor"), json!(actor));
+
+    self.send_json(req, json!(input))
+  }
+
+  /// Creates a mute relationship for the specified list of accounts. Mutes are private in Bluesky. Requires auth.
+  pub fn app_bsky_graph_muteactorlist(&self, list: &str) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/app.bsky.graph.muteActorList",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("list"), json!(list));
+
+    self.send_json(req, json!(input))
+  }
+
+  /// Unmutes the specified account. Requires auth.
+  pub fn app_bsky_graph_unmuteactor(&self, actor: &str) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/app.bsky.graph.unmuteActor",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("actor"), json!(actor));
+
+    self.send_json(req, json!(input))
+  }
+
+  /// Unmutes the specified list of accounts. Requires auth.
+  pub fn app_bsky_graph_unmuteactorlist(&self, list: &str) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/app.bsky.graph.unmuteActorList",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("list"), json!(list));
+
+    self.send_json(req, json!(input))
+  }
 
+  /// Count the number of unread notifications for the requesting account. Requires auth.
   pub fn app_bsky_notification_getunreadcount(
     &self,
     seen_at: Option<&DateTime<Utc>>,
   ) -> Result<AppBskyNotificationGetunreadcount> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.notification.getUnreadCount",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let seen_at_value = serde_json::to_string(&seen_at)?;
+    let seen_at_value = seen_at.map(|v| v.to_rfc3339());
 
-    if seen_at.is_some() {
-      _q.push(("seen_at", seen_at_value.as_str()));
+    if let Some(v) = &seen_at_value {
+      _q.push(("seenAt", v.as_str()));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Enumerate notifications for the requesting account. Requires auth.
-
   pub fn app_bsky_notification_listnotifications(
     &self,
     limit: Option<i64>,
     cursor: Option<&str>,
     seen_at: Option<&DateTime<Utc>>,
   ) -> Result<AppBskyNotificationListnotifications> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.notification.listNotifications",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    let seen_at_value = serde_json::to_string(&seen_at)?;
+    let seen_at_value = seen_at.map(|v| v.to_rfc3339());
 
-    if seen_at.is_some() {
-      _q.push(("seen_at", seen_at_value.as_str()));
+    if let Some(v) = &seen_at_value {
+      _q.push(("seenAt", v.as_str()));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// An unspecced view of globally popular feed generators.
+  /// Register to receive push notifications, via a specified service, for the requesting account. Requires auth.
+  pub fn app_bsky_notification_registerpush(
+    &self,
+    service_did: &str,
+    token: &str,
+    platform: &str,
+    app_id: &str,
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/app.bsky.notification.registerPush",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("serviceDid"), json!(service_did));
+
+    input.insert(String::from("token"), json!(token));
 
+    input.insert(String::from("platform"), json!(platform));
+
+    input.insert(String::from("appId"), json!(app_id));
+
+    self.send_json(req, json!(input))
+  }
+
+  /// Notify server that the requesting account has seen notifications. Requires auth.
+  pub fn app_bsky_notification_updateseen(&self, seen_at: &DateTime<Utc>) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/app.bsky.notification.updateSeen",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("seenAt"), json!(seen_at));
+
+    self.send_json(req, json!(input))
+  }
+
+  /// An unspecced view of globally popular feed generators.
   pub fn app_bsky_unspecced_getpopularfeedgenerators(
     &self,
     limit: Option<i64>,
     cursor: Option<&str>,
     query: Option<&str>,
   ) -> Result<AppBskyUnspeccedGetpopularfeedgenerators> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.unspecced.getPopularFeedGenerators",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
-    };
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
+    }
 
-    if query.is_some() {
-      _q.push(("query", query.unwrap_or_default()));
+    if let Some(v) = query {
+      _q.push(("query", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get a list of suggestions (feeds and users) tagged with categories
-
   pub fn app_bsky_unspecced_gettaggedsuggestions(
     &self,
   ) -> Result<AppBskyUnspeccedGettaggedsuggestions> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.unspecced.getTaggedSuggestions",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
-    Ok(req.call()?.into_json()?)
+    self.call(req)?.into_json()
   }
 
   /// Backend Actors (profile) search, returns only skeleton.
-
   pub fn app_bsky_unspecced_searchactorsskeleton(
     &self,
     q: &str,
@@ -4728,271 +5695,534 @@ impl Client {
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyUnspeccedSearchactorsskeleton> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.unspecced.searchActorsSkeleton",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("q", q));
 
-    let typeahead_value = serde_json::to_string(&typeahead)?;
+    let typeahead_value = typeahead.map(|v| v.to_string());
 
-    if typeahead.is_some() {
-      _q.push(("typeahead", typeahead_value.as_str()));
+    if let Some(v) = &typeahead_value {
+      _q.push(("typeahead", v.as_str()));
     }
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Backend Posts search, returns only skeleton
-
   pub fn app_bsky_unspecced_searchpostsskeleton(
     &self,
     q: &str,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<AppBskyUnspeccedSearchpostsskeleton> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/app.bsky.unspecced.searchPostsSkeleton",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("q", q));
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// Get details about an account.
+  /// Delete a message from the view of the account, other members still see it.
+  pub fn chat_bsky_convo_deletemessageforself(
+    &self,
+    convo_id: &str,
+    message_id: &str,
+  ) -> Result<ChatBskyConvoDefsDeletedmessageview> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/chat.bsky.convo.deleteMessageForSelf",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("convoId"), json!(convo_id));
+
+    input.insert(String::from("messageId"), json!(message_id));
+
+    self.send_json(req, json!(input))?.into_json()
+  }
+
+  /// Get a conversation of the account by its ID.
+  pub fn chat_bsky_convo_getconvo(&self, convo_id: &str) -> Result<ChatBskyConvoGetconvo> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/chat.bsky.convo.getConvo",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("convoId", convo_id));
+
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// Get logs of changes to conversations of the account after the cursor, or the latest logs without a cursor.
+  pub fn chat_bsky_convo_getlog(&self, cursor: Option<&str>) -> Result<ChatBskyConvoGetlog> {
+    let req = HttpRequest::get(format!("https://{}/xrpc/chat.bsky.convo.getLog", self.host));
+
+    let mut _q = Vec::new();
+
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
+    }
+
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// Get messages of a conversation, newest first.
+  pub fn chat_bsky_convo_getmessages(
+    &self,
+    convo_id: &str,
+    limit: Option<i64>,
+    cursor: Option<&str>,
+  ) -> Result<ChatBskyConvoGetmessages> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/chat.bsky.convo.getMessages",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("convoId", convo_id));
+
+    let limit_value = limit.map(|v| v.to_string());
+
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
+
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
+    }
+
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// List conversations of the account, most recently active first.
+  pub fn chat_bsky_convo_listconvos(
+    &self,
+    limit: Option<i64>,
+    cursor: Option<&str>,
+  ) -> Result<ChatBskyConvoListconvos> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/chat.bsky.convo.listConvos",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    let limit_value = limit.map(|v| v.to_string());
+
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
+
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
+    }
+
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// Mute notifications of a conversation.
+  pub fn chat_bsky_convo_muteconvo(&self, convo_id: &str) -> Result<ChatBskyConvoMuteconvo> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/chat.bsky.convo.muteConvo",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("convoId"), json!(convo_id));
+
+    self.send_json(req, json!(input))?.into_json()
+  }
+
+  /// Send a message to a conversation.
+  pub fn chat_bsky_convo_sendmessage(
+    &self,
+    convo_id: &str,
+    message: &ChatBskyConvoDefsMessageinput,
+  ) -> Result<ChatBskyConvoDefsMessageview> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/chat.bsky.convo.sendMessage",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("convoId"), json!(convo_id));
+
+    input.insert(String::from("message"), json!(message));
+
+    self.send_json(req, json!(input))?.into_json()
+  }
+
+  /// Send messages to multiple conversations at once.
+  pub fn chat_bsky_convo_sendmessagebatch(
+    &self,
+    items: &[&ChatBskyConvoSendmessagebatchBatchitem],
+  ) -> Result<ChatBskyConvoSendmessagebatch> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/chat.bsky.convo.sendMessageBatch",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("items"), json!(items));
+
+    self.send_json(req, json!(input))?.into_json()
+  }
+
+  /// Administrative action to create a new, re-usable communication (email for now) template.
+  pub fn com_atproto_admin_createcommunicationtemplate(
+    &self,
+    name: &str,
+    content_markdown: &str,
+    subject: &str,
+    created_by: Option<&str>,
+  ) -> Result<ComAtprotoAdminDefsCommunicationtemplateview> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/com.atproto.admin.createCommunicationTemplate",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("name"), json!(name));
+
+    input.insert(String::from("contentMarkdown"), json!(content_markdown));
+
+    input.insert(String::from("subject"), json!(subject));
+
+    if let Some(v) = &created_by {
+      input.insert(String::from("createdBy"), json!(v));
+    }
+
+    self.send_json(req, json!(input))?.into_json()
+  }
+
+  /// Delete a user account as an administrator.
+  pub fn com_atproto_admin_deleteaccount(&self, did: &str) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/com.atproto.admin.deleteAccount",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("did"), json!(did));
+
+    self.send_json(req, json!(input))
+  }
+
+  /// Delete a communication template.
+  pub fn com_atproto_admin_deletecommunicationtemplate(&self, id: &str) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/com.atproto.admin.deleteCommunicationTemplate",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
 
+    input.insert(String::from("id"), json!(id));
+
+    self.send_json(req, json!(input))
+  }
+
+  /// Disable an account from receiving new invite codes, but does not invalidate existing codes.
+  pub fn com_atproto_admin_disableaccountinvites(
+    &self,
+    account: &str,
+    note: Option<&str>,
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/com.atproto.admin.disableAccountInvites",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("account"), json!(account));
+
+    if let Some(v) = &note {
+      input.insert(String::from("note"), json!(v));
+    }
+
+    self.send_json(req, json!(input))
+  }
+
+  /// Disable some set of codes and/or all codes associated with a set of users.
+  pub fn com_atproto_admin_disableinvitecodes(
+    &self,
+    codes: Option<&[&str]>,
+    accounts: Option<&[&str]>,
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/com.atproto.admin.disableInviteCodes",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    if let Some(v) = &codes {
+      input.insert(String::from("codes"), json!(v));
+    }
+
+    if let Some(v) = &accounts {
+      input.insert(String::from("accounts"), json!(v));
+    }
+
+    self.send_json(req, json!(input))
+  }
+
+  /// Take a moderation action on an actor.
+  pub fn com_atproto_admin_emitmoderationevent(
+    &self,
+    event: ComAtprotoAdminEmitmoderationeventMainInputEvent,
+    subject: ComAtprotoAdminEmitmoderationeventMainInputSubject,
+    created_by: &str,
+    subject_blob_cids: Option<&[&CidString]>,
+  ) -> Result<ComAtprotoAdminDefsModeventview> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/com.atproto.admin.emitModerationEvent",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("event"), json!(event));
+
+    input.insert(String::from("subject"), json!(subject));
+
+    input.insert(String::from("createdBy"), json!(created_by));
+
+    if let Some(v) = &subject_blob_cids {
+      input.insert(String::from("subjectBlobCids"), json!(v));
+    }
+
+    self.send_json(req, json!(input))?.into_json()
+  }
+
+  /// Re-enable an account's ability to receive invite codes.
+  pub fn com_atproto_admin_enableaccountinvites(
+    &self,
+    account: &str,
+    note: Option<&str>,
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/com.atproto.admin.enableAccountInvites",
+      self.host
+    ));
+
+    let mut input = serde_json::Map::new();
+
+    input.insert(String::from("account"), json!(account));
+
+    if let Some(v) = &note {
+      input.insert(String::from("note"), json!(v));
+    }
+
+    self.send_json(req, json!(input))
+  }
+
+  /// Get details about an account.
   pub fn com_atproto_admin_getaccountinfo(
     &self,
     did: &str,
   ) -> Result<ComAtprotoAdminDefsAccountview> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/com.atproto.admin.getAccountInfo",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("did", did));
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get details about some accounts.
-
   pub fn com_atproto_admin_getaccountinfos(
     &self,
     dids: &[&str],
   ) -> Result<ComAtprotoAdminGetaccountinfos> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/com.atproto.admin.getAccountInfos",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let mut dids_value = dids.iter().map(|i| ("dids", *i)).collect::<Vec<_>>();
+    _q.extend(dids.iter().map(|v| ("dids", *v)));
 
-    _q.append(&mut dids_value);
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get an admin view of invite codes.
-
   pub fn com_atproto_admin_getinvitecodes(
     &self,
     sort: Option<&str>,
     limit: Option<i64>,
     cursor: Option<&str>,
   ) -> Result<ComAtprotoAdminGetinvitecodes> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/com.atproto.admin.getInviteCodes",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    if sort.is_some() {
-      _q.push(("sort", sort.unwrap_or_default()));
+    if let Some(v) = sort {
+      _q.push(("sort", v));
     }
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get details about a moderation event.
-
   pub fn com_atproto_admin_getmoderationevent(
     &self,
     id: i64,
   ) -> Result<ComAtprotoAdminDefsModeventviewdetail> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/com.atproto.admin.getModerationEvent",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let id_value = serde_json::to_string(&id)?;
+    let id_value = id.to_string();
 
     _q.push(("id", id_value.as_str()));
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get details about a record.
-
   pub fn com_atproto_admin_getrecord(
     &self,
     uri: &str,
     cid: Option<&CidString>,
   ) -> Result<ComAtprotoAdminDefsRecordviewdetail> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/com.atproto.admin.getRecord",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("uri", uri));
 
-    let cid_value = serde_json::to_string(&cid)?;
+    let cid_value = cid.map(|v| v.to_string());
 
-    if cid.is_some() {
-      _q.push(("cid", cid_value.as_str()));
+    if let Some(v) = &cid_value {
+      _q.push(("cid", v.as_str()));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get details about a repository.
-
   pub fn com_atproto_admin_getrepo(&self, did: &str) -> Result<ComAtprotoAdminDefsRepoviewdetail> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/com.atproto.admin.getRepo",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
     _q.push(("did", did));
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get the service-specific admin status of a subject (account, record, or blob).
-
   pub fn com_atproto_admin_getsubjectstatus(
     &self,
     did: Option<&str>,
     uri: Option<&str>,
     blob: Option<&CidString>,
   ) -> Result<ComAtprotoAdminGetsubjectstatus> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/com.atproto.admin.getSubjectStatus",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    if did.is_some() {
-      _q.push(("did", did.unwrap_or_default()));
-    };
+    if let Some(v) = did {
+      _q.push(("did", v));
+    }
 
-    if uri.is_some() {
-      _q.push(("uri", uri.unwrap_or_default()));
+    if let Some(v) = uri {
+      _q.push(("uri", v));
     }
 
-    let blob_value = serde_json::to_string(&blob)?;
+    let blob_value = blob.map(|v| v.to_string());
 
-    if blob.is_some() {
-      _q.push(("blob", blob_value.as_str()));
+    if let Some(v) = &blob_value {
+      _q.push(("blob", v.as_str()));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
   /// Get list of all communication templates.
-
   pub fn com_atproto_admin_listcommunicationtemplates(
     &self,
   ) -> Result<ComAtprotoAdminListcommunicationtemplates> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/com.atproto.admin.listCommunicationTemplates",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
-    Ok(req.call()?.into_json()?)
+    self.call(req)?.into_json()
   }
 
   /// List moderation events related to a subject.
-
   pub fn com_atproto_admin_querymoderationevents(
     &self,
     types: Option<&[&str]>,
@@ -5012,1237 +6242,247 @@ impl Client {
     report_types: Option<&[&str]>,
     cursor: Option<&str>,
   ) -> Result<ComAtprotoAdminQuerymoderationevents> {
-    let mut req = self.agent.get(&format!(
+    let req = HttpRequest::get(format!(
       "https://{}/xrpc/com.atproto.admin.queryModerationEvents",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut _q = Vec::new();
 
-    let types_value = serde_json::to_string(&types)?;
-
-    if types.is_some() {
-      _q.push(("types", types_value.as_str()));
-    };
+    if let Some(v) = types {
+      _q.extend(v.iter().map(|v| ("types", *v)));
+    }
 
-    if created_by.is_some() {
-      _q.push(("created_by", created_by.unwrap_or_default()));
-    };
+    if let Some(v) = created_by {
+      _q.push(("createdBy", v));
+    }
 
-    if sort_direction.is_some() {
-      _q.push(("sort_direction", sort_direction.unwrap_or_default()));
+    if let Some(v) = sort_direction {
+      _q.push(("sortDirection", v));
     }
 
-    let created_after_value = serde_json::to_string(&created_after)?;
+    let created_after_value = created_after.map(|v| v.to_rfc3339());
 
-    if created_after.is_some() {
-      _q.push(("created_after", created_after_value.as_str()));
+    if let Some(v) = &created_after_value {
+      _q.push(("createdAfter", v.as_str()));
     }
 
-    let created_before_value = serde_json::to_string(&created_before)?;
+    let created_before_value = created_before.map(|v| v.to_rfc3339());
 
-    if created_before.is_some() {
-      _q.push(("created_before", created_before_value.as_str()));
-    };
+    if let Some(v) = &created_before_value {
+      _q.push(("createdBefore", v.as_str()));
+    }
 
-    if subject.is_some() {
-      _q.push(("subject", subject.unwrap_or_default()));
+    if let Some(v) = subject {
+      _q.push(("subject", v));
     }
 
-    let include_all_user_records_value = serde_json::to_string(&include_all_user_records)?;
+    let include_all_user_records_value = include_all_user_records.map(|v| v.to_string());
 
-    if include_all_user_records.is_some() {
-      _q.push((
-        "include_all_user_records",
-        include_all_user_records_value.as_str(),
-      ));
+    if let Some(v) = &include_all_user_records_value {
+      _q.push(("includeAllUserRecords", v.as_str()));
     }
 
-    let limit_value = serde_json::to_string(&limit)?;
+    let limit_value = limit.map(|v| v.to_string());
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
     }
 
-    let has_comment_value = serde_json::to_string(&has_comment)?;
+    let has_comment_value = has_comment.map(|v| v.to_string());
 
-    if has_comment.is_some() {
-      _q.push(("has_comment", has_comment_value.as_str()));
-    };
+    if let Some(v) = &has_comment_value {
+      _q.push(("hasComment", v.as_str()));
+    }
 
-    if comment.is_some() {
-      _q.push(("comment", comment.unwrap_or_default()));
+    if let Some(v) = comment {
+      _q.push(("comment", v));
     }
 
-    let added_labels_value = serde_json::to_string(&added_labels)?;
+    if let Some(v) = added_labels {
+      _q.extend(v.iter().map(|v| ("addedLabels", *v)));
+    }
 
-    if added_labels.is_some() {
-      _q.push(("added_labels", added_labels_value.as_str()));
+    if let Some(v) = removed_labels {
+      _q.extend(v.iter().map(|v| ("removedLabels", *v)));
     }
 
-    let removed_labels_value = serde_json::to_string(&removed_labels)?;
-
-    if removed_labels.is_some() {
-      _q.push(("removed_labels", removed_labels_value.as_str()));
-    }
-
-    let added_tags_value = serde_json::to_string(&added_tags)?;
-
-    if added_tags.is_some() {
-      _q.push(("added_tags", added_tags_value.as_str()));
-    }
-
-    let removed_tags_value = serde_json::to_string(&removed_tags)?;
-
-    if removed_tags.is_some() {
-      _q.push(("removed_tags", removed_tags_value.as_str()));
-    }
-
-    let report_types_value = serde_json::to_string(&report_types)?;
-
-    if report_types.is_some() {
-      _q.push(("report_types", report_types_value.as_str()));
-    };
-
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
-    }
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// View moderation statuses of subjects (record or repo).
-
-  pub fn com_atproto_admin_querymoderationstatuses(
-    &self,
-    subject: Option<&str>,
-    comment: Option<&str>,
-    reported_after: Option<&DateTime<Utc>>,
-    reported_before: Option<&DateTime<Utc>>,
-    reviewed_after: Option<&DateTime<Utc>>,
-    reviewed_before: Option<&DateTime<Utc>>,
-    include_muted: Option<bool>,
-    review_state: Option<&str>,
-    ignore_subjects: Option<&[&str]>,
-    last_reviewed_by: Option<&str>,
-    sort_field: Option<&str>,
-    sort_direction: Option<&str>,
-    takendown: Option<bool>,
-    appealed: Option<bool>,
-    limit: Option<i64>,
-    tags: Option<&[&str]>,
-    exclude_tags: Option<&[&str]>,
-    cursor: Option<&str>,
-  ) -> Result<ComAtprotoAdminQuerymoderationstatuses> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.admin.queryModerationStatuses",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    if subject.is_some() {
-      _q.push(("subject", subject.unwrap_or_default()));
-    };
-
-    if comment.is_some() {
-      _q.push(("comment", comment.unwrap_or_default()));
-    }
-
-    let reported_after_value = serde_json::to_string(&reported_after)?;
-
-    if reported_after.is_some() {
-      _q.push(("reported_after", reported_after_value.as_str()));
-    }
-
-    let reported_before_value = serde_json::to_string(&reported_before)?;
-
-    if reported_before.is_some() {
-      _q.push(("reported_before", reported_before_value.as_str()));
-    }
-
-    let reviewed_after_value = serde_json::to_string(&reviewed_after)?;
-
-    if reviewed_after.is_some() {
-      _q.push(("reviewed_after", reviewed_after_value.as_str()));
-    }
-
-    let reviewed_before_value = serde_json::to_string(&reviewed_before)?;
-
-    if reviewed_before.is_some() {
-      _q.push(("reviewed_before", reviewed_before_value.as_str()));
-    }
-
-    let include_muted_value = serde_json::to_string(&include_muted)?;
-
-    if include_muted.is_some() {
-      _q.push(("include_muted", include_muted_value.as_str()));
-    };
-
-    if review_state.is_some() {
-      _q.push(("review_state", review_state.unwrap_or_default()));
-    }
-
-    let ignore_subjects_value = serde_json::to_string(&ignore_subjects)?;
-
-    if ignore_subjects.is_some() {
-      _q.push(("ignore_subjects", ignore_subjects_value.as_str()));
-    };
-
-    if last_reviewed_by.is_some() {
-      _q.push(("last_reviewed_by", last_reviewed_by.unwrap_or_default()));
-    };
-
-    if sort_field.is_some() {
-      _q.push(("sort_field", sort_field.unwrap_or_default()));
-    };
-
-    if sort_direction.is_some() {
-      _q.push(("sort_direction", sort_direction.unwrap_or_default()));
-    }
-
-    let takendown_value = serde_json::to_string(&takendown)?;
-
-    if takendown.is_some() {
-      _q.push(("takendown", takendown_value.as_str()));
-    }
-
-    let appealed_value = serde_json::to_string(&appealed)?;
-
-    if appealed.is_some() {
-      _q.push(("appealed", appealed_value.as_str()));
-    }
-
-    let limit_value = serde_json::to_string(&limit)?;
-
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    }
-
-    let tags_value = serde_json::to_string(&tags)?;
-
-    if tags.is_some() {
-      _q.push(("tags", tags_value.as_str()));
-    }
-
-    let exclude_tags_value = serde_json::to_string(&exclude_tags)?;
-
-    if exclude_tags.is_some() {
-      _q.push(("exclude_tags", exclude_tags_value.as_str()));
-    };
-
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
-    }
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// Find repositories based on a search term.
-
-  pub fn com_atproto_admin_searchrepos(
-    &self,
-    term: Option<&str>,
-    q: Option<&str>,
-    limit: Option<i64>,
-    cursor: Option<&str>,
-  ) -> Result<ComAtprotoAdminSearchrepos> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.admin.searchRepos",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    if term.is_some() {
-      _q.push(("term", term.unwrap_or_default()));
-    };
-
-    if q.is_some() {
-      _q.push(("q", q.unwrap_or_default()));
-    }
-
-    let limit_value = serde_json::to_string(&limit)?;
-
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
-
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
-    }
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// Describe the credentials that should be included in the DID doc of an account that is migrating to this service.
-
-  pub fn com_atproto_identity_getrecommendeddidcredentials(
-    &self,
-  ) -> Result<ComAtprotoIdentityGetrecommendeddidcredentials> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.identity.getRecommendedDidCredentials",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    Ok(req.call()?.into_json()?)
-  }
-
-  /// Resolves a handle (domain name) to a DID.
-
-  pub fn com_atproto_identity_resolvehandle(
-    &self,
-    handle: &str,
-  ) -> Result<ComAtprotoIdentityResolvehandle> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.identity.resolveHandle",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    _q.push(("handle", handle));
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// Find labels relevant to the provided AT-URI patterns. Public endpoint for moderation services, though may return different or additional results with auth.
-
-  pub fn com_atproto_label_querylabels(
-    &self,
-    uri_patterns: &[&str],
-    sources: Option<&[&str]>,
-    limit: Option<i64>,
-    cursor: Option<&str>,
-  ) -> Result<ComAtprotoLabelQuerylabels> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.label.queryLabels",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    let mut uri_patterns_value = uri_patterns
-      .iter()
-      .map(|i| ("uriPatterns", *i))
-      .collect::<Vec<_>>();
-
-    _q.append(&mut uri_patterns_value);
-
-    let sources_value = serde_json::to_string(&sources)?;
-
-    if sources.is_some() {
-      _q.push(("sources", sources_value.as_str()));
-    }
-
-    let limit_value = serde_json::to_string(&limit)?;
-
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
-
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
-    }
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// Get information about an account and repository, including the list of collections. Does not require auth.
-
-  pub fn com_atproto_repo_describerepo(&self, repo: &str) -> Result<ComAtprotoRepoDescriberepo> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.repo.describeRepo",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    _q.push(("repo", repo));
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// Get a single record from a repository. Does not require auth.
-
-  pub fn com_atproto_repo_getrecord(
-    &self,
-    repo: &str,
-    collection: &str,
-    rkey: &str,
-    cid: Option<&CidString>,
-  ) -> Result<ComAtprotoRepoGetrecord> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.repo.getRecord",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    _q.push(("repo", repo));
-
-    _q.push(("collection", collection));
-
-    _q.push(("rkey", rkey));
-
-    let cid_value = serde_json::to_string(&cid)?;
-
-    if cid.is_some() {
-      _q.push(("cid", cid_value.as_str()));
-    }
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// Returns a list of missing blobs for the requesting account. Intended to be used in the account migration flow.
-
-  pub fn com_atproto_repo_listmissingblobs(
-    &self,
-    limit: Option<i64>,
-    cursor: Option<&str>,
-  ) -> Result<ComAtprotoRepoListmissingblobs> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.repo.listMissingBlobs",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    let limit_value = serde_json::to_string(&limit)?;
-
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
-
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
-    }
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// List a range of records in a repository, matching a specific collection. Does not require auth.
-
-  pub fn com_atproto_repo_listrecords(
-    &self,
-    repo: &str,
-    collection: &str,
-    limit: Option<i64>,
-    cursor: Option<&str>,
-    rkey_start: Option<&str>,
-    rkey_end: Option<&str>,
-    reverse: Option<bool>,
-  ) -> Result<ComAtprotoRepoListrecords> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.repo.listRecords",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    _q.push(("repo", repo));
-
-    _q.push(("collection", collection));
-
-    let limit_value = serde_json::to_string(&limit)?;
-
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
-
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
-    };
-
-    if rkey_start.is_some() {
-      _q.push(("rkey_start", rkey_start.unwrap_or_default()));
-    };
-
-    if rkey_end.is_some() {
-      _q.push(("rkey_end", rkey_end.unwrap_or_default()));
-    }
-
-    let reverse_value = serde_json::to_string(&reverse)?;
-
-    if reverse.is_some() {
-      _q.push(("reverse", reverse_value.as_str()));
-    }
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// Returns the status of an account, especially as pertaining to import or recovery. Can be called many times over the course of an account migration. Requires auth and can only be called pertaining to oneself.
-
-  pub fn com_atproto_server_checkaccountstatus(
-    &self,
-  ) -> Result<ComAtprotoServerCheckaccountstatus> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.server.checkAccountStatus",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    Ok(req.call()?.into_json()?)
-  }
-
-  /// Describes the server&#39;s account creation requirements and capabilities. Implemented by PDS.
-
-  pub fn com_atproto_server_describeserver(&self) -> Result<ComAtprotoServerDescribeserver> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.server.describeServer",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    Ok(req.call()?.into_json()?)
-  }
-
-  /// Get all invite codes for the current account. Requires auth.
-
-  pub fn com_atproto_server_getaccountinvitecodes(
-    &self,
-    include_used: Option<bool>,
-    create_available: Option<bool>,
-  ) -> Result<ComAtprotoServerGetaccountinvitecodes> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.server.getAccountInviteCodes",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    let include_used_value = serde_json::to_string(&include_used)?;
-
-    if include_used.is_some() {
-      _q.push(("include_used", include_used_value.as_str()));
-    }
-
-    let create_available_value = serde_json::to_string(&create_available)?;
-
-    if create_available.is_some() {
-      _q.push(("create_available", create_available_value.as_str()));
-    }
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// Get a signed token on behalf of the requesting DID for the requested service.
-
-  pub fn com_atproto_server_getserviceauth(
-    &self,
-    aud: &str,
-  ) -> Result<ComAtprotoServerGetserviceauth> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.server.getServiceAuth",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    _q.push(("aud", aud));
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// Get information about the current auth session. Requires auth.
-
-  pub fn com_atproto_server_getsession(&self) -> Result<ComAtprotoServerGetsession> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.server.getSession",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    Ok(req.call()?.into_json()?)
-  }
-
-  /// List all App Passwords.
-
-  pub fn com_atproto_server_listapppasswords(&self) -> Result<ComAtprotoServerListapppasswords> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.server.listAppPasswords",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    Ok(req.call()?.into_json()?)
-  }
-
-  /// Get a blob associated with a given account. Returns the full blob as originally uploaded. Does not require auth; implemented by PDS.
-
-  pub fn com_atproto_sync_getblob(&self, did: &str, cid: &CidString) -> Result<Vec<u8>> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.sync.getBlob",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    _q.push(("did", did));
-
-    let cid_value = serde_json::to_string(&cid)?;
-
-    _q.push(("cid", cid_value.as_str()));
-
-    let mut ret = Vec::new();
-    req
-      .query_pairs(_q)
-      .call()?
-      .into_reader()
-      .read_to_end(&mut ret)?;
-    Ok(ret)
-  }
-
-  /// Get data blocks from a given repo, by CID. For example, intermediate MST nodes, or records. Does not require auth; implemented by PDS.
-
-  pub fn com_atproto_sync_getblocks(&self, did: &str, cids: &[&CidString]) -> Result<Blocks> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.sync.getBlocks",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    _q.push(("did", did));
-
-    let cids_value = serde_json::to_string(&cids)?;
-
-    _q.push(("cids", cids_value.as_str()));
-
-    let mut ret = Vec::new();
-    req
-      .query_pairs(_q)
-      .call()?
-      .into_reader()
-      .read_to_end(&mut ret)?;
-
-    Ok(Blocks::from(ret.as_slice()))
-  }
-
-  /// DEPRECATED - please use com.atproto.sync.getRepo instead
-
-  pub fn com_atproto_sync_getcheckout(&self, did: &str) -> Result<Blocks> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.sync.getCheckout",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    _q.push(("did", did));
-
-    let mut ret = Vec::new();
-    req
-      .query_pairs(_q)
-      .call()?
-      .into_reader()
-      .read_to_end(&mut ret)?;
-
-    Ok(Blocks::from(ret.as_slice()))
-  }
-
-  /// DEPRECATED - please use com.atproto.sync.getLatestCommit instead
-
-  pub fn com_atproto_sync_gethead(&self, did: &str) -> Result<ComAtprotoSyncGethead> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.sync.getHead",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    _q.push(("did", did));
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// Get the current commit CID &amp; revision of the specified repo. Does not require auth.
-
-  pub fn com_atproto_sync_getlatestcommit(
-    &self,
-    did: &str,
-  ) -> Result<ComAtprotoSyncGetlatestcommit> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.sync.getLatestCommit",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    _q.push(("did", did));
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// Get data blocks needed to prove the existence or non-existence of record in the current version of repo. Does not require auth.
-
-  pub fn com_atproto_sync_getrecord(
-    &self,
-    did: &str,
-    collection: &str,
-    rkey: &str,
-    commit: Option<&CidString>,
-  ) -> Result<Blocks> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.sync.getRecord",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    _q.push(("did", did));
-
-    _q.push(("collection", collection));
-
-    _q.push(("rkey", rkey));
-
-    let commit_value = serde_json::to_string(&commit)?;
-
-    if commit.is_some() {
-      _q.push(("commit", commit_value.as_str()));
-    }
-
-    let mut ret = Vec::new();
-    req
-      .query_pairs(_q)
-      .call()?
-      .into_reader()
-      .read_to_end(&mut ret)?;
-
-    Ok(Blocks::from(ret.as_slice()))
-  }
-
-  /// Download a repository export as CAR file. Optionally only a &#39;diff&#39; since a previous revision. Does not require auth; implemented by PDS.
-
-  pub fn com_atproto_sync_getrepo(&self, did: &str, since: Option<&str>) -> Result<Blocks> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.sync.getRepo",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    _q.push(("did", did));
-
-    if since.is_some() {
-      _q.push(("since", since.unwrap_or_default()));
-    }
-
-    let mut ret = Vec::new();
-    req
-      .query_pairs(_q)
-      .call()?
-      .into_reader()
-      .read_to_end(&mut ret)?;
-
-    Ok(Blocks::from(ret.as_slice()))
-  }
-
-  /// List blob CIDso for an account, since some repo revision. Does not require auth; implemented by PDS.
-
-  pub fn com_atproto_sync_listblobs(
-    &self,
-    did: &str,
-    since: Option<&str>,
-    limit: Option<i64>,
-    cursor: Option<&str>,
-  ) -> Result<ComAtprotoSyncListblobs> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.sync.listBlobs",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    _q.push(("did", did));
-
-    if since.is_some() {
-      _q.push(("since", since.unwrap_or_default()));
-    }
-
-    let limit_value = serde_json::to_string(&limit)?;
-
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
-
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
-    }
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// Enumerates all the DID, rev, and commit CID for all repos hosted by this service. Does not require auth; implemented by PDS and Relay.
-
-  pub fn com_atproto_sync_listrepos(
-    &self,
-    limit: Option<i64>,
-    cursor: Option<&str>,
-  ) -> Result<ComAtprotoSyncListrepos> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.sync.listRepos",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    let limit_value = serde_json::to_string(&limit)?;
-
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
-    };
-
-    if cursor.is_some() {
-      _q.push(("cursor", cursor.unwrap_or_default()));
-    }
-
-    Ok(req.query_pairs(_q).call()?.into_json()?)
-  }
-
-  /// Check accounts location in signup queue.
-
-  pub fn com_atproto_temp_checksignupqueue(&self) -> Result<ComAtprotoTempChecksignupqueue> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.temp.checkSignupQueue",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    Ok(req.call()?.into_json()?)
-  }
-
-  /// DEPRECATED: use queryLabels or subscribeLabels instead -- Fetch all labels from a labeler created after a certain date.
-
-  pub fn com_atproto_temp_fetchlabels(
-    &self,
-    since: Option<i64>,
-    limit: Option<i64>,
-  ) -> Result<ComAtprotoTempFetchlabels> {
-    let mut req = self.agent.get(&format!(
-      "https://{}/xrpc/com.atproto.temp.fetchLabels",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut _q = Vec::new();
-
-    let since_value = serde_json::to_string(&since)?;
+    if let Some(v) = added_tags {
+      _q.extend(v.iter().map(|v| ("addedTags", *v)));
+    }
 
-    if since.is_some() {
-      _q.push(("since", since_value.as_str()));
+    if let Some(v) = removed_tags {
+      _q.extend(v.iter().map(|v| ("removedTags", *v)));
     }
 
-    let limit_value = serde_json::to_string(&limit)?;
+    if let Some(v) = report_types {
+      _q.extend(v.iter().map(|v| ("reportTypes", *v)));
+    }
 
-    if limit.is_some() {
-      _q.push(("limit", limit_value.as_str()));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.query_pairs(_q).call()?.into_json()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// Set the private preferences attached to the account.
-
-  pub fn app_bsky_actor_putpreferences(
+  /// View moderation statuses of subjects (record or repo).
+  pub fn com_atproto_admin_querymoderationstatuses(
     &self,
-    preferences: &AppBskyActorDefsPreferences,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/app.bsky.actor.putPreferences",
+    subject: Option<&str>,
+    comment: Option<&str>,
+    reported_after: Option<&DateTime<Utc>>,
+    reported_before: Option<&DateTime<Utc>>,
+    reviewed_after: Option<&DateTime<Utc>>,
+    reviewed_before: Option<&DateTime<Utc>>,
+    include_muted: Option<bool>,
+    review_state: Option<&str>,
+    ignore_subjects: Option<&[&str]>,
+    last_reviewed_by: Option<&str>,
+    sort_field: Option<&str>,
+    sort_direction: Option<&str>,
+    takendown: Option<bool>,
+    appealed: Option<bool>,
+    limit: Option<i64>,
+    tags: Option<&[&str]>,
+    exclude_tags: Option<&[&str]>,
+    cursor: Option<&str>,
+  ) -> Result<ComAtprotoAdminQuerymoderationstatuses> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.admin.queryModerationStatuses",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
-    let mut input = serde_json::Map::new();
-
-    input.insert(String::from("preferences"), json!(preferences));
-
-    Ok(req.send_json(json!(input))?)
-  }
-
-  /// Creates a mute relationship for the specified account. Mutes are private in Bluesky. Requires auth.
+    let mut _q = Vec::new();
 
-  pub fn app_bsky_graph_muteactor(&self, actor: &str) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/app.bsky.graph.muteActor",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+    if let Some(v) = subject {
+      _q.push(("subject", v));
     }
 
-    let mut input = serde_json::Map::new();
-
-    input.insert(String::from("actor"), json!(actor));
-
-    Ok(req.send_json(json!(input))?)
-  }
-
-  /// Creates a mute relationship for the specified list of accounts. Mutes are private in Bluesky. Requires auth.
-
-  pub fn app_bsky_graph_muteactorlist(&self, list: &str) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/app.bsky.graph.muteActorList",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+    if let Some(v) = comment {
+      _q.push(("comment", v));
     }
 
-    let mut input = serde_json::Map::new();
-
-    input.insert(String::from("list"), json!(list));
-
-    Ok(req.send_json(json!(input))?)
-  }
-
-  /// Unmutes the specified account. Requires auth.
+    let reported_after_value = reported_after.map(|v| v.to_rfc3339());
 
-  pub fn app_bsky_graph_unmuteactor(&self, actor: &str) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/app.bsky.graph.unmuteActor",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+    if let Some(v) = &reported_after_value {
+      _q.push(("reportedAfter", v.as_str()));
     }
 
-    let mut input = serde_json::Map::new();
-
-    input.insert(String::from("actor"), json!(actor));
-
-    Ok(req.send_json(json!(input))?)
-  }
-
-  /// Unmutes the specified list of accounts. Requires auth.
+    let reported_before_value = reported_before.map(|v| v.to_rfc3339());
 
-  pub fn app_bsky_graph_unmuteactorlist(&self, list: &str) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/app.bsky.graph.unmuteActorList",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+    if let Some(v) = &reported_before_value {
+      _q.push(("reportedBefore", v.as_str()));
     }
 
-    let mut input = serde_json::Map::new();
-
-    input.insert(String::from("list"), json!(list));
-
-    Ok(req.send_json(json!(input))?)
-  }
-
-  /// Register to receive push notifications, via a specified service, for the requesting account. Requires auth.
+    let reviewed_after_value = reviewed_after.map(|v| v.to_rfc3339());
 
-  pub fn app_bsky_notification_registerpush(
-    &self,
-    service_did: &str,
-    token: &str,
-    platform: &str,
-    app_id: &str,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/app.bsky.notification.registerPush",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+    if let Some(v) = &reviewed_after_value {
+      _q.push(("reviewedAfter", v.as_str()));
     }
 
-    let mut input = serde_json::Map::new();
-
-    input.insert(String::from("service_did"), json!(service_did));
-
-    input.insert(String::from("token"), json!(token));
-
-    input.insert(String::from("platform"), json!(platform));
-
-    input.insert(String::from("app_id"), json!(app_id));
-
-    Ok(req.send_json(json!(input))?)
-  }
-
-  /// Notify server that the requesting account has seen notifications. Requires auth.
+    let reviewed_before_value = reviewed_before.map(|v| v.to_rfc3339());
 
-  pub fn app_bsky_notification_updateseen(
-    &self,
-    seen_at: &DateTime<Utc>,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/app.bsky.notification.updateSeen",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+    if let Some(v) = &reviewed_before_value {
+      _q.push(("reviewedBefore", v.as_str()));
     }
 
-    let mut input = serde_json::Map::new();
-
-    input.insert(String::from("seen_at"), json!(seen_at));
-
-    Ok(req.send_json(json!(input))?)
-  }
-
-  /// Administrative action to create a new, re-usable communication (email for now) template.
+    let include_muted_value = include_muted.map(|v| v.to_string());
 
-  pub fn com_atproto_admin_createcommunicationtemplate(
-    &self,
-    name: &str,
-    content_markdown: &str,
-    subject: &str,
-    created_by: Option<&str>,
-  ) -> Result<ComAtprotoAdminDefsCommunicationtemplateview> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/com.atproto.admin.createCommunicationTemplate",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+    if let Some(v) = &include_muted_value {
+      _q.push(("includeMuted", v.as_str()));
     }
 
-    let mut input = serde_json::Map::new();
-
-    input.insert(String::from("name"), json!(name));
-
-    input.insert(String::from("content_markdown"), json!(content_markdown));
-
-    input.insert(String::from("subject"), json!(subject));
-
-    if let Some(v) = &created_by {
-      input.insert(String::from("created_by"), json!(v));
+    if let Some(v) = review_state {
+      _q.push(("reviewState", v));
     }
 
-    Ok(req.send_json(json!(input))?.into_json()?)
-  }
-
-  /// Delete a user account as an administrator.
-
-  pub fn com_atproto_admin_deleteaccount(&self, did: &str) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/com.atproto.admin.deleteAccount",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+    if let Some(v) = ignore_subjects {
+      _q.extend(v.iter().map(|v| ("ignoreSubjects", *v)));
     }
 
-    let mut input = serde_json::Map::new();
-
-    input.insert(String::from("did"), json!(did));
-
-    Ok(req.send_json(json!(input))?)
-  }
-
-  /// Delete a communication template.
-
-  pub fn com_atproto_admin_deletecommunicationtemplate(&self, id: &str) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/com.atproto.admin.deleteCommunicationTemplate",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+    if let Some(v) = last_reviewed_by {
+      _q.push(("lastReviewedBy", v));
     }
 
-    let mut input = serde_json::Map::new();
-
-    input.insert(String::from("id"), json!(id));
+    if let Some(v) = sort_field {
+      _q.push(("sortField", v));
+    }
 
-    Ok(req.send_json(json!(input))?)
-  }
+    if let Some(v) = sort_direction {
+      _q.push(("sortDirection", v));
+    }
 
-  /// Disable an account from receiving new invite codes, but does not invalidate existing codes.
+    let takendown_value = takendown.map(|v| v.to_string());
 
-  pub fn com_atproto_admin_disableaccountinvites(
-    &self,
-    account: &str,
-    note: Option<&str>,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/com.atproto.admin.disableAccountInvites",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+    if let Some(v) = &takendown_value {
+      _q.push(("takendown", v.as_str()));
     }
 
-    let mut input = serde_json::Map::new();
-
-    input.insert(String::from("account"), json!(account));
+    let appealed_value = appealed.map(|v| v.to_string());
 
-    if let Some(v) = &note {
-      input.insert(String::from("note"), json!(v));
+    if let Some(v) = &appealed_value {
+      _q.push(("appealed", v.as_str()));
     }
 
-    Ok(req.send_json(json!(input))?)
-  }
-
-  /// Disable some set of codes and/or all codes associated with a set of users.
+    let limit_value = limit.map(|v| v.to_string());
 
-  pub fn com_atproto_admin_disableinvitecodes(
-    &self,
-    codes: Option<&[&str]>,
-    accounts: Option<&[&str]>,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/com.atproto.admin.disableInviteCodes",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
     }
 
-    let mut input = serde_json::Map::new();
+    if let Some(v) = tags {
+      _q.extend(v.iter().map(|v| ("tags", *v)));
+    }
 
-    if let Some(v) = &codes {
-      input.insert(String::from("codes"), json!(v));
+    if let Some(v) = exclude_tags {
+      _q.extend(v.iter().map(|v| ("excludeTags", *v)));
     }
 
-    if let Some(v) = &accounts {
-      input.insert(String::from("accounts"), json!(v));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.send_json(json!(input))?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// Take a moderation action on an actor.
-
-  pub fn com_atproto_admin_emitmoderationevent(
+  /// Find repositories based on a search term.
+  pub fn com_atproto_admin_searchrepos(
     &self,
-    event: ComAtprotoAdminEmitmoderationeventMainInputEvent,
-    subject: ComAtprotoAdminEmitmoderationeventMainInputSubject,
-    created_by: &str,
-    subject_blob_cids: Option<&[&CidString]>,
-  ) -> Result<ComAtprotoAdminDefsModeventview> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/com.atproto.admin.emitModerationEvent",
+    term: Option<&str>,
+    q: Option<&str>,
+    limit: Option<i64>,
+    cursor: Option<&str>,
+  ) -> Result<ComAtprotoAdminSearchrepos> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.admin.searchRepos",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
-
-    let mut input = serde_json::Map::new();
-
-    input.insert(String::from("event"), json!(event));
 
-    input.insert(String::from("subject"), json!(subject));
-
-    input.insert(String::from("created_by"), json!(created_by));
+    let mut _q = Vec::new();
 
-    if let Some(v) = &subject_blob_cids {
-      input.insert(String::from("subject_blob_cids"), json!(v));
+    if let Some(v) = term {
+      _q.push(("term", v));
     }
 
-    Ok(req.send_json(json!(input))?.into_json()?)
-  }
-
-  /// Re-enable an account&#39;s ability to receive invite codes.
-
-  pub fn com_atproto_admin_enableaccountinvites(
-    &self,
-    account: &str,
-    note: Option<&str>,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/com.atproto.admin.enableAccountInvites",
-      self.host
-    ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+    if let Some(v) = q {
+      _q.push(("q", v));
     }
 
-    let mut input = serde_json::Map::new();
+    let limit_value = limit.map(|v| v.to_string());
 
-    input.insert(String::from("account"), json!(account));
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
-    if let Some(v) = &note {
-      input.insert(String::from("note"), json!(v));
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.send_json(json!(input))?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// Send email to a user&#39;s account email address.
-
+  /// Send email to a user's account email address.
   pub fn com_atproto_admin_sendemail(
     &self,
     recipient_did: &str,
@@ -6251,21 +6491,18 @@ impl Client {
     subject: Option<&str>,
     comment: Option<&str>,
   ) -> Result<ComAtprotoAdminSendemail> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.admin.sendEmail",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
-    input.insert(String::from("recipient_did"), json!(recipient_did));
+    input.insert(String::from("recipientDid"), json!(recipient_did));
 
     input.insert(String::from("content"), json!(content));
 
-    input.insert(String::from("sender_did"), json!(sender_did));
+    input.insert(String::from("senderDid"), json!(sender_did));
 
     if let Some(v) = &subject {
       input.insert(String::from("subject"), json!(v));
@@ -6275,23 +6512,19 @@ impl Client {
       input.insert(String::from("comment"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?.into_json()?)
+    self.send_json(req, json!(input))?.into_json()
   }
 
-  /// Administrative action to update an account&#39;s email.
-
+  /// Administrative action to update an account's email.
   pub fn com_atproto_admin_updateaccountemail(
     &self,
     account: &str,
     email: &str,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.admin.updateAccountEmail",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6299,23 +6532,19 @@ impl Client {
 
     input.insert(String::from("email"), json!(email));
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
-  /// Administrative action to update an account&#39;s handle.
-
+  /// Administrative action to update an account's handle.
   pub fn com_atproto_admin_updateaccounthandle(
     &self,
     did: &str,
     handle: &str,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.admin.updateAccountHandle",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6323,23 +6552,19 @@ impl Client {
 
     input.insert(String::from("handle"), json!(handle));
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
   /// Update the password for a user account as an administrator.
-
   pub fn com_atproto_admin_updateaccountpassword(
     &self,
     did: &str,
     password: &str,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.admin.updateAccountPassword",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6347,11 +6572,10 @@ impl Client {
 
     input.insert(String::from("password"), json!(password));
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
   /// Administrative action to update an existing communication template. Allows passing partial fields to patch specific fields only.
-
   pub fn com_atproto_admin_updatecommunicationtemplate(
     &self,
     id: &str,
@@ -6361,13 +6585,10 @@ impl Client {
     updated_by: Option<&str>,
     disabled: Option<bool>,
   ) -> Result<ComAtprotoAdminDefsCommunicationtemplateview> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.admin.updateCommunicationTemplate",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6378,7 +6599,7 @@ impl Client {
     }
 
     if let Some(v) = &content_markdown {
-      input.insert(String::from("content_markdown"), json!(v));
+      input.insert(String::from("contentMarkdown"), json!(v));
     }
 
     if let Some(v) = &subject {
@@ -6386,30 +6607,26 @@ impl Client {
     }
 
     if let Some(v) = &updated_by {
-      input.insert(String::from("updated_by"), json!(v));
+      input.insert(String::from("updatedBy"), json!(v));
     }
 
     if let Some(v) = &disabled {
       input.insert(String::from("disabled"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?.into_json()?)
+    self.send_json(req, json!(input))?.into_json()
   }
 
   /// Update the service-specific admin status of a subject (account, record, or blob).
-
   pub fn com_atproto_admin_updatesubjectstatus(
     &self,
     subject: ComAtprotoAdminUpdatesubjectstatusMainInputSubject,
     takedown: Option<&ComAtprotoAdminDefsStatusattr>,
   ) -> Result<ComAtprotoAdminUpdatesubjectstatus> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.admin.updateSubjectStatus",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6419,25 +6636,49 @@ impl Client {
       input.insert(String::from("takedown"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?.into_json()?)
+    self.send_json(req, json!(input))?.into_json()
+  }
+
+  /// Describe the credentials that should be included in the DID doc of an account that is migrating to this service.
+  pub fn com_atproto_identity_getrecommendeddidcredentials(
+    &self,
+  ) -> Result<ComAtprotoIdentityGetrecommendeddidcredentials> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.identity.getRecommendedDidCredentials",
+      self.host
+    ));
+
+    self.call(req)?.into_json()
   }
 
   /// Request an email with a code to in order to request a signed PLC operation. Requires Auth.
-
-  pub fn com_atproto_identity_requestplcoperationsignature(&self) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  pub fn com_atproto_identity_requestplcoperationsignature(&self) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.identity.requestPlcOperationSignature",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
-    Ok(req.call()?)
+    self.call(req)
   }
 
-  /// Signs a PLC operation to update some value(s) in the requesting DID&#39;s document.
+  /// Resolves a handle (domain name) to a DID.
+  pub fn com_atproto_identity_resolvehandle(
+    &self,
+    handle: &str,
+  ) -> Result<ComAtprotoIdentityResolvehandle> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.identity.resolveHandle",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("handle", handle));
+
+    self.call(req.query_pairs(_q))?.into_json()
+  }
 
+  /// Signs a PLC operation to update some value(s) in the requesting DID's document.
   pub fn com_atproto_identity_signplcoperation(
     &self,
     token: Option<&str>,
@@ -6446,13 +6687,10 @@ impl Client {
     verification_methods: Option<&Record>,
     services: Option<&Record>,
   ) -> Result<ComAtprotoIdentitySignplcoperation> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.identity.signPlcOperation",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6461,82 +6699,104 @@ impl Client {
     }
 
     if let Some(v) = &rotation_keys {
-      input.insert(String::from("rotation_keys"), json!(v));
+      input.insert(String::from("rotationKeys"), json!(v));
     }
 
     if let Some(v) = &also_known_as {
-      input.insert(String::from("also_known_as"), json!(v));
+      input.insert(String::from("alsoKnownAs"), json!(v));
     }
 
     if let Some(v) = &verification_methods {
-      input.insert(String::from("verification_methods"), json!(v));
+      input.insert(String::from("verificationMethods"), json!(v));
     }
 
     if let Some(v) = &services {
       input.insert(String::from("services"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?.into_json()?)
+    self.send_json(req, json!(input))?.into_json()
   }
 
-  /// Validates a PLC operation to ensure that it doesn&#39;t violate a service&#39;s constraints or get the identity into a bad state, then submits it to the PLC registry
-
+  /// Validates a PLC operation to ensure that it doesn't violate a service's constraints or get the identity into a bad state, then submits it to the PLC registry
   pub fn com_atproto_identity_submitplcoperation(
     &self,
     operation: &Record,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.identity.submitPlcOperation",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
     input.insert(String::from("operation"), json!(operation));
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
-  /// Updates the current account&#39;s handle. Verifies handle validity, and updates did:plc document if necessary. Implemented by PDS, and requires auth.
-
-  pub fn com_atproto_identity_updatehandle(&self, handle: &str) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  /// Updates the current account's handle. Verifies handle validity, and updates did:plc document if necessary. Implemented by PDS, and requires auth.
+  pub fn com_atproto_identity_updatehandle(&self, handle: &str) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.identity.updateHandle",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
     input.insert(String::from("handle"), json!(handle));
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
-  /// Submit a moderation report regarding an atproto account or record. Implemented by moderation services (with PDS proxying), and requires auth.
+  /// Find labels relevant to the provided AT-URI patterns. Public endpoint for moderation services, though may return different or additional results with auth.
+  pub fn com_atproto_label_querylabels(
+    &self,
+    uri_patterns: &[&str],
+    sources: Option<&[&str]>,
+    limit: Option<i64>,
+    cursor: Option<&str>,
+  ) -> Result<ComAtprotoLabelQuerylabels> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.label.queryLabels",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.extend(uri_patterns.iter().map(|v| ("uriPatterns", *v)));
 
+    if let Some(v) = sources {
+      _q.extend(v.iter().map(|v| ("sources", *v)));
+    }
+
+    let limit_value = limit.map(|v| v.to_string());
+
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
+
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
+    }
+
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// Submit a moderation report regarding an atproto account or record. Implemented by moderation services (with PDS proxying), and requires auth.
   pub fn com_atproto_moderation_createreport(
     &self,
     reason_type: &ComAtprotoModerationDefsReasontype,
     subject: ComAtprotoModerationCreatereportMainInputSubject,
     reason: Option<&str>,
   ) -> Result<ComAtprotoModerationCreatereport> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.moderation.createReport",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
-    input.insert(String::from("reason_type"), json!(reason_type));
+    input.insert(String::from("reasonType"), json!(reason_type));
 
     input.insert(String::from("subject"), json!(subject));
 
@@ -6544,25 +6804,21 @@ impl Client {
       input.insert(String::from("reason"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?.into_json()?)
+    self.send_json(req, json!(input))?.into_json()
   }
 
   /// Apply a batch transaction of repository creates, updates, and deletes. Requires auth, implemented by PDS.
-
   pub fn com_atproto_repo_applywrites(
     &self,
     repo: &str,
     writes: &[&ComAtprotoRepoApplywritesMainInputWritesItem],
     validate: Option<bool>,
     swap_commit: Option<&CidString>,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.repo.applyWrites",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6575,14 +6831,13 @@ impl Client {
     }
 
     if let Some(v) = &swap_commit {
-      input.insert(String::from("swap_commit"), json!(v));
+      input.insert(String::from("swapCommit"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
   /// Create a single new repository record. Requires auth, implemented by PDS.
-
   pub fn com_atproto_repo_createrecord(
     &self,
     repo: &str,
@@ -6592,13 +6847,10 @@ impl Client {
     validate: Option<bool>,
     swap_commit: Option<&CidString>,
   ) -> Result<ComAtprotoRepoCreaterecord> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.repo.createRecord",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6617,14 +6869,13 @@ impl Client {
     }
 
     if let Some(v) = &swap_commit {
-      input.insert(String::from("swap_commit"), json!(v));
+      input.insert(String::from("swapCommit"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?.into_json()?)
+    self.send_json(req, json!(input))?.into_json()
   }
 
-  /// Delete a repository record, or ensure it doesn&#39;t exist. Requires auth, implemented by PDS.
-
+  /// Delete a repository record, or ensure it doesn't exist. Requires auth, implemented by PDS.
   pub fn com_atproto_repo_deleterecord(
     &self,
     repo: &str,
@@ -6632,14 +6883,11 @@ impl Client {
     rkey: &str,
     swap_record: Option<&CidString>,
     swap_commit: Option<&CidString>,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.repo.deleteRecord",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6650,32 +6898,148 @@ impl Client {
     input.insert(String::from("rkey"), json!(rkey));
 
     if let Some(v) = &swap_record {
-      input.insert(String::from("swap_record"), json!(v));
+      input.insert(String::from("swapRecord"), json!(v));
     }
 
     if let Some(v) = &swap_commit {
-      input.insert(String::from("swap_commit"), json!(v));
+      input.insert(String::from("swapCommit"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
-  /// Import a repo in the form of a CAR file. Requires Content-Length HTTP header to be set.
+  /// Get information about an account and repository, including the list of collections. Does not require auth.
+  pub fn com_atproto_repo_describerepo(&self, repo: &str) -> Result<ComAtprotoRepoDescriberepo> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.repo.describeRepo",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("repo", repo));
+
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// Get a single record from a repository. Does not require auth.
+  pub fn com_atproto_repo_getrecord(
+    &self,
+    repo: &str,
+    collection: &str,
+    rkey: &str,
+    cid: Option<&CidString>,
+  ) -> Result<ComAtprotoRepoGetrecord> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.repo.getRecord",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("repo", repo));
+
+    _q.push(("collection", collection));
+
+    _q.push(("rkey", rkey));
+
+    let cid_value = cid.map(|v| v.to_string());
+
+    if let Some(v) = &cid_value {
+      _q.push(("cid", v.as_str()));
+    }
 
-  pub fn com_atproto_repo_importrepo(&self) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// Import a repo in the form of a CAR file. Requires Content-Length HTTP header to be set.
+  pub fn com_atproto_repo_importrepo(&self, car: &[u8]) -> Result<HttpResponse> {
+    let mut req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.repo.importRepo",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+
+    req = req.set("Content-Type", "application/vnd.ipld.car");
+
+    self.send_bytes(req, car)
+  }
+
+  /// Returns a list of missing blobs for the requesting account. Intended to be used in the account migration flow.
+  pub fn com_atproto_repo_listmissingblobs(
+    &self,
+    limit: Option<i64>,
+    cursor: Option<&str>,
+  ) -> Result<ComAtprotoRepoListmissingblobs> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.repo.listMissingBlobs",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    let limit_value = limit.map(|v| v.to_string());
+
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
+
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
-    Ok(req.call()?)
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// Write a repository record, creating or updating it as needed. Requires auth, implemented by PDS.
+  /// List a range of records in a repository, matching a specific collection. Does not require auth.
+  pub fn com_atproto_repo_listrecords(
+    &self,
+    repo: &str,
+    collection: &str,
+    limit: Option<i64>,
+    cursor: Option<&str>,
+    rkey_start: Option<&str>,
+    rkey_end: Option<&str>,
+    reverse: Option<bool>,
+  ) -> Result<ComAtprotoRepoListrecords> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.repo.listRecords",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("repo", repo));
+
+    _q.push(("collection", collection));
+
+    let limit_value = limit.map(|v| v.to_string());
+
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
+
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
+    }
+
+    if let Some(v) = rkey_start {
+      _q.push(("rkeyStart", v));
+    }
+
+    if let Some(v) = rkey_end {
+      _q.push(("rkeyEnd", v));
+    }
+
+    let reverse_value = reverse.map(|v| v.to_string());
+
+    if let Some(v) = &reverse_value {
+      _q.push(("reverse", v.as_str()));
+    }
 
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// Write a repository record, creating or updating it as needed. Requires auth, implemented by PDS.
   pub fn com_atproto_repo_putrecord(
     &self,
     repo: &str,
@@ -6686,13 +7050,10 @@ impl Client {
     swap_record: Option<&CidString>,
     swap_commit: Option<&CidString>,
   ) -> Result<ComAtprotoRepoPutrecord> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.repo.putRecord",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6709,64 +7070,60 @@ impl Client {
     }
 
     if let Some(v) = &swap_record {
-      input.insert(String::from("swap_record"), json!(v));
+      input.insert(String::from("swapRecord"), json!(v));
     }
 
     if let Some(v) = &swap_commit {
-      input.insert(String::from("swap_commit"), json!(v));
+      input.insert(String::from("swapCommit"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?.into_json()?)
+    self.send_json(req, json!(input))?.into_json()
   }
 
   /// Upload a new blob, to be referenced from a repository record. The blob will be deleted if it is not referenced within a time window (eg, minutes). Blob restrictions (mimetype, size, etc) are enforced when the reference is created. Requires auth, implemented by PDS.
-
   pub fn com_atproto_repo_uploadblob(
     &self,
     bytes: &[u8],
     content_type: &str,
   ) -> Result<ComAtprotoRepoUploadblob> {
-    let mut req = self.agent.post(&format!(
+    let mut req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.repo.uploadBlob",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     req = req.set("Content-Type", content_type);
 
-    Ok(req.send_bytes(bytes)?.into_json()?)
+    self.send_bytes(req, bytes)?.into_json()
   }
 
-  /// Activates a currently deactivated account. Used to finalize account migration after the account&#39;s repo is imported and identity is setup.
-
-  pub fn com_atproto_server_activateaccount(&self) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  /// Activates a currently deactivated account. Used to finalize account migration after the account's repo is imported and identity is setup.
+  pub fn com_atproto_server_activateaccount(&self) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.activateAccount",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
-    Ok(req.call()?)
+    self.call(req)
   }
 
-  /// Confirm an email using a token from com.atproto.server.requestEmailConfirmation.
-
-  pub fn com_atproto_server_confirmemail(
+  /// Returns the status of an account, especially as pertaining to import or recovery. Can be called many times over the course of an account migration. Requires auth and can only be called pertaining to oneself.
+  pub fn com_atproto_server_checkaccountstatus(
     &self,
-    email: &str,
-    token: &str,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  ) -> Result<ComAtprotoServerCheckaccountstatus> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.server.checkAccountStatus",
+      self.host
+    ));
+
+    self.call(req)?.into_json()
+  }
+
+  /// Confirm an email using a token from com.atproto.server.requestEmailConfirmation.
+  pub fn com_atproto_server_confirmemail(&self, email: &str, token: &str) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.confirmEmail",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6774,11 +7131,10 @@ impl Client {
 
     input.insert(String::from("token"), json!(token));
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
   /// Create an account. Implemented by PDS.
-
   pub fn com_atproto_server_createaccount(
     &self,
     handle: &str,
@@ -6791,13 +7147,10 @@ impl Client {
     recovery_key: Option<&str>,
     plc_op: Option<&Record>,
   ) -> Result<ComAtprotoServerCreateaccount> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.createAccount",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6812,15 +7165,15 @@ impl Client {
     }
 
     if let Some(v) = &invite_code {
-      input.insert(String::from("invite_code"), json!(v));
+      input.insert(String::from("inviteCode"), json!(v));
     }
 
     if let Some(v) = &verification_code {
-      input.insert(String::from("verification_code"), json!(v));
+      input.insert(String::from("verificationCode"), json!(v));
     }
 
     if let Some(v) = &verification_phone {
-      input.insert(String::from("verification_phone"), json!(v));
+      input.insert(String::from("verificationPhone"), json!(v));
     }
 
     if let Some(v) = &password {
@@ -6828,106 +7181,90 @@ impl Client {
     }
 
     if let Some(v) = &recovery_key {
-      input.insert(String::from("recovery_key"), json!(v));
+      input.insert(String::from("recoveryKey"), json!(v));
     }
 
     if let Some(v) = &plc_op {
-      input.insert(String::from("plc_op"), json!(v));
+      input.insert(String::from("plcOp"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?.into_json()?)
+    self.send_json(req, json!(input))?.into_json()
   }
 
   /// Create an App Password.
-
   pub fn com_atproto_server_createapppassword(
     &self,
     name: &str,
   ) -> Result<ComAtprotoServerCreateapppasswordApppassword> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.createAppPassword",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
     input.insert(String::from("name"), json!(name));
 
-    Ok(req.send_json(json!(input))?.into_json()?)
+    self.send_json(req, json!(input))?.into_json()
   }
 
   /// Create an invite code.
-
   pub fn com_atproto_server_createinvitecode(
     &self,
     use_count: i64,
     for_account: Option<&str>,
   ) -> Result<ComAtprotoServerCreateinvitecode> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.createInviteCode",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
-    input.insert(String::from("use_count"), json!(use_count));
+    input.insert(String::from("useCount"), json!(use_count));
 
     if let Some(v) = &for_account {
-      input.insert(String::from("for_account"), json!(v));
+      input.insert(String::from("forAccount"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?.into_json()?)
+    self.send_json(req, json!(input))?.into_json()
   }
 
   /// Create invite codes.
-
   pub fn com_atproto_server_createinvitecodes(
     &self,
     code_count: i64,
     use_count: i64,
     for_accounts: Option<&[&str]>,
   ) -> Result<ComAtprotoServerCreateinvitecodes> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.createInviteCodes",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
-    input.insert(String::from("code_count"), json!(code_count));
+    input.insert(String::from("codeCount"), json!(code_count));
 
-    input.insert(String::from("use_count"), json!(use_count));
+    input.insert(String::from("useCount"), json!(use_count));
 
     if let Some(v) = &for_accounts {
-      input.insert(String::from("for_accounts"), json!(v));
+      input.insert(String::from("forAccounts"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?.into_json()?)
+    self.send_json(req, json!(input))?.into_json()
   }
 
   /// Create an authentication session.
-
   pub fn com_atproto_server_createsession(
     &self,
     identifier: &str,
     password: &str,
   ) -> Result<ComAtprotoServerCreatesession> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.createSession",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6935,47 +7272,39 @@ impl Client {
 
     input.insert(String::from("password"), json!(password));
 
-    Ok(req.send_json(json!(input))?.into_json()?)
+    self.send_json(req, json!(input))?.into_json()
   }
 
   /// Deactivates a currently active account. Stops serving of repo, and future writes to repo until reactivated. Used to finalize account migration with the old host after the account has been activated on the new host.
-
   pub fn com_atproto_server_deactivateaccount(
     &self,
     delete_after: Option<&DateTime<Utc>>,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.deactivateAccount",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
     if let Some(v) = &delete_after {
-      input.insert(String::from("delete_after"), json!(v));
+      input.insert(String::from("deleteAfter"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
-  /// Delete an actor&#39;s account with a token and password. Can only be called after requesting a deletion token. Requires auth.
-
+  /// Delete an actor's account with a token and password. Can only be called after requesting a deletion token. Requires auth.
   pub fn com_atproto_server_deleteaccount(
     &self,
     did: &str,
     password: &str,
     token: &str,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.deleteAccount",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -6985,112 +7314,149 @@ impl Client {
 
     input.insert(String::from("token"), json!(token));
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
+  }
+
+  /// Delete the current session. Requires auth.
+  pub fn com_atproto_server_deletesession(&self) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/com.atproto.server.deleteSession",
+      self.host
+    ));
+
+    self.call(req)
+  }
+
+  /// Describes the server's account creation requirements and capabilities. Implemented by PDS.
+  pub fn com_atproto_server_describeserver(&self) -> Result<ComAtprotoServerDescribeserver> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.server.describeServer",
+      self.host
+    ));
+
+    self.call(req)?.into_json()
+  }
+
+  /// Get all invite codes for the current account. Requires auth.
+  pub fn com_atproto_server_getaccountinvitecodes(
+    &self,
+    include_used: Option<bool>,
+    create_available: Option<bool>,
+  ) -> Result<ComAtprotoServerGetaccountinvitecodes> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.server.getAccountInviteCodes",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    let include_used_value = include_used.map(|v| v.to_string());
+
+    if let Some(v) = &include_used_value {
+      _q.push(("includeUsed", v.as_str()));
+    }
+
+    let create_available_value = create_available.map(|v| v.to_string());
+
+    if let Some(v) = &create_available_value {
+      _q.push(("createAvailable", v.as_str()));
+    }
+
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// Get a signed token on behalf of the requesting DID for the requested service.
+  pub fn com_atproto_server_getserviceauth(
+    &self,
+    aud: &str,
+  ) -> Result<ComAtprotoServerGetserviceauth> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.server.getServiceAuth",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("aud", aud));
+
+    self.call(req.query_pairs(_q))?.into_json()
   }
 
-  /// Delete the current session. Requires auth.
-
-  pub fn com_atproto_server_deletesession(&self) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/com.atproto.server.deleteSession",
+  /// Get information about the current auth session. Requires auth.
+  pub fn com_atproto_server_getsession(&self) -> Result<ComAtprotoServerGetsession> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.server.getSession",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
-    Ok(req.call()?)
+    self.call(req)?.into_json()
   }
 
-  /// Refresh an authentication session. Requires auth using the &#39;refreshJwt&#39; (not the &#39;accessJwt&#39;).
-
-  pub fn com_atproto_server_refreshsession(&self) -> Result<ComAtprotoServerRefreshsession> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/com.atproto.server.refreshSession",
+  /// List all App Passwords.
+  pub fn com_atproto_server_listapppasswords(&self) -> Result<ComAtprotoServerListapppasswords> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.server.listAppPasswords",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
-    Ok(req.call()?.into_json()?)
+    self.call(req)?.into_json()
   }
 
   /// Initiate a user account deletion via email.
-
-  pub fn com_atproto_server_requestaccountdelete(&self) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  pub fn com_atproto_server_requestaccountdelete(&self) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.requestAccountDelete",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
-    Ok(req.call()?)
+    self.call(req)
   }
 
   /// Request an email with a code to confirm ownership of email.
-
-  pub fn com_atproto_server_requestemailconfirmation(&self) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  pub fn com_atproto_server_requestemailconfirmation(&self) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.requestEmailConfirmation",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
-    Ok(req.call()?)
+    self.call(req)
   }
 
   /// Request a token in order to update email.
-
   pub fn com_atproto_server_requestemailupdate(
     &self,
   ) -> Result<ComAtprotoServerRequestemailupdate> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.requestEmailUpdate",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
-    Ok(req.call()?.into_json()?)
+    self.call(req)?.into_json()
   }
 
   /// Initiate a user account password reset via email.
-
-  pub fn com_atproto_server_requestpasswordreset(&self, email: &str) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  pub fn com_atproto_server_requestpasswordreset(&self, email: &str) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.requestPasswordReset",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
     input.insert(String::from("email"), json!(email));
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
   /// Reserve a repo signing key, for use with account creation. Necessary so that a DID PLC update operation can be constructed during an account migraiton. Public and does not require auth; implemented by PDS. NOTE: this endpoint may change when full account migration is implemented.
-
   pub fn com_atproto_server_reservesigningkey(
     &self,
     did: Option<&str>,
   ) -> Result<ComAtprotoServerReservesigningkey> {
-    let mut req = self.agent.post(&format!(
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.reserveSigningKey",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -7098,23 +7464,19 @@ impl Client {
       input.insert(String::from("did"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?.into_json()?)
+    self.send_json(req, json!(input))?.into_json()
   }
 
   /// Reset a user account password using a token.
-
   pub fn com_atproto_server_resetpassword(
     &self,
     token: &str,
     password: &str,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.resetPassword",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -7122,41 +7484,33 @@ impl Client {
 
     input.insert(String::from("password"), json!(password));
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
   /// Revoke an App Password by name.
-
-  pub fn com_atproto_server_revokeapppassword(&self, name: &str) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  pub fn com_atproto_server_revokeapppassword(&self, name: &str) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.revokeAppPassword",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
     input.insert(String::from("name"), json!(name));
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
-  /// Update an account&#39;s email.
-
+  /// Update an account's email.
   pub fn com_atproto_server_updateemail(
     &self,
     email: &str,
     token: Option<&str>,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.server.updateEmail",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
@@ -7166,64 +7520,301 @@ impl Client {
       input.insert(String::from("token"), json!(v));
     }
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
-  /// Notify a crawling service of a recent update, and that crawling should resume. Intended use is after a gap between repo stream events caused the crawling service to disconnect. Does not require auth; implemented by Relay.
+  /// Get a blob associated with a given account. Returns the full blob as originally uploaded. Does not require auth; implemented by PDS.
+  pub fn com_atproto_sync_getblob(&self, did: &str, cid: &CidString) -> Result<Vec<u8>> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.sync.getBlob",
+      self.host
+    ));
 
-  pub fn com_atproto_sync_notifyofupdate(&self, hostname: &str) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
-      "https://{}/xrpc/com.atproto.sync.notifyOfUpdate",
+    let mut _q = Vec::new();
+
+    _q.push(("did", did));
+
+    let cid_value = cid.to_string();
+
+    _q.push(("cid", cid_value.as_str()));
+
+    Ok(self.call(req.query_pairs(_q))?.body)
+  }
+
+  /// Get data blocks from a given repo, by CID. For example, intermediate MST nodes, or records. Does not require auth; implemented by PDS.
+  pub fn com_atproto_sync_getblocks(&self, did: &str, cids: &[&CidString]) -> Result<Blocks> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.sync.getBlocks",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
+
+    let mut _q = Vec::new();
+
+    _q.push(("did", did));
+
+    let cids_value = cids.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+    _q.extend(cids_value.iter().map(|v| ("cids", v.as_str())));
+
+    let ret = self.call(req.query_pairs(_q))?.body;
+
+    Blocks::from_car(ret.as_slice(), self.verify_blocks)
+  }
+
+  /// DEPRECATED - please use com.atproto.sync.getRepo instead
+  pub fn com_atproto_sync_getcheckout(&self, did: &str) -> Result<Blocks> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.sync.getCheckout",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("did", did));
+
+    let ret = self.call(req.query_pairs(_q))?.body;
+
+    Blocks::from_car(ret.as_slice(), self.verify_blocks)
+  }
+
+  /// DEPRECATED - please use com.atproto.sync.getLatestCommit instead
+  pub fn com_atproto_sync_gethead(&self, did: &str) -> Result<ComAtprotoSyncGethead> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.sync.getHead",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("did", did));
+
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// Get the current commit CID & revision of the specified repo. Does not require auth.
+  pub fn com_atproto_sync_getlatestcommit(
+    &self,
+    did: &str,
+  ) -> Result<ComAtprotoSyncGetlatestcommit> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.sync.getLatestCommit",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("did", did));
+
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// Get data blocks needed to prove the existence or non-existence of record in the current version of repo. Does not require auth.
+  pub fn com_atproto_sync_getrecord(
+    &self,
+    did: &str,
+    collection: &str,
+    rkey: &str,
+    commit: Option<&CidString>,
+  ) -> Result<Blocks> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.sync.getRecord",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("did", did));
+
+    _q.push(("collection", collection));
+
+    _q.push(("rkey", rkey));
+
+    let commit_value = commit.map(|v| v.to_string());
+
+    if let Some(v) = &commit_value {
+      _q.push(("commit", v.as_str()));
+    }
+
+    let ret = self.call(req.query_pairs(_q))?.body;
+
+    Blocks::from_car(ret.as_slice(), self.verify_blocks)
+  }
+
+  /// Download a repository export as CAR file. Optionally only a 'diff' since a previous revision. Does not require auth; implemented by PDS.
+  pub fn com_atproto_sync_getrepo(&self, did: &str, since: Option<&str>) -> Result<Blocks> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.sync.getRepo",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("did", did));
+
+    if let Some(v) = since {
+      _q.push(("since", v));
+    }
+
+    let ret = self.call(req.query_pairs(_q))?.body;
+
+    Blocks::from_car(ret.as_slice(), self.verify_blocks)
+  }
+
+  /// Get the hosting status for a repository, on this server. Expected to be implemented by PDS and Relay.
+  pub fn com_atproto_sync_getrepostatus(&self, did: &str) -> Result<ComAtprotoSyncGetrepostatus> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.sync.getRepoStatus",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("did", did));
+
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// List blob CIDso for an account, since some repo revision. Does not require auth; implemented by PDS.
+  pub fn com_atproto_sync_listblobs(
+    &self,
+    did: &str,
+    since: Option<&str>,
+    limit: Option<i64>,
+    cursor: Option<&str>,
+  ) -> Result<ComAtprotoSyncListblobs> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.sync.listBlobs",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    _q.push(("did", did));
+
+    if let Some(v) = since {
+      _q.push(("since", v));
+    }
+
+    let limit_value = limit.map(|v| v.to_string());
+
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
+
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
+    }
+
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// Enumerates all the DID, rev, and commit CID for all repos hosted by this service. Does not require auth; implemented by PDS and Relay.
+  pub fn com_atproto_sync_listrepos(
+    &self,
+    limit: Option<i64>,
+    cursor: Option<&str>,
+  ) -> Result<ComAtprotoSyncListrepos> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.sync.listRepos",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    let limit_value = limit.map(|v| v.to_string());
+
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
+
+    if let Some(v) = cursor {
+      _q.push(("cursor", v));
     }
 
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// Notify a crawling service of a recent update, and that crawling should resume. Intended use is after a gap between repo stream events caused the crawling service to disconnect. Does not require auth; implemented by Relay.
+  pub fn com_atproto_sync_notifyofupdate(&self, hostname: &str) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
+      "https://{}/xrpc/com.atproto.sync.notifyOfUpdate",
+      self.host
+    ));
+
     let mut input = serde_json::Map::new();
 
     input.insert(String::from("hostname"), json!(hostname));
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
   /// Request a service to persistently crawl hosted repos. Expected use is new PDS instances declaring their existence to Relays. Does not require auth.
-
-  pub fn com_atproto_sync_requestcrawl(&self, hostname: &str) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  pub fn com_atproto_sync_requestcrawl(&self, hostname: &str) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.sync.requestCrawl",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
     input.insert(String::from("hostname"), json!(hostname));
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
-  /// Request a verification code to be sent to the supplied phone number
+  /// Check accounts location in signup queue.
+  pub fn com_atproto_temp_checksignupqueue(&self) -> Result<ComAtprotoTempChecksignupqueue> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.temp.checkSignupQueue",
+      self.host
+    ));
+
+    self.call(req)?.into_json()
+  }
+
+  /// DEPRECATED: use queryLabels or subscribeLabels instead -- Fetch all labels from a labeler created after a certain date.
+  pub fn com_atproto_temp_fetchlabels(
+    &self,
+    since: Option<i64>,
+    limit: Option<i64>,
+  ) -> Result<ComAtprotoTempFetchlabels> {
+    let req = HttpRequest::get(format!(
+      "https://{}/xrpc/com.atproto.temp.fetchLabels",
+      self.host
+    ));
+
+    let mut _q = Vec::new();
+
+    let since_value = since.map(|v| v.to_string());
+
+    if let Some(v) = &since_value {
+      _q.push(("since", v.as_str()));
+    }
+
+    let limit_value = limit.map(|v| v.to_string());
+
+    if let Some(v) = &limit_value {
+      _q.push(("limit", v.as_str()));
+    }
 
+    self.call(req.query_pairs(_q))?.into_json()
+  }
+
+  /// Request a verification code to be sent to the supplied phone number
   pub fn com_atproto_temp_requestphoneverification(
     &self,
     phone_number: &str,
-  ) -> Result<ureq::Response> {
-    let mut req = self.agent.post(&format!(
+  ) -> Result<HttpResponse> {
+    let req = HttpRequest::post(format!(
       "https://{}/xrpc/com.atproto.temp.requestPhoneVerification",
       self.host
     ));
-    if let Some(jwt) = &self.jwt {
-      req = req.set("Authorization", &format!("Bearer {}", jwt));
-    }
 
     let mut input = serde_json::Map::new();
 
-    input.insert(String::from("phone_number"), json!(phone_number));
+    input.insert(String::from("phoneNumber"), json!(phone_number));
 
-    Ok(req.send_json(json!(input))?)
+    self.send_json(req, json!(input))
   }
 
   /// Subscribe to stream of labels (and negations). Public endpoint implemented by mod services. Uses same sequencing scheme as repo event stream.
@@ -7236,15 +7827,15 @@ impl Client {
       self.bgs_host
     ))?;
 
-    let mut query = Vec::new();
+    let mut _q = Vec::new();
 
-    let cursor_value = serde_json::to_string(&cursor)?;
+    let cursor_value = cursor.map(|v| v.to_string());
 
-    if cursor.is_some() {
-      query.push(("cursor", cursor_value.as_str()));
+    if let Some(v) = &cursor_value {
+      _q.push(("cursor", v.as_str()));
     }
 
-    url.query_pairs_mut().extend_pairs(query);
+    url.query_pairs_mut().extend_pairs(_q);
 
     Ok(tungstenite::connect(&url)?.0)
   }
@@ -7259,16 +7850,470 @@ impl Client {
       self.bgs_host
     ))?;
 
-    let mut query = Vec::new();
+    let mut _q = Vec::new();
 
-    let cursor_value = serde_json::to_string(&cursor)?;
+    let cursor_value = cursor.map(|v| v.to_string());
 
-    if cursor.is_some() {
-      query.push(("cursor", cursor_value.as_str()));
+    if let Some(v) = &cursor_value {
+      _q.push(("cursor", v.as_str()));
     }
 
-    url.query_pairs_mut().extend_pairs(query);
+    url.query_pairs_mut().extend_pairs(_q);
 
     Ok(tungstenite::connect(&url)?.0)
   }
+
+  // codegen:end methods
+}
+
+// codegen:begin errors
+/// Returns error names defined by the lexicon of the method
+pub fn known_errors(method: &str) -> &'static [&'static str] {
+  match method {
+    "app.bsky.feed.getActorLikes" => &["BlockedActor", "BlockedByActor"],
+    "app.bsky.feed.getAuthorFeed" => &["BlockedActor", "BlockedByActor"],
+    "app.bsky.feed.getFeed" => &["UnknownFeed"],
+    "app.bsky.feed.getFeedSkeleton" => &["UnknownFeed"],
+    "app.bsky.feed.getListFeed" => &["UnknownList"],
+    "app.bsky.feed.getPostThread" => &["NotFound"],
+    "app.bsky.feed.searchPosts" => &["BadQueryString"],
+    "app.bsky.graph.getRelationships" => &["ActorNotFound"],
+    "app.bsky.unspecced.searchActorsSkeleton" => &["BadQueryString"],
+    "app.bsky.unspecced.searchPostsSkeleton" => &["BadQueryString"],
+    "com.atproto.admin.getRecord" => &["RecordNotFound"],
+    "com.atproto.admin.getRepo" => &["RepoNotFound"],
+    "com.atproto.identity.resolveHandle" => &["HandleNotFound"],
+    "com.atproto.repo.applyWrites" => &["InvalidSwap"],
+    "com.atproto.repo.createRecord" => &["InvalidSwap"],
+    "com.atproto.repo.deleteRecord" => &["InvalidSwap"],
+    "com.atproto.repo.getRecord" => &["RecordNotFound"],
+    "com.atproto.repo.putRecord" => &["InvalidSwap"],
+    "com.atproto.server.confirmEmail" => &[
+      "AccountNotFound",
+      "ExpiredToken",
+      "InvalidToken",
+      "InvalidEmail",
+    ],
+    "com.atproto.server.createAccount" => &[
+      "InvalidHandle",
+      "InvalidPassword",
+      "InvalidInviteCode",
+      "HandleNotAvailable",
+      "UnsupportedDomain",
+      "UnresolvableDid",
+      "IncompatibleDidDoc",
+    ],
+    "com.atproto.server.createAppPassword" => &["AccountTakedown"],
+    "com.atproto.server.createSession" => &["AccountTakedown", "AuthFactorTokenRequired"],
+    "com.atproto.server.deleteAccount" => &["ExpiredToken", "InvalidToken"],
+    "com.atproto.server.getServiceAuth" => &["BadExpiration"],
+    "com.atproto.server.listAppPasswords" => &["AccountTakedown"],
+    "com.atproto.server.refreshSession" => &["AccountTakedown"],
+    "com.atproto.server.resetPassword" => &["ExpiredToken", "InvalidToken"],
+    "com.atproto.server.updateEmail" => &["ExpiredToken", "InvalidToken", "TokenRequired"],
+    "com.atproto.sync.getBlob" => &[
+      "BlobNotFound",
+      "RepoNotFound",
+      "RepoTakendown",
+      "RepoSuspended",
+      "RepoDeactivated",
+    ],
+    "com.atproto.sync.getBlocks" => &[
+      "BlockNotFound",
+      "RepoNotFound",
+      "RepoTakendown",
+      "RepoSuspended",
+      "RepoDeactivated",
+    ],
+    "com.atproto.sync.getHead" => &["HeadNotFound"],
+    "com.atproto.sync.getLatestCommit" => &[
+      "RepoNotFound",
+      "RepoTakendown",
+      "RepoSuspended",
+      "RepoDeactivated",
+    ],
+    "com.atproto.sync.getRecord" => &[
+      "RecordNotFound",
+      "RepoNotFound",
+      "RepoTakendown",
+      "RepoSuspended",
+      "RepoDeactivated",
+    ],
+    "com.atproto.sync.getRepo" => &[
+      "RepoNotFound",
+      "RepoTakendown",
+      "RepoSuspended",
+      "RepoDeactivated",
+    ],
+    "com.atproto.sync.getRepoStatus" => &["RepoNotFound"],
+    "com.atproto.sync.listBlobs" => &[
+      "RepoNotFound",
+      "RepoTakendown",
+      "RepoSuspended",
+      "RepoDeactivated",
+    ],
+    _ => &[],
+  }
+}
+// codegen:end errors
+
+#[cfg(test)]
+mod tests {
+  use std::thread::scope;
+
+  use super::*;
+  use crate::MockTransport;
+
+  const PREFERENCES: &str = "/xrpc/app.bsky.actor.getPreferences";
+  const REFRESH: &str = "/xrpc/com.atproto.server.refreshSession";
+
+  fn jwt(exp: i64) -> String {
+    let payload = json!({ "sub": "did:plc:alice", "exp": Utc::now().timestamp() + exp });
+    format!(
+      "{}.{}.sig",
+      Base::Base64Url.encode(br#"{"alg":"ES256K"}"#),
+      Base::Base64Url.encode(payload.to_string())
+    )
+  }
+
+  fn client(transport: &MockTransport, access_exp: i64) -> Client {
+    let mut client = Client::new("pds.test", "relay.test", None::<String>);
+    client.set_transport(transport.clone());
+    client.set_retry_policy(RetryPolicy::none());
+    client.set_jwt(Some(jwt(access_exp)));
+    client.set_refresh_jwt(Some(String::from("refresh")));
+    client
+  }
+
+  fn car_cid(codec: u64, bytes: &[u8]) -> Cid {
+    Cid::new_v1(
+      codec,
+      libipld::multihash::Multihash::wrap(SHA2_256, &Sha256::digest(bytes)).unwrap(),
+    )
+  }
+
+  fn car(blocks: &[(Cid, Vec<u8>)]) -> Vec<u8> {
+    let header = Ipld::Map(
+      [
+        (String::from("version"), Ipld::Integer(1)),
+        (
+          String::from("roots"),
+          Ipld::List(vec![Ipld::Link(blocks[0].0)]),
+        ),
+      ]
+      .into(),
+    );
+    let header = DagCborCodec.encode(&header).unwrap();
+    let mut ret = Vec::new();
+    leb128::write::unsigned(&mut ret, header.len() as u64).unwrap();
+    ret.extend(header);
+    for (cid, block) in blocks {
+      let cid = cid.to_bytes();
+      leb128::write::unsigned(&mut ret, (cid.len() + block.len()) as u64).unwrap();
+      ret.extend(cid);
+      ret.extend(block);
+    }
+    ret
+  }
+
+  #[test]
+  fn from_car_keeps_raw_blocks() {
+    let node = DagCborCodec
+      .encode(&Ipld::Map(
+        [(String::from("text"), Ipld::String(String::from("hi")))].into(),
+      ))
+      .unwrap();
+    let raw = b"raw blob".to_vec();
+    let data = car(&[
+      (car_cid(DAG_CBOR_CODEC, &node), node.clone()),
+      (car_cid(RAW_CODEC, &raw), raw.clone()),
+    ]);
+    let blocks = Blocks::from_car(&data, true).unwrap();
+    assert_eq!(
+      blocks.get(&car_cid(RAW_CODEC, &raw)),
+      Some(Ipld::Bytes(raw))
+    );
+    assert_eq!(blocks.root(), Some(car_cid(DAG_CBOR_CODEC, &node)));
+    assert_eq!(blocks.to_car().unwrap(), data);
+  }
+
+  #[test]
+  fn from_car_rejects_broken_blocks_with_verify() {
+    let node = DagCborCodec
+      .encode(&Ipld::String(String::from("hi")))
+      .unwrap();
+    let tampered = car(&[(car_cid(DAG_CBOR_CODEC, &node), b"tampered".to_vec())]);
+    assert!(Blocks::from_car(&tampered, true).is_err());
+    assert_eq!(
+      Blocks::from_car(&tampered, false).unwrap().iter().count(),
+      0
+    );
+    // hashes right but is not DAG-CBOR
+    let broken = vec![0xff, 0xff];
+    let undecodable = car(&[(car_cid(DAG_CBOR_CODEC, &broken), broken)]);
+    assert!(Blocks::from_car(&undecodable, true).is_err());
+    assert_eq!(
+      Blocks::from_car(&undecodable, false)
+        .unwrap()
+        .iter()
+        .count(),
+      0
+    );
+  }
+
+  #[test]
+  fn from_car_broken_header() {
+    assert!(Blocks::from_car(&[0x05, 0xff], true).is_err());
+    let blocks = Blocks::from_car(&[0x05, 0xff], false).unwrap();
+    assert!(blocks.root().is_none());
+  }
+
+  fn count(transport: &MockTransport, path: &str) -> usize {
+    transport
+      .get_requests()
+      .iter()
+      .filter(|r| r.url().contains(path))
+      .count()
+  }
+
+  #[test]
+  fn refresh_once_for_concurrent_requests() {
+    let transport = MockTransport::new();
+    transport.push_json(PREFERENCES, 200, json!({ "preferences": [] }));
+    transport.push_json(
+      REFRESH,
+      200,
+      json!({ "accessJwt": jwt(3600), "refreshJwt": "refresh2", "handle": "alice.test", "did": "did:plc:alice" }),
+    );
+    let client = client(&transport, -10);
+    scope(|s| {
+      for _ in 0..4 {
+        s.spawn(|| client.app_bsky_actor_getpreferences().unwrap());
+      }
+    });
+    assert_eq!(count(&transport, REFRESH), 1);
+    assert_eq!(client.get_refresh_jwt().as_deref(), Some("refresh2"));
+  }
+
+  #[test]
+  fn clear_refresh_token_when_refresh_is_rejected() {
+    let transport = MockTransport::new();
+    transport.push_json(
+      PREFERENCES,
+      400,
+      json!({ "error": "ExpiredToken", "message": "Token has expired" }),
+    );
+    transport.push_json(
+      REFRESH,
+      400,
+      json!({ "error": "ExpiredToken", "message": "Token has been revoked" }),
+    );
+    let client = client(&transport, 3600);
+    assert!(client.app_bsky_actor_getpreferences().is_err());
+    assert!(client.app_bsky_actor_getpreferences().is_err());
+    assert_eq!(count(&transport, REFRESH), 1);
+    assert_eq!(client.get_refresh_jwt(), None);
+  }
+
+  #[test]
+  fn back_off_after_transient_refresh_failure() {
+    let transport = MockTransport::new();
+    transport.push_json(
+      PREFERENCES,
+      400,
+      json!({ "error": "ExpiredToken", "message": "Token has expired" }),
+    );
+    transport.push_json(REFRESH, 502, json!({ "error": "UpstreamFailure" }));
+    let client = client(&transport, 3600);
+    assert!(client.app_bsky_actor_getpreferences().is_err());
+    assert!(client.app_bsky_actor_getpreferences().is_err());
+    assert_eq!(count(&transport, REFRESH), 1);
+    assert_eq!(client.get_refresh_jwt().as_deref(), Some("refresh"));
+  }
+
+  #[test]
+  fn typed_xrpc_error() {
+    let transport = MockTransport::new();
+    transport.push_json(
+      "/xrpc/com.atproto.repo.getRecord",
+      400,
+      json!({ "error": "RecordNotFound", "message": "Could not locate record" }),
+    );
+    let client = client(&transport, 3600);
+    let e = client
+      .com_atproto_repo_getrecord("did:plc:alice", "app.bsky.feed.post", "3k", None)
+      .unwrap_err();
+    let e = e.downcast_ref::<XrpcError>().unwrap();
+    assert_eq!(e.status(), Some(400));
+    assert_eq!(e.method(), "com.atproto.repo.getRecord");
+    assert_eq!(e.error(), Some(&XrpcErrorName::RecordNotFound));
+    assert_eq!(e.message(), Some("Could not locate record"));
+    assert!(e.is_known());
+  }
+
+  #[test]
+  fn refresh_expiring_token_before_request() {
+    let transport = MockTransport::new();
+    transport.push_json(PREFERENCES, 200, json!({ "preferences": [] }));
+    let access = jwt(3600);
+    transport.push_json(
+      REFRESH,
+      200,
+      json!({ "accessJwt": access, "refreshJwt": "refresh2", "handle": "alice.test", "did": "did:plc:alice" }),
+    );
+    let client = client(&transport, 10);
+    client.app_bsky_actor_getpreferences().unwrap();
+    let requests = transport.get_requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].header("Authorization"), Some("Bearer refresh"));
+    assert_eq!(
+      requests[1].header("Authorization"),
+      Some(format!("Bearer {}", access).as_str())
+    );
+  }
+
+  #[test]
+  fn refresh_expired_token_and_retry() {
+    let transport = MockTransport::new();
+    transport.push_json(
+      PREFERENCES,
+      400,
+      json!({ "error": "ExpiredToken", "message": "Token has expired" }),
+    );
+    transport.push_json(PREFERENCES, 200, json!({ "preferences": [] }));
+    transport.push_json(
+      REFRESH,
+      200,
+      json!({ "accessJwt": jwt(3600), "refreshJwt": "refresh2", "handle": "alice.test", "did": "did:plc:alice" }),
+    );
+    let client = client(&transport, 3600);
+    client.app_bsky_actor_getpreferences().unwrap();
+    assert_eq!(count(&transport, PREFERENCES), 2);
+    assert_eq!(count(&transport, REFRESH), 1);
+  }
+
+  #[test]
+  fn wait_for_rate_limit_reset() {
+    let transport = MockTransport::new();
+    let reset = Utc::now().timestamp() + 1;
+    transport.push_response(
+      PREFERENCES,
+      HttpResponse::new(
+        PREFERENCES,
+        429,
+        br#"{"error":"RateLimitExceeded"}"#.to_vec(),
+      )
+      .with_header("RateLimit-Limit", "3000")
+      .with_header("RateLimit-Remaining", "0")
+      .with_header("RateLimit-Reset", &reset.to_string())
+      .with_header("RateLimit-Policy", "3000;w=300"),
+    );
+    transport.push_response(
+      PREFERENCES,
+      HttpResponse::new(PREFERENCES, 200, br#"{"preferences":[]}"#.to_vec())
+        .with_header("RateLimit-Limit", "3000")
+        .with_header("RateLimit-Remaining", "2999")
+        .with_header("RateLimit-Reset", &(reset + 300).to_string()),
+    );
+    let mut client = client(&transport, 3600);
+    client.set_retry_policy(RetryPolicy {
+      max_retries: 1,
+      initial_backoff: Duration::from_millis(10),
+      ..Default::default()
+    });
+    client.app_bsky_actor_getpreferences().unwrap();
+    assert_eq!(count(&transport, PREFERENCES), 2);
+    assert!(Utc::now().timestamp() >= reset);
+    let rate_limit = client.get_rate_limiter().get("pds.test").unwrap();
+    assert_eq!(rate_limit.limit, Some(3000));
+    assert_eq!(rate_limit.remaining, Some(2999));
+    assert_eq!(rate_limit.reset, DateTime::from_timestamp(reset + 300, 0));
+  }
+
+  #[test]
+  fn fail_when_rate_limit_resets_too_late() {
+    let transport = MockTransport::new();
+    transport.push_response(
+      PREFERENCES,
+      HttpResponse::new(
+        PREFERENCES,
+        429,
+        br#"{"error":"RateLimitExceeded"}"#.to_vec(),
+      )
+      .with_header("RateLimit-Remaining", "0")
+      .with_header(
+        "RateLimit-Reset",
+        &(Utc::now().timestamp() + 3600).to_string(),
+      ),
+    );
+    let mut client = client(&transport, 3600);
+    client.set_retry_policy(RetryPolicy::default());
+    let e = client.app_bsky_actor_getpreferences().unwrap_err();
+    let e = e.downcast_ref::<XrpcError>().unwrap();
+    assert!(e.is_rate_limited());
+    assert_eq!(e.rate_limit().and_then(|r| r.remaining), Some(0));
+    assert_eq!(count(&transport, PREFERENCES), 1);
+  }
+
+  #[test]
+  fn paginate_follows() {
+    let transport = MockTransport::new();
+    let profile = |did: &str| json!({ "did": did, "handle": format!("{}.test", did) });
+    transport.push_json(
+      "/xrpc/app.bsky.graph.getFollows",
+      200,
+      json!({ "subject": profile("alice"), "follows": [profile("bob"), profile("carol")], "cursor": "c1" }),
+    );
+    transport.push_json(
+      "/xrpc/app.bsky.graph.getFollows",
+      200,
+      json!({ "subject": profile("alice"), "follows": [profile("dave")] }),
+    );
+    let client = client(&transport, 3600);
+    let follows = client
+      .app_bsky_graph_getfollows_iter("alice.test")
+      .page_size(2)
+      .map(|f| f.map(|f| f.did))
+      .collect::<Result<Vec<_>>>()
+      .unwrap();
+    assert_eq!(follows, vec!["bob", "carol", "dave"]);
+    let requests = transport.get_requests();
+    assert_eq!(requests.len(), 2);
+    assert!(!requests[0].url().contains("cursor"));
+    assert!(requests[1].url().contains("cursor=c1"));
+    assert!(requests[1].url().contains("limit=2"));
+  }
+
+  #[test]
+  fn camel_case_query_and_input_keys() {
+    let transport = MockTransport::new();
+    transport.push_json(
+      "/xrpc/app.bsky.feed.getPostThread",
+      200,
+      json!({ "thread": { "$type": "app.bsky.feed.defs#notFoundPost", "uri": "at://did:plc:alice/app.bsky.feed.post/3k", "notFound": true } }),
+    );
+    transport.push_json("/xrpc/com.atproto.repo.deleteRecord", 200, json!({}));
+    let client = client(&transport, 3600);
+    client
+      .app_bsky_feed_getpostthread("at://did:plc:alice/app.bsky.feed.post/3k", None, Some(2))
+      .unwrap();
+    let commit = CidString(String::from(
+      "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+    ));
+    client
+      .com_atproto_repo_deleterecord(
+        "did:plc:alice",
+        "app.bsky.feed.post",
+        "3k",
+        None,
+        Some(&commit),
+      )
+      .unwrap();
+    let requests = transport.get_requests();
+    assert!(requests[0].url().contains("parentHeight=2"));
+    assert!(!requests[0].url().contains("parent_height"));
+    let input: Value = serde_json::from_slice(requests[1].body.as_ref().unwrap()).unwrap();
+    assert_eq!(input["swapCommit"], json!(commit.0));
+    assert!(input.get("swap_commit").is_none());
+  }
 }
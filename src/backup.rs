@@ -0,0 +1,396 @@
+//! Offline backup and restore of repositories
+//!
+//! Each account is stored in its own directory under the backup root,
+//! named by the DID with ':' replaced by '_' like `did_plc_abc` for `did:plc:abc`.
+//!
+//! ```text
+//! <root>/did_plc_abc/manifest.json  BackupManifest, its version is BACKUP_LAYOUT_VERSION
+//! <root>/did_plc_abc/repo.car       repository whose root is the latest commit
+//! <root>/did_plc_abc/blobs/<cid>    blob referenced by the records
+//! ```
+use std::{
+  collections::BTreeMap,
+  fs::{create_dir_all, read, remove_file, write, File},
+  path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use libipld::{Cid, Ipld};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::api::{verify_cid, Blocks, Client, RAW_CODEC};
+
+/// Version of the on-disk layout written by this module
+pub const BACKUP_LAYOUT_VERSION: u32 = 1;
+
+/// Blob saved in a backup
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupBlob {
+  pub mime_type: Option<String>,
+  pub size: u64,
+}
+
+/// Description of the saved repository and blobs
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+  pub version: u32,
+  pub did: String,
+  pub rev: String,
+  pub commit: String,
+  pub car_sha256: String,
+  pub blobs: BTreeMap<String, BackupBlob>,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+/// Repository backup directory
+pub struct Backup {
+  root: PathBuf,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+  Sha256::digest(data)
+    .iter()
+    .map(|b| format!("{:02x}", b))
+    .collect()
+}
+
+fn verify_blob(cid: &Cid, data: &[u8]) -> Result<()> {
+  if cid.codec() != RAW_CODEC {
+    bail!("unsupported blob cid {}", cid);
+  }
+  verify_cid(cid, data)
+}
+
+fn collect_mime_types(ipld: &Ipld, mime_types: &mut BTreeMap<String, String>) {
+  match ipld {
+    Ipld::Map(m) => {
+      match (m.get("ref"), m.get("cid"), m.get("mimeType")) {
+        (Some(Ipld::Link(cid)), _, Some(Ipld::String(mime_type))) => {
+          mime_types.insert(cid.to_string(), mime_type.clone());
+        }
+        // legacy blob
+        (None, Some(Ipld::String(cid)), Some(Ipld::String(mime_type))) => {
+          mime_types.insert(cid.clone(), mime_type.clone());
+        }
+        _ => (),
+      }
+      m.values().for_each(|v| collect_mime_types(v, mime_types));
+    }
+    Ipld::List(l) => l.iter().for_each(|v| collect_mime_types(v, mime_types)),
+    _ => (),
+  }
+}
+
+impl Backup {
+  /// Create backup directory handler
+  pub fn new<P: AsRef<Path>>(root: P) -> Self {
+    Self {
+      root: root.as_ref().to_path_buf(),
+    }
+  }
+
+  fn account_dir(&self, did: &str) -> PathBuf {
+    self.root.join(did.replace(':', "_"))
+  }
+
+  fn manifest_path(&self, did: &str) -> PathBuf {
+    self.account_dir(did).join("manifest.json")
+  }
+
+  fn car_path(&self, did: &str) -> PathBuf {
+    self.account_dir(did).join("repo.car")
+  }
+
+  fn blob_path(&self, did: &str, cid: &str) -> PathBuf {
+    self.account_dir(did).join("blobs").join(cid)
+  }
+
+  /// Read the manifest of a saved account
+  pub fn load_manifest(&self, did: &str) -> Result<Option<BackupManifest>> {
+    let Ok(file) = File::open(self.manifest_path(did)) else {
+      return Ok(None);
+    };
+    let manifest: BackupManifest = serde_json::from_reader(file)?;
+    if manifest.version != BACKUP_LAYOUT_VERSION {
+      bail!("unsupported backup layout version {}", manifest.version);
+    }
+    Ok(Some(manifest))
+  }
+
+  fn save_manifest(&self, manifest: &BackupManifest) -> Result<()> {
+    let file = File::create(self.manifest_path(&manifest.did))?;
+    serde_json::to_writer_pretty(file, manifest)?;
+    Ok(())
+  }
+
+  /// Download the repository and blobs of the DID, only the difference if already saved
+  pub fn backup(&self, client: &Client, did: &str) -> Result<BackupManifest> {
+    create_dir_all(self.account_dir(did).join("blobs"))?;
+    let previous = self.load_manifest(did)?;
+    // a broken backup is downloaded again instead of merging the difference into it
+    let verified = match &previous {
+      Some(_) => match self.verify(did) {
+        Ok(manifest) => Some(manifest),
+        Err(e) => {
+          log::warn!("download whole repository of {} : {}", did, e);
+          None
+        }
+      },
+      None => None,
+    };
+    let since = verified.as_ref().map(|m| m.rev.clone());
+    let diff = client.com_atproto_sync_getrepo(did, since.as_deref())?;
+    let mut blocks = match &verified {
+      Some(_) => Blocks::from_car(read(self.car_path(did))?.as_slice(), true)?,
+      None => Blocks::default(),
+    };
+    blocks.merge(diff);
+    blocks.retain_reachable();
+    let Some(commit_cid) = blocks.root() else {
+      bail!("repository of {} has no root", did);
+    };
+    let Some(commit) = blocks.root_commit() else {
+      bail!("root of {} is not a commit", did);
+    };
+    let car = blocks.to_car()?;
+    write(self.car_path(did), &car)?;
+
+    let mut mime_types = BTreeMap::new();
+    for (_, ipld) in blocks.iter() {
+      collect_mime_types(ipld, &mut mime_types);
+    }
+    let mut saved = verified
+      .as_ref()
+      .map(|m| m.blobs.clone())
+      .unwrap_or_default();
+    let mut cursor = None;
+    loop {
+      let list =
+        client.com_atproto_sync_listblobs(did, since.as_deref(), Some(500), cursor.as_deref())?;
+      for cid in list.cids.iter() {
+        let key = cid.to_string();
+        let path = self.blob_path(did, &key);
+        if saved.contains_key(&key) && path.exists() {
+          continue;
+        }
+        if !mime_types.contains_key(&key) {
+          log::debug!("skip blob {} of {} not referenced by records", key, did);
+          continue;
+        }
+        let data = client.com_atproto_sync_getblob(did, cid)?;
+        verify_blob(&cid.to_cid()?, &data)?;
+        write(path, &data)?;
+        log::info!("saved blob {} of {}", key, did);
+        saved.insert(
+          key.clone(),
+          BackupBlob {
+            mime_type: mime_types.get(&key).cloned(),
+            size: data.len() as u64,
+          },
+        );
+      }
+      match list.cursor {
+        Some(c) if !list.cids.is_empty() => cursor = Some(c),
+        _ => break,
+      }
+    }
+    // blobs of deleted records are not restored
+    let unreferenced = saved
+      .keys()
+      .filter(|k| !mime_types.contains_key(*k))
+      .cloned()
+      .collect::<Vec<_>>();
+    for key in unreferenced {
+      saved.remove(&key);
+      match remove_file(self.blob_path(did, &key)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        _ => log::info!("pruned blob {} of {}", key, did),
+      }
+    }
+
+    let now = Utc::now();
+    let manifest = BackupManifest {
+      version: BACKUP_LAYOUT_VERSION,
+      did: did.to_string(),
+      rev: commit.rev.unwrap_or_default(),
+      commit: commit_cid.to_string(),
+      car_sha256: sha256_hex(&car),
+      blobs: saved,
+      created_at: previous.map(|m| m.created_at).unwrap_or(now),
+      updated_at: now,
+    };
+    self.save_manifest(&manifest)?;
+    Ok(manifest)
+  }
+
+  /// Check that the saved files match the manifest
+  pub fn verify(&self, did: &str) -> Result<BackupManifest> {
+    let Some(manifest) = self.load_manifest(did)? else {
+      bail!("no backup of {}", did);
+    };
+    let car = read(self.car_path(did))?;
+    if sha256_hex(&car) != manifest.car_sha256 {
+      bail!("repository of {} does not match the manifest", did);
+    }
+    let blocks = Blocks::from_car(car.as_slice(), true)?;
+    if blocks.root().map(|c| c.to_string()) != Some(manifest.commit.clone()) {
+      bail!("root of {} does not match the manifest", did);
+    }
+    for (key, blob) in manifest.blobs.iter() {
+      let data = read(self.blob_path(did, key))?;
+      if data.len() as u64 != blob.size {
+        bail!("size of blob {} does not match the manifest", key);
+      }
+      verify_blob(&Cid::try_from(key.as_str())?, &data)?;
+    }
+    Ok(manifest)
+  }
+
+  /// Upload the saved repository and blobs to the logged in account of the client
+  pub fn restore(&self, client: &Client, did: &str) -> Result<()> {
+    let manifest = self.verify(did)?;
+    client.com_atproto_repo_importrepo(&read(self.car_path(did))?)?;
+    for (key, blob) in manifest.blobs.iter() {
+      let data = read(self.blob_path(did, key))?;
+      let uploaded = client.com_atproto_repo_uploadblob(
+        &data,
+        blob
+          .mime_type
+          .as_deref()
+          .unwrap_or("application/octet-stream"),
+      )?;
+      let uploaded = uploaded.blob.blob_ref.map(|r| r.link).unwrap_or_default();
+      if uploaded != *key {
+        log::warn!("blob {} is uploaded as {}", key, uploaded);
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs::remove_dir_all;
+
+  use libipld::multihash::Multihash;
+
+  use super::*;
+  use crate::{
+    api::SHA2_256, xrpc::RetryPolicy, KeyAlgorithm, Keypair, LocalRepo, MockTransport, RepoWrite,
+  };
+
+  const DID: &str = "did:plc:alice";
+
+  fn blob_record(cid: Cid) -> Ipld {
+    Ipld::Map(BTreeMap::from([
+      (
+        String::from("$type"),
+        Ipld::String(String::from("test.file")),
+      ),
+      (
+        String::from("file"),
+        Ipld::Map(BTreeMap::from([
+          (String::from("$type"), Ipld::String(String::from("blob"))),
+          (String::from("ref"), Ipld::Link(cid)),
+          (
+            String::from("mimeType"),
+            Ipld::String(String::from("text/plain")),
+          ),
+          (String::from("size"), Ipld::Integer(4)),
+        ])),
+      ),
+    ]))
+  }
+
+  fn mock(transport: &MockTransport, repo: &LocalRepo, blobs: &[(Cid, &[u8])]) {
+    transport.clear();
+    transport.push_response(
+      "/xrpc/com.atproto.sync.getRepo",
+      crate::HttpResponse::new("/", 200, repo.to_car().unwrap()),
+    );
+    let cids = blobs.iter().map(|(c, _)| c.to_string()).collect::<Vec<_>>();
+    transport.push_json(
+      "/xrpc/com.atproto.sync.listBlobs",
+      200,
+      serde_json::json!({ "cids": cids }),
+    );
+    for (_, data) in blobs {
+      transport.push_response(
+        "/xrpc/com.atproto.sync.getBlob",
+        crate::HttpResponse::new("/", 200, data.to_vec()),
+      );
+    }
+  }
+
+  #[test]
+  fn prune_blobs_of_deleted_records() {
+    let root = std::env::temp_dir().join(format!("aerostream-backup-{}", std::process::id()));
+    let data: &[u8] = b"blob";
+    let cid = Cid::new_v1(
+      RAW_CODEC,
+      Multihash::wrap(SHA2_256, &Sha256::digest(data)).unwrap(),
+    );
+    let transport = MockTransport::new();
+    let mut client = Client::new("pds.test", "relay.test", None::<String>);
+    client.set_transport(transport.clone());
+    client.set_retry_policy(RetryPolicy::none());
+    let mut repo = LocalRepo::new(DID, Keypair::generate(KeyAlgorithm::Secp256k1));
+    repo
+      .apply_writes(vec![RepoWrite::Create {
+        collection: String::from("test.file"),
+        rkey: Some(String::from("one")),
+        record: blob_record(cid),
+      }])
+      .unwrap();
+    mock(&transport, &repo, &[(cid, data)]);
+    let backup = Backup::new(&root);
+    let manifest = backup.backup(&client, DID).unwrap();
+    assert_eq!(manifest.blobs[&cid.to_string()].size, 4);
+    assert!(backup.blob_path(DID, &cid.to_string()).exists());
+
+    repo
+      .apply_writes(vec![RepoWrite::Delete {
+        collection: String::from("test.file"),
+        rkey: String::from("one"),
+      }])
+      .unwrap();
+    mock(&transport, &repo, &[]);
+    let manifest = backup.backup(&client, DID).unwrap();
+    assert!(manifest.blobs.is_empty());
+    assert!(!backup.blob_path(DID, &cid.to_string()).exists());
+    backup.verify(DID).unwrap();
+    remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn download_whole_repository_of_broken_backup() {
+    let root =
+      std::env::temp_dir().join(format!("aerostream-backup-broken-{}", std::process::id()));
+    let transport = MockTransport::new();
+    let mut client = Client::new("pds.test", "relay.test", None::<String>);
+    client.set_transport(transport.clone());
+    client.set_retry_policy(RetryPolicy::none());
+    let mut repo = LocalRepo::new(DID, Keypair::generate(KeyAlgorithm::Secp256k1));
+    repo.commit().unwrap();
+    mock(&transport, &repo, &[]);
+    let backup = Backup::new(&root);
+    backup.backup(&client, DID).unwrap();
+
+    mock(&transport, &repo, &[]);
+    backup.backup(&client, DID).unwrap();
+    assert!(transport.get_requests()[0].url().contains("since="));
+
+    write(backup.car_path(DID), b"broken").unwrap();
+    mock(&transport, &repo, &[]);
+    let manifest = backup.backup(&client, DID).unwrap();
+    assert!(!transport.get_requests()[0].url().contains("since="));
+    assert_eq!(manifest.rev, repo.rev().unwrap().to_string());
+    backup.verify(DID).unwrap();
+    remove_dir_all(&root).unwrap();
+  }
+}
@@ -5,7 +5,7 @@ use std::thread::sleep;
 use std::time::Duration;
 
 use aerostream::api::ComAtprotoSyncSubscribereposCommit;
-use aerostream::Client;
+use aerostream::{BlobFetcher, Client};
 use anyhow::Result;
 use chrono::{DateTime, Local};
 use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
@@ -119,15 +119,16 @@ fn ui(
   filters: &mut HashMap<String, (VecDeque<Post>, ListState)>,
   focus: usize,
   image_status: &mut (Option<String>, Option<DynamicImage>),
+  fetcher: &BlobFetcher,
 ) {
   if let Some(img) = &image_status.0 {
-    if let Ok(res) = ureq::get(&img).call() {
-      let mut buf: Vec<u8> = Vec::new();
-      if res.into_reader().read_to_end(&mut buf).is_ok() {
+    match fetcher.fetch_url(img) {
+      Ok((buf, _)) => {
         if let Ok(img_data) = load_from_memory(buf.as_slice()) {
           *image_status = (None, Some(img_data));
         }
       }
+      Err(e) => log::warn!("blob fetch error {}", e),
     }
   }
   if let Some(img_data) = &image_status.1 {
@@ -230,11 +231,15 @@ fn main() -> Result<()> {
     .collect::<HashMap<String, (VecDeque<Post>, ListState)>>();
   let mut focus = 0;
   let mut image_status = (None, None);
+  let fetcher = BlobFetcher::new(
+    std::env::temp_dir().join("aerostream-blobs"),
+    &client.client,
+  );
   let mut image_index = 0;
   if filters.is_empty() {
     filters.insert(String::from(""), (VecDeque::new(), ListState::default()));
   }
-  terminal.draw(|f| ui(f, &mut filters, focus, &mut image_status))?;
+  terminal.draw(|f| ui(f, &mut filters, focus, &mut image_status, &fetcher))?;
   loop {
     let mut updated = false;
     for (filter, event) in client.next_event_filtered_all()?.into_iter() {
@@ -515,7 +520,7 @@ fn main() -> Result<()> {
       }
     }
     if updated {
-      terminal.draw(|f| ui(f, &mut filters, focus, &mut image_status))?;
+      terminal.draw(|f| ui(f, &mut filters, focus, &mut image_status, &fetcher))?;
     } else {
       sleep(Duration::from_millis(10));
     }
@@ -0,0 +1,200 @@
+//! Blob download with CID verification
+use std::{
+  collections::HashMap,
+  fs::{create_dir_all, read, remove_file, write},
+  path::{Path, PathBuf},
+  str::FromStr,
+  sync::{Arc, Mutex},
+};
+
+use anyhow::{bail, Result};
+use libipld::Cid;
+use url::Url;
+
+use crate::{
+  api::{verify_cid, CidString, Client, RAW_CODEC},
+  IdentityResolver,
+};
+
+/// Guess MIME type from the leading bytes of the data
+pub fn sniff_mime_type(data: &[u8]) -> &'static str {
+  match data {
+    [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, ..] => "image/png",
+    [0xff, 0xd8, 0xff, ..] => "image/jpeg",
+    [b'G', b'I', b'F', b'8', ..] => "image/gif",
+    [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+    [_, _, _, _, b'f', b't', b'y', b'p', b'a', b'v', b'i', b'f', ..] => "image/avif",
+    [_, _, _, _, b'f', b't', b'y', b'p', b'h', b'e', b'i', b'c', ..] => "image/heic",
+    [_, _, _, _, b'f', b't', b'y', b'p', b'q', b't', ..] => "video/quicktime",
+    [_, _, _, _, b'f', b't', b'y', b'p', ..] => "video/mp4",
+    [0x1a, 0x45, 0xdf, 0xa3, ..] => "video/webm",
+    [b'%', b'P', b'D', b'F', ..] => "application/pdf",
+    _ => "application/octet-stream",
+  }
+}
+
+/// Downloader of blobs from the PDS hosting the repository
+#[derive(Clone)]
+pub struct BlobFetcher {
+  cache_dir: Option<PathBuf>,
+  client: Client,
+  resolver: IdentityResolver,
+  pds_store: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl Default for BlobFetcher {
+  fn default() -> Self {
+    Self {
+      cache_dir: None,
+      client: Client::new(
+        "bsky.social",
+        "bsky.network",
+        std::env::var("HTTPS_PROXY")
+          .ok()
+          .or_else(|| std::env::var("https_proxy").ok()),
+      ),
+      resolver: IdentityResolver::default(),
+      pds_store: Arc::new(Mutex::new(HashMap::new())),
+    }
+  }
+}
+
+impl BlobFetcher {
+  /// Create blob fetcher caching the blobs in the directory
+  ///
+  /// Requests are sent to the PDS of each repository with the settings of the client,
+  /// like proxy, transport and retry policy, but without its session.
+  pub fn new<P: AsRef<Path>>(cache_dir: P, client: &Client) -> Self {
+    Self {
+      cache_dir: Some(cache_dir.as_ref().to_path_buf()),
+      client: client.clone(),
+      ..Default::default()
+    }
+  }
+
+  /// Set resolver to find the PDS of the repositories
+  pub fn set_identity_resolver(&mut self, resolver: IdentityResolver) {
+    self.resolver = resolver;
+  }
+
+  fn get_pds(&self, did: &str) -> Result<String> {
+    if let Some(host) = self.pds_store.lock().ok().and_then(|s| s.get(did).cloned()) {
+      return Ok(host);
+    }
+    let host = self.resolver.resolve_pds(did)?;
+    if let Ok(mut store) = self.pds_store.lock() {
+      store.insert(did.to_string(), host.clone());
+    }
+    Ok(host)
+  }
+
+  fn read_cache(&self, cid: &Cid) -> Option<Vec<u8>> {
+    let path = self.cache_dir.as_ref()?.join(cid.to_string());
+    let data = read(&path).ok()?;
+    match verify_cid(cid, &data) {
+      Ok(_) => Some(data),
+      Err(e) => {
+        log::warn!("remove broken cache : {}", e);
+        remove_file(path).ok();
+        None
+      }
+    }
+  }
+
+  fn write_cache(&self, cid: &Cid, data: &[u8]) {
+    let Some(dir) = &self.cache_dir else {
+      return;
+    };
+    if let Err(e) = create_dir_all(dir).and_then(|_| write(dir.join(cid.to_string()), data)) {
+      log::warn!("blob cache write error : {}", e);
+    }
+  }
+
+  /// Download the blob and returns its data and MIME type
+  pub fn fetch(&self, did: &str, cid: &str) -> Result<(Vec<u8>, String)> {
+    let cid = Cid::from_str(cid)?;
+    if cid.codec() != RAW_CODEC {
+      bail!("{} is not a blob", cid);
+    }
+    if let Some(data) = self.read_cache(&cid) {
+      let mime_type = sniff_mime_type(&data).to_string();
+      return Ok((data, mime_type));
+    }
+    let mut client = self.client.clone();
+    client.set_host(self.get_pds(did)?);
+    let data = client.com_atproto_sync_getblob(did, &CidString::from(cid))?;
+    verify_cid(&cid, &data)?;
+    self.write_cache(&cid, &data);
+    let mime_type = sniff_mime_type(&data).to_string();
+    Ok((data, mime_type))
+  }
+
+  /// Download the blob given by com.atproto.sync.getBlob URL
+  pub fn fetch_url(&self, url: &str) -> Result<(Vec<u8>, String)> {
+    let url = Url::parse(url)?;
+    let queries = url.query_pairs().collect::<HashMap<_, _>>();
+    let (Some(did), Some(cid)) = (queries.get("did"), queries.get("cid")) else {
+      bail!("{} is not a blob URL", url);
+    };
+    self.fetch(did, cid)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use libipld::multihash::Multihash;
+  use sha2::{Digest, Sha256};
+
+  use super::*;
+  use crate::{api::SHA2_256, HttpResponse, MockTransport};
+
+  #[test]
+  fn fetch_from_pds_with_client_settings() {
+    let data = b"\x89PNG\r\n\x1a\nimage".to_vec();
+    let cid = Cid::new_v1(
+      RAW_CODEC,
+      Multihash::wrap(SHA2_256, &Sha256::digest(&data)).unwrap(),
+    );
+    let transport = MockTransport::new();
+    transport.push_json(
+      "/.well-known/did.json",
+      200,
+      serde_json::json!({
+        "@context": ["https://www.w3.org/ns/did/v1"],
+        "id": "did:web:alice.test",
+        "alsoKnownAs": ["at://alice.test"],
+        "verificationMethod": [],
+        "service": [{
+          "id": "#atproto_pds",
+          "type": "AtprotoPersonalDataServer",
+          "serviceEndpoint": "https://pds.alice.test",
+        }],
+      }),
+    );
+    transport.push_response(
+      "/xrpc/com.atproto.sync.getBlob",
+      HttpResponse::new("/", 200, data.clone()),
+    );
+    let mut client = Client::new("bsky.test", "relay.test", None::<String>);
+    client.set_transport(transport.clone());
+    client.set_jwt(Some(String::from("token of bsky.test")));
+    let mut resolver = IdentityResolver::default();
+    resolver.set_transport(transport.clone());
+    let mut fetcher = BlobFetcher {
+      client,
+      ..Default::default()
+    };
+    fetcher.set_identity_resolver(resolver);
+    let (fetched, mime_type) = fetcher
+      .fetch("did:web:alice.test", &cid.to_string())
+      .unwrap();
+    assert_eq!(fetched, data);
+    assert_eq!(mime_type, "image/png");
+    let requests = transport.get_requests();
+    assert_eq!(requests[0].url(), "https://alice.test/.well-known/did.json");
+    assert!(requests[1]
+      .url()
+      .starts_with("https://pds.alice.test/xrpc/com.atproto.sync.getBlob?"));
+    assert_eq!(requests[1].header("Authorization"), None);
+  }
+}
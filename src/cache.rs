@@ -0,0 +1,363 @@
+//! Identity cache with expiration and firehose invalidation
+use std::{
+  collections::HashMap,
+  fs::File,
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{api::ComAtprotoRepoDescriberepo, xrpc::XrpcError, Event};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry<T> {
+  value: Option<T>,
+  expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheData {
+  repos: HashMap<String, CacheEntry<ComAtprotoRepoDescriberepo>>,
+  handles: HashMap<String, CacheEntry<String>>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+  data: CacheData,
+  dirty: bool,
+  saved_at: DateTime<Utc>,
+  path: Option<PathBuf>,
+}
+
+impl CacheState {
+  fn save(&mut self) -> Result<()> {
+    if let Some(path) = &self.path {
+      if self.dirty {
+        serde_json::to_writer(File::create(path)?, &self.data)?;
+      }
+    }
+    self.dirty = false;
+    self.saved_at = Utc::now();
+    Ok(())
+  }
+}
+
+impl Drop for CacheState {
+  // updates after the last save are written when the last clone of the cache is dropped
+  fn drop(&mut self) {
+    if let Err(e) = self.save() {
+      log::warn!("identity cache save error : {}", e);
+    }
+  }
+}
+
+fn get_entry<T: Clone>(map: &mut HashMap<String, CacheEntry<T>>, key: &str) -> Option<Option<T>> {
+  match map.get(key) {
+    Some(e) if e.expires_at > Utc::now() => Some(e.value.clone()),
+    Some(_) => {
+      map.remove(key);
+      None
+    }
+    None => None,
+  }
+}
+
+fn evict<T>(map: &mut HashMap<String, CacheEntry<T>>, capacity: usize) {
+  if map.len() <= capacity {
+    return;
+  }
+  let now = Utc::now();
+  map.retain(|_, e| e.expires_at > now);
+  if map.len() <= capacity {
+    return;
+  }
+  let mut keys = map
+    .iter()
+    .map(|(k, e)| (e.expires_at, k.clone()))
+    .collect::<Vec<_>>();
+  keys.sort();
+  for (_, key) in keys.into_iter().take(map.len() - capacity) {
+    map.remove(&key);
+  }
+}
+
+/// Handle of accounts failing the handle verification
+const INVALID_HANDLE: &str = "handle.invalid";
+
+/// Returns true if the error may not happen on retry
+pub fn is_transient(e: &anyhow::Error) -> bool {
+  if let Some(e) = e.downcast_ref::<XrpcError>() {
+    return e.is_transient();
+  }
+  match e.downcast_ref::<ureq::Error>() {
+    Some(ureq::Error::Status(status, _)) => *status == 429 || *status >= 500,
+    Some(ureq::Error::Transport(_)) => true,
+    None => false,
+  }
+}
+
+/// Cache of repository descriptions and handle resolutions shared between clients
+#[derive(Debug, Clone)]
+pub struct IdentityCache {
+  state: Arc<Mutex<CacheState>>,
+  ttl: Duration,
+  negative_ttl: Duration,
+  capacity: usize,
+  save_interval: Duration,
+}
+
+impl Default for IdentityCache {
+  fn default() -> Self {
+    Self {
+      state: Arc::new(Mutex::new(CacheState::default())),
+      ttl: Duration::hours(1),
+      negative_ttl: Duration::minutes(5),
+      capacity: 10000,
+      save_interval: Duration::seconds(10),
+    }
+  }
+}
+
+impl IdentityCache {
+  /// Create cache persisted in the JSON file, loaded if exists
+  ///
+  /// The file is written at most once in 10 seconds and when the last clone of the cache is dropped,
+  /// call `flush` before exiting without dropping it like `std::process::exit`.
+  pub fn open<P: AsRef<Path>>(path: P) -> Self {
+    let data = File::open(&path)
+      .ok()
+      .and_then(|f| serde_json::from_reader::<_, CacheData>(f).ok())
+      .unwrap_or_default();
+    Self {
+      state: Arc::new(Mutex::new(CacheState {
+        data,
+        path: Some(path.as_ref().to_path_buf()),
+        ..Default::default()
+      })),
+      ..Default::default()
+    }
+  }
+
+  /// Set seconds to keep resolved entries
+  pub fn set_ttl(&mut self, seconds: i64) {
+    self.ttl = Duration::seconds(seconds);
+  }
+
+  /// Set seconds to keep failed resolutions
+  pub fn set_negative_ttl(&mut self, seconds: i64) {
+    self.negative_ttl = Duration::seconds(seconds);
+  }
+
+  /// Set maximum number of entries of each kind
+  pub fn set_capacity(&mut self, capacity: usize) {
+    self.capacity = capacity;
+  }
+
+  fn expires_at(&self, found: bool) -> DateTime<Utc> {
+    Utc::now()
+      + match found {
+        true => self.ttl,
+        false => self.negative_ttl,
+      }
+  }
+
+  fn updated(&self, state: &mut CacheState) {
+    state.dirty = true;
+    if Utc::now() - state.saved_at >= self.save_interval {
+      if let Err(e) = state.save() {
+        log::warn!("identity cache save error : {}", e);
+      }
+    }
+  }
+
+  /// Write the cache to the file now
+  pub fn flush(&self) -> Result<()> {
+    match self.state.lock() {
+      Ok(mut state) => state.save(),
+      Err(_) => Ok(()),
+    }
+  }
+
+  /// Returns Some(None) if the repository is cached as not found
+  pub fn get_repo(&self, did: &str) -> Option<Option<ComAtprotoRepoDescriberepo>> {
+    get_entry(&mut self.state.lock().ok()?.data.repos, did)
+  }
+
+  /// Store description of the repository, None if not found
+  pub fn put_repo(&self, did: &str, repo: Option<ComAtprotoRepoDescriberepo>) {
+    if let Ok(mut state) = self.state.lock() {
+      let entry = CacheEntry {
+        expires_at: self.expires_at(repo.is_some()),
+        value: repo,
+      };
+      state.data.repos.insert(did.to_string(), entry);
+      evict(&mut state.data.repos, self.capacity);
+      self.updated(&mut state);
+    }
+  }
+
+  /// Returns Some(None) if the handle is cached as not resolved, handles are case-insensitive
+  pub fn get_handle(&self, handle: &str) -> Option<Option<String>> {
+    get_entry(
+      &mut self.state.lock().ok()?.data.handles,
+      &handle.to_lowercase(),
+    )
+  }
+
+  /// Store DID of the handle, None if not resolved
+  pub fn put_handle(&self, handle: &str, did: Option<String>) {
+    if let Ok(mut state) = self.state.lock() {
+      let entry = CacheEntry {
+        expires_at: self.expires_at(did.is_some()),
+        value: did,
+      };
+      state.data.handles.insert(handle.to_lowercase(), entry);
+      evict(&mut state.data.handles, self.capacity);
+      self.updated(&mut state);
+    }
+  }
+
+  /// Remove entries of the DID
+  pub fn invalidate(&self, did: &str) {
+    if let Ok(mut state) = self.state.lock() {
+      state.data.repos.remove(did);
+      state
+        .data
+        .handles
+        .retain(|_, e| e.value.as_deref() != Some(did));
+      self.updated(&mut state);
+    }
+  }
+
+  /// Remove entry of the handle
+  pub fn invalidate_handle(&self, handle: &str) {
+    if let Ok(mut state) = self.state.lock() {
+      state.data.handles.remove(&handle.to_lowercase());
+      self.updated(&mut state);
+    }
+  }
+
+  /// Remove all entries
+  pub fn clear(&self) {
+    if let Ok(mut state) = self.state.lock() {
+      state.data = CacheData::default();
+      self.updated(&mut state);
+    }
+  }
+
+  /// Remove entries of the DID and map the new handle to it if valid
+  fn update_handle(&self, did: &str, handle: Option<&str>) {
+    self.invalidate(did);
+    if let Some(handle) = handle.filter(|h| *h != INVALID_HANDLE) {
+      self.put_handle(handle, Some(did.to_string()));
+    }
+  }
+
+  /// Update entries changed by #identity, #handle or #tombstone event
+  pub fn apply_event(&self, event: &Event) {
+    if let Some(identity) = event.as_identity() {
+      self.update_handle(&identity.did, identity.handle.as_deref());
+    } else if let Some(handle) = event.as_handle() {
+      self.update_handle(&handle.did, Some(&handle.handle));
+    } else if let Some(tombstone) = event.as_tombstone() {
+      self.invalidate(&tombstone.did);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::api::{
+    ComAtprotoSyncSubscribereposHandle, ComAtprotoSyncSubscribereposIdentity,
+    ComAtprotoSyncSubscribereposMainMessage,
+  };
+
+  fn identity(did: &str, handle: Option<&str>) -> Event {
+    Event {
+      payload: ComAtprotoSyncSubscribereposMainMessage::ComAtprotoSyncSubscribereposIdentity(
+        Box::new(ComAtprotoSyncSubscribereposIdentity {
+          did: did.to_string(),
+          handle: handle.map(String::from),
+          ..Default::default()
+        }),
+      ),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn handles_are_case_insensitive() {
+    let cache = IdentityCache::default();
+    cache.put_handle("Alice.Example.COM", Some(String::from("did:plc:alice")));
+    assert_eq!(
+      cache.get_handle("alice.example.com"),
+      Some(Some(String::from("did:plc:alice")))
+    );
+    cache.invalidate_handle("ALICE.example.com");
+    assert_eq!(cache.get_handle("Alice.Example.COM"), None);
+  }
+
+  #[test]
+  fn identity_event_updates_handle() {
+    let cache = IdentityCache::default();
+    cache.put_handle("alice.example.com", Some(String::from("did:plc:alice")));
+    cache.put_repo("did:plc:alice", None);
+    cache.apply_event(&identity("did:plc:alice", Some("Alice.Example.ORG")));
+    assert_eq!(cache.get_handle("alice.example.com"), None);
+    assert_eq!(
+      cache.get_handle("alice.example.org"),
+      Some(Some(String::from("did:plc:alice")))
+    );
+    assert!(cache.get_repo("did:plc:alice").is_none());
+    // identity without handle or with invalid handle only evicts
+    cache.apply_event(&identity("did:plc:alice", Some(INVALID_HANDLE)));
+    assert_eq!(cache.get_handle("alice.example.org"), None);
+    assert_eq!(cache.get_handle(INVALID_HANDLE), None);
+  }
+
+  #[test]
+  fn save_on_drop() {
+    let path = std::env::temp_dir().join(format!("aerostream-cache-{}.json", std::process::id()));
+    let cache = IdentityCache::open(&path);
+    cache.put_handle("alice.example.com", Some(String::from("did:plc:alice")));
+    // written only on drop, it is within the save interval of the first write
+    let clone = cache.clone();
+    clone.put_handle("bob.example.com", None);
+    drop(clone);
+    drop(cache);
+    let cache = IdentityCache::open(&path);
+    assert_eq!(
+      cache.get_handle("alice.example.com"),
+      Some(Some(String::from("did:plc:alice")))
+    );
+    assert_eq!(cache.get_handle("bob.example.com"), Some(None));
+    drop(cache);
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn handle_event_updates_handle() {
+    let cache = IdentityCache::default();
+    cache.put_handle("bob.example.com", None);
+    let event = Event {
+      payload: ComAtprotoSyncSubscribereposMainMessage::ComAtprotoSyncSubscribereposHandle(
+        Box::new(ComAtprotoSyncSubscribereposHandle {
+          did: String::from("did:plc:bob"),
+          handle: String::from("bob.example.com"),
+          ..Default::default()
+        }),
+      ),
+      ..Default::default()
+    };
+    cache.apply_event(&event);
+    assert_eq!(
+      cache.get_handle("bob.example.com"),
+      Some(Some(String::from("did:plc:bob")))
+    );
+  }
+}
@@ -0,0 +1,262 @@
+//! Direct messages through the chat service
+use std::{collections::VecDeque, thread::sleep, time::Duration};
+
+use anyhow::Result;
+
+use crate::api::{
+  ChatBskyConvoDefsLogcreatemessageMessage, ChatBskyConvoDefsMessageview,
+  ChatBskyConvoGetlogMainOutputLogsItem, Client,
+};
+
+/// Service the PDS proxies chat.bsky.* calls to
+pub const CHAT_SERVICE: &str = "did:web:api.bsky.chat#bsky_chat";
+
+/// Seconds between polls of the chat log by default
+pub const POLL_INTERVAL_SECONDS: u64 = 5;
+
+/// Message created in a conversation
+#[derive(Debug, Clone)]
+pub struct ChatMessage {
+  pub convo_id: String,
+  pub rev: String,
+  pub message: ChatBskyConvoDefsMessageview,
+}
+
+/// Iterator blocking until messages created after it starts arrive, errors do not stop the polling
+pub struct MessagePoller<'a> {
+  client: &'a Client,
+  cursor: Option<String>,
+  interval: Duration,
+  exclude_sender: Option<String>,
+  buffer: VecDeque<ChatMessage>,
+  started: bool,
+}
+
+impl<'a> MessagePoller<'a> {
+  pub fn new(client: &'a Client) -> Self {
+    Self {
+      client,
+      cursor: None,
+      interval: Duration::from_secs(POLL_INTERVAL_SECONDS),
+      exclude_sender: None,
+      buffer: VecDeque::new(),
+      started: false,
+    }
+  }
+
+  /// Set wait between polls while no log is added
+  pub fn interval(mut self, interval: Duration) -> Self {
+    self.interval = interval;
+    self
+  }
+
+  /// Skip messages sent by the DID, usually the account itself
+  pub fn exclude_sender<T: ToString>(mut self, did: T) -> Self {
+    self.exclude_sender = Some(did.to_string());
+    self
+  }
+
+  /// Resume from the cursor of the previous polling instead of the latest log
+  pub fn cursor<T: ToString>(mut self, cursor: T) -> Self {
+    self.cursor = Some(cursor.to_string());
+    self.started = true;
+    self
+  }
+
+  /// Returns cursor of the chat log to resume later
+  pub fn get_cursor(&self) -> Option<String> {
+    self.cursor.clone()
+  }
+
+  /// Fetch logs after the cursor, returns false if there are no new logs
+  fn poll(&mut self) -> Result<bool> {
+    let log = self.client.chat_bsky_convo_getlog(self.cursor.as_deref())?;
+    let updated = !log.logs.is_empty() && log.cursor.is_some() && log.cursor != self.cursor;
+    if log.cursor.is_some() {
+      self.cursor = log.cursor;
+    }
+    // logs before starting are only used to find the latest cursor, page through all of them
+    if !self.started {
+      self.started = !updated;
+      return Ok(updated);
+    }
+    for item in log.logs {
+      let ChatBskyConvoGetlogMainOutputLogsItem::ChatBskyConvoDefsLogcreatemessage(log) = item
+      else {
+        continue;
+      };
+      let ChatBskyConvoDefsLogcreatemessageMessage::ChatBskyConvoDefsMessageview(message) =
+        log.message
+      else {
+        continue;
+      };
+      if self.exclude_sender.as_ref() == Some(&message.sender.did) {
+        continue;
+      }
+      self.buffer.push_back(ChatMessage {
+        convo_id: log.convo_id,
+        rev: log.rev,
+        message: *message,
+      });
+    }
+    Ok(updated)
+  }
+}
+
+impl Iterator for MessagePoller<'_> {
+  type Item = Result<ChatMessage>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(message) = self.buffer.pop_front() {
+        return Some(Ok(message));
+      }
+      match self.poll() {
+        Ok(true) => (),
+        Ok(false) => sleep(self.interval),
+        Err(e) => return Some(Err(e)),
+      }
+    }
+  }
+}
+
+impl Client {
+  /// Iterate messages sent to any conversation of the account from now on
+  pub fn chat_bsky_convo_poll_messages(&self) -> MessagePoller<'_> {
+    MessagePoller::new(self)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::{json, Value};
+
+  use super::*;
+  use crate::MockTransport;
+
+  const GET_LOG: &str = "/xrpc/chat.bsky.convo.getLog";
+
+  fn create_message(rev: &str, sender: &str, text: &str) -> Value {
+    json!({
+      "$type": "chat.bsky.convo.defs#logCreateMessage",
+      "rev": rev,
+      "convoId": "convo",
+      "message": {
+        "$type": "chat.bsky.convo.defs#messageView",
+        "id": format!("message-{}", rev),
+        "rev": rev,
+        "text": text,
+        "sender": { "did": sender },
+        "sentAt": "2024-05-01T00:00:00.000Z"
+      }
+    })
+  }
+
+  fn client(transport: &MockTransport) -> Client {
+    let mut client = Client::new("pds.test", "relay.test", None::<String>);
+    client.set_transport(transport.clone());
+    client
+  }
+
+  #[test]
+  fn poll_messages_after_start() {
+    let transport = MockTransport::new();
+    transport.push_json(
+      GET_LOG,
+      200,
+      json!({ "logs": [create_message("1", "did:plc:bob", "old")], "cursor": "1" }),
+    );
+    transport.push_json(GET_LOG, 200, json!({ "logs": [] }));
+    transport.push_json(
+      GET_LOG,
+      200,
+      json!({
+        "logs": [
+          { "$type": "chat.bsky.convo.defs#logBeginConvo", "rev": "2", "convoId": "convo" },
+          create_message("3", "did:plc:alice", "mine"),
+          create_message("4", "did:plc:bob", "new"),
+        ],
+        "cursor": "4"
+      }),
+    );
+    let client = client(&transport);
+    let mut poller = client
+      .chat_bsky_convo_poll_messages()
+      .interval(Duration::ZERO)
+      .exclude_sender("did:plc:alice");
+    let message = poller.next().unwrap().unwrap();
+    assert_eq!(message.convo_id, "convo");
+    assert_eq!(message.rev, "4");
+    assert_eq!(message.message.text, "new");
+    assert!(poller.buffer.is_empty());
+    assert_eq!(poller.get_cursor().as_deref(), Some("4"));
+    let requests = transport.get_requests();
+    assert_eq!(requests.len(), 3);
+    assert!(!requests[0].url().contains("cursor="));
+    assert!(requests[1].url().contains("cursor=1"));
+    assert!(requests[2].url().contains("cursor=1"));
+    assert!(requests
+      .iter()
+      .all(|r| r.header("atproto-proxy") == Some(CHAT_SERVICE)));
+  }
+
+  #[test]
+  fn skip_every_page_of_logs_before_start() {
+    let transport = MockTransport::new();
+    transport.push_json(
+      GET_LOG,
+      200,
+      json!({ "logs": [create_message("1", "did:plc:bob", "old")], "cursor": "1" }),
+    );
+    transport.push_json(
+      GET_LOG,
+      200,
+      json!({ "logs": [create_message("2", "did:plc:bob", "older")], "cursor": "2" }),
+    );
+    transport.push_json(GET_LOG, 200, json!({ "logs": [], "cursor": "2" }));
+    let client = client(&transport);
+    let mut poller = client.chat_bsky_convo_poll_messages();
+    assert!(poller.poll().unwrap());
+    assert!(poller.poll().unwrap());
+    assert!(!poller.started);
+    assert!(!poller.poll().unwrap());
+    assert!(poller.started);
+    assert!(poller.buffer.is_empty());
+    assert_eq!(poller.get_cursor().as_deref(), Some("2"));
+  }
+
+  #[test]
+  fn resume_from_cursor() {
+    let transport = MockTransport::new();
+    transport.push_json(
+      GET_LOG,
+      200,
+      json!({ "logs": [create_message("2", "did:plc:bob", "missed")], "cursor": "2" }),
+    );
+    let client = client(&transport);
+    let mut poller = client
+      .chat_bsky_convo_poll_messages()
+      .interval(Duration::ZERO)
+      .cursor("1");
+    assert_eq!(poller.next().unwrap().unwrap().message.text, "missed");
+    assert!(transport.get_requests()[0].url().contains("cursor=1"));
+  }
+
+  #[test]
+  fn errors_do_not_stop_polling() {
+    let transport = MockTransport::new();
+    transport.push_json(GET_LOG, 400, json!({ "error": "InvalidRequest" }));
+    transport.push_json(
+      GET_LOG,
+      200,
+      json!({ "logs": [create_message("2", "did:plc:bob", "after")], "cursor": "2" }),
+    );
+    let client = client(&transport);
+    let mut poller = client
+      .chat_bsky_convo_poll_messages()
+      .interval(Duration::ZERO)
+      .cursor("1");
+    assert!(poller.next().unwrap().is_err());
+    assert_eq!(poller.next().unwrap().unwrap().message.text, "after");
+  }
+}
@@ -11,18 +11,21 @@ use anyhow::{anyhow, bail, Result};
 use chrono::{DateTime, Datelike, Utc};
 use serde::Deserialize;
 use tungstenite::Message;
-use url::Url;
 
-use crate::{api::*, Plc};
-use crate::{Event, Filters};
+use crate::api::*;
+use crate::{
+  cache::is_transient, xrpc::XrpcError, Event, Filters, HttpTransport, IdentityCache,
+  IdentityResolver, OAuthAgent, Session, SessionStore,
+};
 
 /// Client to use Bluesky server
 pub struct Client {
   pub client: crate::api::Client,
-  pub refresh_jwt: Option<String>,
   repo: Option<String>,
-  repo_store: Arc<Mutex<HashMap<String, ComAtprotoRepoDescriberepo>>>,
-  handle_store: Arc<Mutex<HashMap<String, String>>>,
+  handle: Option<String>,
+  session_store: Option<Arc<dyn SessionStore>>,
+  resolver: IdentityResolver,
+  cache: IdentityCache,
   thread: Option<JoinHandle<()>>,
   rx: Arc<Mutex<HashMap<String, Receiver<Event>>>>,
   last_received: Arc<Mutex<DateTime<Utc>>>,
@@ -32,18 +35,13 @@ pub struct Client {
 
 impl Clone for Client {
   fn clone(&self) -> Self {
-    let mut client = crate::api::Client::new(
-      self.client.get_host(),
-      self.client.get_bgs_host(),
-      self.client.get_proxy(),
-    );
-    client.set_jwt(self.client.get_jwt());
     Self {
-      client,
-      refresh_jwt: None,
+      client: self.client.clone(),
       repo: None,
-      repo_store: Arc::clone(&self.repo_store),
-      handle_store: Arc::clone(&self.handle_store),
+      handle: None,
+      session_store: self.session_store.clone(),
+      resolver: self.resolver.clone(),
+      cache: self.cache.clone(),
       thread: None,
       rx: Arc::clone(&self.rx),
       last_received: Arc::new(Mutex::new(DateTime::default())),
@@ -60,10 +58,11 @@ impl Default for Client {
       .or_else(|| std::env::var("https_proxy").ok());
     let mut client = Self {
       client: crate::api::Client::new("bsky.social", "bsky.network", proxy),
-      refresh_jwt: None,
       repo: None,
-      repo_store: Arc::new(Mutex::new(HashMap::new())),
-      handle_store: Arc::new(Mutex::new(HashMap::new())),
+      handle: None,
+      session_store: None,
+      resolver: IdentityResolver::default(),
+      cache: IdentityCache::default(),
       thread: None,
       rx: Arc::new(Mutex::new(HashMap::new())),
       last_received: Arc::new(Mutex::new(DateTime::default())),
@@ -87,6 +86,8 @@ fn receiver_thread(
   last_received: Arc<Mutex<DateTime<Utc>>>,
   mut tx_map: HashMap<String, Sender<Event>>,
   filters: Arc<Mutex<Filters>>,
+  verify_blocks: bool,
+  cache: IdentityCache,
 ) {
   let mut last_seq = None;
   let mut is_terminating = false;
@@ -116,6 +117,13 @@ fn receiver_thread(
           if let Some(seq) = event.get_seq() {
             last_seq = Some(seq);
           }
+          cache.apply_event(&event);
+          if verify_blocks {
+            if let Some(Err(e)) = event.as_commit().map(|c| c.verify_blocks()) {
+              log::warn!("drop commit with invalid blocks : {}", e);
+              continue;
+            }
+          }
           if let Some(time) = event.get_time() {
             if let Ok(mut write) = last_received.lock() {
               *write = time;
@@ -174,8 +182,47 @@ impl Client {
 
   /// Set Host
   pub fn set_host<T: ToString>(&mut self, host: T) {
-    self.client =
-      crate::api::Client::new(host, self.client.get_bgs_host(), self.client.get_proxy());
+    self.client.set_host(host);
+  }
+
+  /// Set callback called with new tokens after the access token is refreshed automatically
+  pub fn set_refresh_callback<F: Fn(&ComAtprotoServerRefreshsession) + Send + Sync + 'static>(
+    &mut self,
+    callback: F,
+  ) {
+    self.client.set_refresh_callback(callback);
+  }
+
+  /// Verify CIDs of the blocks in WebSocket commits and downloaded CAR files
+  pub fn set_verify_blocks(&mut self, verify: bool) {
+    self.client.set_verify_blocks(verify);
+  }
+
+  /// Set labelers applied to responses of the AppView
+  pub fn set_accept_labelers<T: ToString>(&mut self, labelers: &[T]) {
+    self.client.set_accept_labelers(labelers);
+  }
+
+  /// Set resolver to find the PDS of the account on login and DIDs of handles
+  pub fn set_identity_resolver(&mut self, resolver: IdentityResolver) {
+    self.resolver = resolver;
+  }
+
+  /// Set transport to send HTTP requests of the API and identity lookups instead of ureq
+  pub fn set_transport<T: HttpTransport + 'static>(&mut self, transport: T) {
+    let transport: Arc<dyn HttpTransport> = Arc::new(transport);
+    self.client.set_transport(Arc::clone(&transport));
+    self.resolver.set_transport(transport);
+  }
+
+  /// Set cache of repositories and handles, shared with the WebSocket receiver
+  pub fn set_identity_cache(&mut self, cache: IdentityCache) {
+    self.cache = cache;
+  }
+
+  /// Returns cache of repositories and handles
+  pub fn get_identity_cache(&self) -> &IdentityCache {
+    &self.cache
   }
 
   /// Set timeout for waiting to receive WebSocket events
@@ -186,35 +233,129 @@ impl Client {
   /// Login to Bluesky server
   pub fn login<T1: ToString, T2: ToString>(&mut self, id: T1, pw: T2) -> Result<()> {
     let id = id.to_string();
-    if let Ok(did) = self.client.com_atproto_identity_resolvehandle(&id) {
-      if let Ok(diddoc) = Plc::default().resolve_did(&did.did) {
-        if let Some(pds) = diddoc.service.iter().find_map(|s| match &s {
-          AtprotoService::AtprotoPds(pds) => {
-            (pds.pds_type == "AtprotoPersonalDataServer").then(|| pds)
-          }
-          AtprotoService::Other => None,
-        }) {
-          if let Ok(url) = Url::parse(&pds.service_endpoint) {
-            if let Some(host) = url.host() {
-              self.set_host(host);
-            }
-          }
-        }
+    // login by email keeps the current host since it cannot be resolved
+    let did = match id.starts_with("did:") {
+      true => Some(id.clone()),
+      false => self.resolver.resolve_handle(&id).ok(),
+    };
+    if let Some(did) = did {
+      match self.resolver.resolve_pds(&did) {
+        Ok(host) => self.set_host(host),
+        Err(e) => log::warn!("PDS resolution error of {} : {}", did, e),
       }
     }
     let session = self
       .client
       .com_atproto_server_createsession(&id, &pw.to_string())?;
-    self.refresh_jwt = Some(session.refresh_jwt);
     self.client.set_jwt(Some(session.access_jwt));
-    self.repo = self
+    self.client.set_refresh_jwt(Some(session.refresh_jwt));
+    self.repo = Some(session.did);
+    self.handle = Some(session.handle);
+    self.watch_session()?;
+    Ok(())
+  }
+
+  /// Returns the refresh token of the current session, updated by automatic refresh
+  pub fn get_refresh_jwt(&self) -> Option<String> {
+    self.client.get_refresh_jwt()
+  }
+
+  /// Returns the current session to save and resume later
+  pub fn get_session(&self) -> Option<Session> {
+    Some(Session {
+      did: self.repo.clone()?,
+      handle: self.handle.clone().unwrap_or_default(),
+      host: self.client.get_host(),
+      access_jwt: self.client.get_jwt()?,
+      refresh_jwt: self.client.get_refresh_jwt()?,
+    })
+  }
+
+  /// Set storage to save the session after login, resume and token refresh
+  pub fn set_session_store<S: SessionStore + 'static>(&mut self, store: S) {
+    self.session_store = Some(Arc::new(store));
+  }
+
+  fn watch_session(&mut self) -> Result<()> {
+    let Some(store) = self.session_store.clone() else {
+      return Ok(());
+    };
+    if let Some(session) = self.get_session() {
+      store.save(&session)?;
+    }
+    let host = self.client.get_host();
+    self.client.set_refresh_callback(move |s| {
+      let session = Session {
+        did: s.did.clone(),
+        handle: s.handle.clone(),
+        host: host.clone(),
+        access_jwt: s.access_jwt.clone(),
+        refresh_jwt: s.refresh_jwt.clone(),
+      };
+      if let Err(e) = store.save(&session) {
+        log::warn!("session save error : {}", e);
+      }
+    });
+    Ok(())
+  }
+
+  /// Resume the saved session without password, refreshing the tokens if expired
+  pub fn resume_session(&mut self, session: Session) -> Result<Session> {
+    self.set_host(&session.host);
+    self.client.set_jwt(Some(session.access_jwt));
+    self
       .client
-      .com_atproto_identity_resolvehandle(&id)
-      .ok()
-      .map(|h| h.did);
+      .set_refresh_jwt(Some(session.refresh_jwt.clone()));
+    let (did, handle) = match self.client.com_atproto_server_getsession() {
+      Ok(s) => (s.did, s.handle),
+      Err(e)
+        if e
+          .downcast_ref::<XrpcError>()
+          .map(|e| e.is_auth_error())
+          .unwrap_or_default() =>
+      {
+        log::info!("refresh session : {}", e);
+        let s = self.client.com_atproto_server_refreshsession()?;
+        (s.did, s.handle)
+      }
+      Err(e) => return Err(e),
+    };
+    self.repo = Some(did);
+    self.handle = Some(handle);
+    self.watch_session()?;
+    self
+      .get_session()
+      .ok_or_else(|| anyhow!("session is not resumed"))
+  }
+
+  /// Login with the OAuth session instead of password
+  pub fn login_oauth(&mut self, oauth: OAuthAgent) -> Result<()> {
+    let did = oauth
+      .get_session()
+      .ok_or_else(|| anyhow!("no OAuth session"))?
+      .did;
+    self.client.set_oauth(oauth)?;
+    self.handle = Some(self.client.com_atproto_server_getsession()?.handle);
+    self.repo = Some(did);
     Ok(())
   }
 
+  /// Resume the session in the store, or login with the password if it cannot be resumed
+  pub fn resume_or_login<T1: ToString, T2: ToString>(&mut self, id: T1, pw: T2) -> Result<()> {
+    let id = id.to_string();
+    let saved = match &self.session_store {
+      Some(store) => store.load()?,
+      None => None,
+    };
+    if let Some(session) = saved.filter(|s| s.did == id || s.handle == id) {
+      match self.resume_session(session) {
+        Ok(_) => return Ok(()),
+        Err(e) => log::warn!("session resume error : {}", e),
+      }
+    }
+    self.login(id, pw)
+  }
+
   /// Post text to Bluesky server
   pub fn post<T: ToString>(&self, text: T) -> Result<()> {
     let repo = self.repo.as_ref().ok_or_else(|| anyhow!("no login"))?;
@@ -298,11 +439,21 @@ impl Client {
       }
     }
     let filters = Arc::clone(&self.filters);
+    let verify_blocks = self.client.get_verify_blocks();
+    let cache = self.cache.clone();
     self.last_received = Arc::new(Mutex::new(DateTime::default()));
     let last_received = Arc::clone(&self.last_received);
     self.rx = Arc::new(Mutex::new(rx_map));
     self.thread = Some(spawn(move || {
-      receiver_thread(host, bgs_host, last_received, tx_map, filters);
+      receiver_thread(
+        host,
+        bgs_host,
+        last_received,
+        tx_map,
+        filters,
+        verify_blocks,
+        cache,
+      );
     }));
     Ok(())
   }
@@ -374,43 +525,41 @@ impl Client {
   /// Return repository information via cache
   pub fn get_repo<T: ToString>(&mut self, did: T) -> Result<ComAtprotoRepoDescriberepo> {
     let did = did.to_string();
-    if let Ok(mut repo_store) = self.repo_store.lock() {
-      match repo_store.get(&did) {
-        Some(r) => {
-          return Ok(r.clone());
-        }
-        None => {
-          let repo = self.client.com_atproto_repo_describerepo(&did)?;
-          repo_store.insert(did, repo.clone());
-          return Ok(repo);
+    if let Some(repo) = self.cache.get_repo(&did) {
+      return repo.ok_or_else(|| anyhow!("repository {} is not found", did));
+    }
+    match self.client.com_atproto_repo_describerepo(&did) {
+      Ok(repo) => {
+        self.cache.put_repo(&did, Some(repo.clone()));
+        Ok(repo)
+      }
+      Err(e) => {
+        if !is_transient(&e) {
+          self.cache.put_repo(&did, None);
         }
+        Err(e)
       }
     }
-    self.get_repo(did)
   }
 
   /// Return DID information via cache
   pub fn get_handle<T: ToString>(&mut self, handle: T) -> Result<String> {
     let handle = handle.to_string();
-    if let Ok(mut handle_store) = self.handle_store.lock() {
-      match handle_store.get(&handle) {
-        Some(h) => {
-          return Ok(h.clone());
-        }
-        None => {
-          let did = self.client.com_atproto_identity_resolvehandle(&handle)?;
-          handle_store.insert(handle, did.did.clone());
-          return Ok(did.did.clone());
+    if let Some(did) = self.cache.get_handle(&handle) {
+      return did.ok_or_else(|| anyhow!("handle {} is not resolved", handle));
+    }
+    match self.resolver.resolve_handle(&handle) {
+      Ok(did) => {
+        self.cache.put_handle(&handle, Some(did.clone()));
+        Ok(did)
+      }
+      Err(e) => {
+        if !is_transient(&e) {
+          self.cache.put_handle(&handle, None);
         }
+        Err(e)
       }
     }
-    Ok(
-      self
-        .client
-        .com_atproto_identity_resolvehandle(&handle)?
-        .did
-        .clone(),
-    )
   }
 
   /// Get Filter names
@@ -0,0 +1,215 @@
+//! Signing keys used by repositories and identities
+use anyhow::{bail, Result};
+use k256::ecdsa::signature::{Signer, Verifier};
+use libipld::multibase::Base;
+use rand_core::OsRng;
+use serde_json::{json, Value};
+
+const SECP256K1_PUB: [u8; 2] = [0xe7, 0x01];
+const P256_PUB: [u8; 2] = [0x80, 0x24];
+
+/// Elliptic curve of a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAlgorithm {
+  Secp256k1,
+  P256,
+}
+
+impl KeyAlgorithm {
+  /// Returns JWS algorithm name, ES256K or ES256
+  pub fn jwt_algorithm(&self) -> &'static str {
+    match self {
+      Self::Secp256k1 => "ES256K",
+      Self::P256 => "ES256",
+    }
+  }
+}
+
+/// Private key to sign repository commits and identity operations
+#[derive(Debug, Clone)]
+pub enum Keypair {
+  Secp256k1(k256::ecdsa::SigningKey),
+  P256(p256::ecdsa::SigningKey),
+}
+
+impl Keypair {
+  /// Generate new random key
+  pub fn generate(algorithm: KeyAlgorithm) -> Self {
+    match algorithm {
+      KeyAlgorithm::Secp256k1 => Self::Secp256k1(k256::ecdsa::SigningKey::random(&mut OsRng)),
+      KeyAlgorithm::P256 => Self::P256(p256::ecdsa::SigningKey::random(&mut OsRng)),
+    }
+  }
+
+  /// Load key from 32 bytes private scalar
+  pub fn from_bytes(algorithm: KeyAlgorithm, bytes: &[u8]) -> Result<Self> {
+    Ok(match algorithm {
+      KeyAlgorithm::Secp256k1 => Self::Secp256k1(k256::ecdsa::SigningKey::from_slice(bytes)?),
+      KeyAlgorithm::P256 => Self::P256(p256::ecdsa::SigningKey::from_slice(bytes)?),
+    })
+  }
+
+  /// Returns 32 bytes private scalar
+  pub fn to_bytes(&self) -> Vec<u8> {
+    match self {
+      Self::Secp256k1(k) => k.to_bytes().to_vec(),
+      Self::P256(k) => k.to_bytes().to_vec(),
+    }
+  }
+
+  /// Returns elliptic curve of the key
+  pub fn algorithm(&self) -> KeyAlgorithm {
+    match self {
+      Self::Secp256k1(_) => KeyAlgorithm::Secp256k1,
+      Self::P256(_) => KeyAlgorithm::P256,
+    }
+  }
+
+  /// Sign SHA-256 hash of the data, returns 64 bytes low-S signature
+  pub fn sign(&self, data: &[u8]) -> Vec<u8> {
+    match self {
+      Self::Secp256k1(k) => {
+        let sig: k256::ecdsa::Signature = k.sign(data);
+        sig.normalize_s().unwrap_or(sig).to_bytes().to_vec()
+      }
+      Self::P256(k) => {
+        let sig: p256::ecdsa::Signature = k.sign(data);
+        sig.normalize_s().unwrap_or(sig).to_bytes().to_vec()
+      }
+    }
+  }
+
+  /// Returns public key
+  pub fn public_key(&self) -> PublicKey {
+    match self {
+      Self::Secp256k1(k) => PublicKey::Secp256k1(*k.verifying_key()),
+      Self::P256(k) => PublicKey::P256(*k.verifying_key()),
+    }
+  }
+
+  /// Returns public key as did:key
+  pub fn did_key(&self) -> String {
+    self.public_key().to_did_key()
+  }
+
+  /// Create compact JWS of the claims, alg is added to the header
+  pub fn sign_jwt(&self, header: &Value, claims: &Value) -> Result<String> {
+    let mut header = header.clone();
+    if let Some(h) = header.as_object_mut() {
+      h.insert(
+        String::from("alg"),
+        Value::from(self.algorithm().jwt_algorithm()),
+      );
+    }
+    let input = format!(
+      "{}.{}",
+      Base::Base64Url.encode(serde_json::to_vec(&header)?),
+      Base::Base64Url.encode(serde_json::to_vec(claims)?)
+    );
+    let sig = Base::Base64Url.encode(self.sign(input.as_bytes()));
+    Ok(format!("{}.{}", input, sig))
+  }
+}
+
+/// Public key to verify signatures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicKey {
+  Secp256k1(k256::ecdsa::VerifyingKey),
+  P256(p256::ecdsa::VerifyingKey),
+}
+
+impl PublicKey {
+  /// Load key from multicodec prefixed key encoded in multibase
+  pub fn from_multibase(multibase: &str) -> Result<Self> {
+    let (_, bytes) = libipld::multibase::decode(multibase)?;
+    match bytes.split_at(2.min(bytes.len())) {
+      (prefix, key) if prefix == SECP256K1_PUB => Self::from_sec1(KeyAlgorithm::Secp256k1, key),
+      (prefix, key) if prefix == P256_PUB => Self::from_sec1(KeyAlgorithm::P256, key),
+      _ => bail!("unsupported key type : {}", multibase),
+    }
+  }
+
+  /// Load key from SEC1 encoded point
+  pub fn from_sec1(algorithm: KeyAlgorithm, bytes: &[u8]) -> Result<Self> {
+    Ok(match algorithm {
+      KeyAlgorithm::Secp256k1 => {
+        Self::Secp256k1(k256::ecdsa::VerifyingKey::from_sec1_bytes(bytes)?)
+      }
+      KeyAlgorithm::P256 => Self::P256(p256::ecdsa::VerifyingKey::from_sec1_bytes(bytes)?),
+    })
+  }
+
+  /// Load key from did:key
+  pub fn from_did_key(did_key: &str) -> Result<Self> {
+    match did_key.strip_prefix("did:key:") {
+      Some(multibase) => Self::from_multibase(multibase),
+      None => bail!("invalid did:key : {}", did_key),
+    }
+  }
+
+  /// Returns elliptic curve of the key
+  pub fn algorithm(&self) -> KeyAlgorithm {
+    match self {
+      Self::Secp256k1(_) => KeyAlgorithm::Secp256k1,
+      Self::P256(_) => KeyAlgorithm::P256,
+    }
+  }
+
+  /// Returns compressed SEC1 point
+  pub fn to_bytes(&self) -> Vec<u8> {
+    match self {
+      Self::Secp256k1(k) => k.to_encoded_point(true).as_bytes().to_vec(),
+      Self::P256(k) => k.to_encoded_point(true).as_bytes().to_vec(),
+    }
+  }
+
+  /// Returns multicodec prefixed key encoded in base58btc multibase
+  pub fn to_multibase(&self) -> String {
+    let mut bytes = match self {
+      Self::Secp256k1(_) => SECP256K1_PUB.to_vec(),
+      Self::P256(_) => P256_PUB.to_vec(),
+    };
+    bytes.extend(self.to_bytes());
+    libipld::multibase::encode(Base::Base58Btc, bytes)
+  }
+
+  /// Returns public key as did:key
+  pub fn to_did_key(&self) -> String {
+    format!("did:key:{}", self.to_multibase())
+  }
+
+  /// Returns public key as EC JWK
+  pub fn to_jwk(&self) -> Value {
+    let (crv, point) = match self {
+      Self::Secp256k1(k) => ("secp256k1", k.to_encoded_point(false).as_bytes().to_vec()),
+      Self::P256(k) => ("P-256", k.to_encoded_point(false).as_bytes().to_vec()),
+    };
+    json!({
+      "kty": "EC",
+      "crv": crv,
+      "x": Base::Base64Url.encode(&point[1..33]),
+      "y": Base::Base64Url.encode(&point[33..]),
+    })
+  }
+
+  /// Verify 64 bytes signature of SHA-256 hash of the data
+  pub fn verify(&self, data: &[u8], sig: &[u8]) -> Result<()> {
+    match self {
+      Self::Secp256k1(k) => {
+        let sig = k256::ecdsa::Signature::from_slice(sig)?;
+        if sig.normalize_s().is_some() {
+          bail!("signature is not low-S");
+        }
+        k.verify(data, &sig)?;
+      }
+      Self::P256(k) => {
+        let sig = p256::ecdsa::Signature::from_slice(sig)?;
+        if sig.normalize_s().is_some() {
+          bail!("signature is not low-S");
+        }
+        k.verify(data, &sig)?;
+      }
+    }
+    Ok(())
+  }
+}
@@ -11,9 +11,9 @@ use libipld::{cbor::DagCborCodec, json::DagJsonCodec, prelude::Codec, Cid, DagCb
 
 use crate::api::{
   AppBskyFeedPost, ComAtprotoSyncSubscribereposCommit, ComAtprotoSyncSubscribereposHandle,
-  ComAtprotoSyncSubscribereposInfo, ComAtprotoSyncSubscribereposMainMessage,
-  ComAtprotoSyncSubscribereposMigrate, ComAtprotoSyncSubscribereposRepoop,
-  ComAtprotoSyncSubscribereposTombstone,
+  ComAtprotoSyncSubscribereposIdentity, ComAtprotoSyncSubscribereposInfo,
+  ComAtprotoSyncSubscribereposMainMessage, ComAtprotoSyncSubscribereposMigrate,
+  ComAtprotoSyncSubscribereposRepoop, ComAtprotoSyncSubscribereposTombstone,
 };
 
 /// Event Header
@@ -66,6 +66,25 @@ impl ComAtprotoSyncSubscribereposCommit {
   pub fn get_post_path(&self) -> Option<String> {
     self.get_post().first().map(|(op, _)| op.path.clone())
   }
+
+  /// Check that every block hashes to its CID and the root is the commit
+  pub fn verify_blocks(&self) -> Result<()> {
+    if self.too_big && self.blocks.is_empty() {
+      return Ok(());
+    }
+    let blocks = Blocks::verified(self.blocks.as_slice())?;
+    let root = match &blocks.header {
+      Ipld::Map(header) => match header.get("roots") {
+        Some(Ipld::List(roots)) => roots.first().cloned(),
+        _ => None,
+      },
+      _ => None,
+    };
+    match root {
+      Some(Ipld::Link(cid)) if cid.to_string() == self.commit => Ok(()),
+      _ => bail!("root of the blocks is not the commit {}", self.commit),
+    }
+  }
 }
 
 /// Serialized Block Information
@@ -129,6 +148,15 @@ impl From<&[u8]> for Blocks {
 }
 
 impl Blocks {
+  /// Parse CAR file, every block must hash to its CID
+  pub fn verified(data: &[u8]) -> Result<Self> {
+    let blocks = crate::api::Blocks::from_car(data, true)?;
+    Ok(Self {
+      header: blocks.header,
+      data: blocks.data,
+    })
+  }
+
   /// Returns data with the specified CID from Blocks
   pub fn get(&self, cid: &Cid) -> Option<Ipld> {
     self.data.get(cid).cloned()
@@ -209,6 +237,27 @@ impl From<CommitInner> for ComAtprotoSyncSubscribereposCommit {
   }
 }
 
+#[derive(Debug, Clone, DagCbor)]
+struct IdentityInner {
+  seq: i64,
+  did: String,
+  time: String,
+  #[ipld(default = None)]
+  handle: Option<String>,
+}
+
+impl From<IdentityInner> for ComAtprotoSyncSubscribereposIdentity {
+  fn from(value: IdentityInner) -> Self {
+    Self {
+      seq: value.seq,
+      did: value.did,
+      time: value.time.parse().unwrap_or_default(),
+      handle: value.handle,
+      ..Default::default()
+    }
+  }
+}
+
 #[derive(Debug, Clone, DagCbor)]
 struct HandleInner {
   seq: i64,
@@ -299,29 +348,34 @@ impl TryFrom<&[u8]> for Event {
     ret.payload = match header.t.as_str() {
       "#commit" => {
         ComAtprotoSyncSubscribereposMainMessage::ComAtprotoSyncSubscribereposCommit(Box::new(
-          ComAtprotoSyncSubscribereposCommit::from(DagCborCodec.decode::<CommitInner>(&payload)?),
+          ComAtprotoSyncSubscribereposCommit::from(DagCborCodec.decode::<CommitInner>(payload)?),
         ))
       }
+      "#identity" => ComAtprotoSyncSubscribereposMainMessage::ComAtprotoSyncSubscribereposIdentity(
+        Box::new(ComAtprotoSyncSubscribereposIdentity::from(
+          DagCborCodec.decode::<IdentityInner>(payload)?,
+        )),
+      ),
       "#handle" => {
         ComAtprotoSyncSubscribereposMainMessage::ComAtprotoSyncSubscribereposHandle(Box::new(
-          ComAtprotoSyncSubscribereposHandle::from(DagCborCodec.decode::<HandleInner>(&payload)?),
+          ComAtprotoSyncSubscribereposHandle::from(DagCborCodec.decode::<HandleInner>(payload)?),
         ))
       }
       "#migrate" => {
         ComAtprotoSyncSubscribereposMainMessage::ComAtprotoSyncSubscribereposMigrate(Box::new(
-          ComAtprotoSyncSubscribereposMigrate::from(DagCborCodec.decode::<MigrateInner>(&payload)?),
+          ComAtprotoSyncSubscribereposMigrate::from(DagCborCodec.decode::<MigrateInner>(payload)?),
         ))
       }
       "#tombstone" => {
         ComAtprotoSyncSubscribereposMainMessage::ComAtprotoSyncSubscribereposTombstone(Box::new(
           ComAtprotoSyncSubscribereposTombstone::from(
-            DagCborCodec.decode::<TombstoneInner>(&payload)?,
+            DagCborCodec.decode::<TombstoneInner>(payload)?,
           ),
         ))
       }
       "#info" => {
         ComAtprotoSyncSubscribereposMainMessage::ComAtprotoSyncSubscribereposInfo(Box::new(
-          ComAtprotoSyncSubscribereposInfo::from(DagCborCodec.decode::<InfoInner>(&payload)?),
+          ComAtprotoSyncSubscribereposInfo::from(DagCborCodec.decode::<InfoInner>(payload)?),
         ))
       }
       t => bail!("unknown event type {}", t),
@@ -342,6 +396,17 @@ impl Event {
     }
   }
 
+  /// Returns Payload if Event is Identity
+  pub fn as_identity(&self) -> Option<&ComAtprotoSyncSubscribereposIdentity> {
+    if let ComAtprotoSyncSubscribereposMainMessage::ComAtprotoSyncSubscribereposIdentity(i) =
+      &self.payload
+    {
+      Some(i)
+    } else {
+      None
+    }
+  }
+
   /// Returns Payload if Event is Handle
   pub fn as_handle(&self) -> Option<&ComAtprotoSyncSubscribereposHandle> {
     if let ComAtprotoSyncSubscribereposMainMessage::ComAtprotoSyncSubscribereposHandle(h) =
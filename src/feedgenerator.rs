@@ -10,8 +10,8 @@ use std::{
 };
 
 use anyhow::{bail, Result};
-use libipld::multibase::Base;
 use serde::Serialize;
+use serde_json::json;
 use tiny_http::{Header, Response, Server};
 use url::Url;
 
@@ -20,7 +20,8 @@ use crate::{
     AppBskyFeedDescribefeedgenerator, AppBskyFeedDescribefeedgeneratorFeed,
     AppBskyFeedGetfeedskeleton, ComAtprotoSyncSubscribereposCommit,
   },
-  AtUri, Client,
+  jwt::ServiceJwtVerifier,
+  AtUri, Client, IdentityResolver,
 };
 
 /// Custom Feed
@@ -207,7 +208,33 @@ fn worker(server: Arc<Server>, context: Arc<Context>, rx: Receiver<bool>) {
             feed: Vec::new(),
             cursor: None,
           };
-          {
+          let token = request.headers().iter().find_map(|header| {
+            header
+              .field
+              .equiv("Authorization")
+              .then(|| header.value.to_string())
+          });
+          // resolving the signing key may take a while, so verify before locking algorithms
+          let verified = token.as_ref().map(|token| {
+            context.verifier.verify_did(
+              token,
+              &context.get_service_did(),
+              Some("app.bsky.feed.getFeedSkeleton"),
+            )
+          });
+          if let Some(Err(e)) = verified {
+            log::warn!("JWT verification error : {}", e);
+            response = Response::from_string(
+              json!({
+                "error": "AuthenticationRequired",
+                "message": e.to_string(),
+              })
+              .to_string(),
+            )
+            .with_status_code(401)
+            .with_header(header);
+          } else {
+            let verified = verified.and_then(|v| v.ok());
             let mut algo = None;
             let read = match context.algorithms.read() {
               Ok(r) => r,
@@ -231,29 +258,16 @@ fn worker(server: Arc<Server>, context: Arc<Context>, rx: Receiver<bool>) {
                 };
               }
             }
-            if let Some(a) = &algo {
-              let token = request.headers().iter().find_map(|header| {
-                (header.field.as_str() == "authorization").then(|| header.value.to_string())
-              });
-              let message = token
-                .as_ref()
-                .and_then(|token| token.split(".").nth(1).map(|t| t.to_string()));
-              let body = message.and_then(|message| Base::Base64Url.decode(message).ok());
-              let json =
-                body.and_then(|body| serde_json::from_slice::<serde_json::Value>(&body).ok());
-              let did = json.and_then(|json| {
-                json
-                  .get("iss")
-                  .map(|value| value.to_string().replace('"', ""))
-              });
-              rsp = a.handler(
-                queries.get("limit").and_then(|l| l.parse::<usize>().ok()),
-                queries.get("cursor").cloned(),
-                did,
-                token,
-              );
-            } else {
-              log::warn!("no algorithm is fouund : {}", feed);
+            match &algo {
+              Some(a) => {
+                rsp = a.handler(
+                  queries.get("limit").and_then(|l| l.parse::<usize>().ok()),
+                  queries.get("cursor").cloned(),
+                  verified,
+                  token,
+                );
+              }
+              None => log::warn!("no algorithm is fouund : {}", feed),
             }
             response = match serde_json::to_string(&rsp) {
               Ok(r) => {
@@ -287,6 +301,7 @@ fn worker(server: Arc<Server>, context: Arc<Context>, rx: Receiver<bool>) {
 struct Context {
   hostname: String,
   algorithms: Arc<RwLock<Option<Vec<Box<dyn Algorithm>>>>>,
+  verifier: ServiceJwtVerifier,
 }
 
 impl Context {
@@ -302,6 +317,7 @@ pub struct FeedGenerator {
   hostname: String,
   algorithms: Arc<RwLock<Option<Vec<Box<dyn Algorithm>>>>>,
   subscription: Arc<RwLock<Option<Box<dyn Subscription>>>>,
+  resolver: IdentityResolver,
 }
 
 impl FeedGenerator {
@@ -312,9 +328,15 @@ impl FeedGenerator {
       hostname: hostname.to_string(),
       algorithms: Arc::new(RwLock::new(None)),
       subscription: Arc::new(RwLock::new(None)),
+      resolver: IdentityResolver::default(),
     }
   }
 
+  /// Set resolver to find the signing keys of the requesting services
+  pub fn set_identity_resolver(&mut self, resolver: IdentityResolver) {
+    self.resolver = resolver;
+  }
+
   /// Remove custom feed from feed generator
   pub fn remove_algorithm(&mut self, algorithm: &dyn Algorithm) {
     let mut write = match self.algorithms.write() {
@@ -372,6 +394,7 @@ impl FeedGenerator {
     let context = Arc::new(Context {
       hostname: self.hostname.clone(),
       algorithms: Arc::clone(&self.algorithms),
+      verifier: ServiceJwtVerifier::new(self.resolver.clone()),
     });
     std::fs::remove_file("filters.yaml").ok();
     let server = Arc::new(match Server::http("0.0.0.0:8000") {
@@ -373,7 +373,9 @@ impl Filters {
 
   /// Add user's timeline as a filter
   pub fn add_timeline<T: ToString>(&self, client: &crate::api::Client, handle: T) -> Result<Self> {
-    let follows = client.app_bsky_graph_getfollows(&handle.to_string(), None, None)?;
+    let follows = client
+      .app_bsky_graph_getfollows_iter(&handle.to_string())
+      .collect::<Result<Vec<_>>>()?;
     let mut filters = self
       .filters
       .clone()
@@ -383,13 +385,7 @@ impl Filters {
     filters.push(Filter {
       name: handle.to_string(),
       subscribes: Some(Subscribes {
-        dids: Some(
-          follows
-            .follows
-            .iter()
-            .map(|f| f.did.clone())
-            .collect::<Vec<_>>(),
-        ),
+        dids: Some(follows.into_iter().map(|f| f.did).collect::<Vec<_>>()),
         handles: None,
       }),
       ..Default::default()
@@ -0,0 +1,632 @@
+//! Resolution of DIDs and handles
+use std::{
+  io::{Read, Write},
+  net::{IpAddr, SocketAddr, TcpStream, UdpSocket},
+  sync::Arc,
+  time::Duration,
+};
+
+use anyhow::{anyhow, bail, Result};
+use rand_core::{OsRng, RngCore};
+use serde::Deserialize;
+use url::Url;
+
+use crate::{
+  api::DidDoc,
+  transport::{default_agent, HttpRequest, HttpResponse, HttpTransport},
+  Plc,
+};
+
+/// Backend to look up DNS TXT records
+pub trait DnsResolver: Send + Sync {
+  /// Returns TXT records of the name
+  fn txt(&self, name: &str) -> Result<Vec<String>>;
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DohAnswer {
+  #[serde(rename = "type")]
+  record_type: u16,
+  data: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DohResponse {
+  answer: Option<Vec<DohAnswer>>,
+}
+
+/// Name servers of the system
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// TXT of DNS record types
+const DNS_TYPE_TXT: u16 = 16;
+
+/// Resolver sending DNS queries to the name servers configured in the system
+///
+/// Name servers are read from /etc/resolv.conf, lookups fail where it does not exist
+/// and handles are resolved by HTTPS only.
+#[derive(Debug, Clone)]
+pub struct SystemDnsResolver {
+  servers: Vec<SocketAddr>,
+  timeout: Duration,
+}
+
+impl Default for SystemDnsResolver {
+  fn default() -> Self {
+    let servers = std::fs::read_to_string(RESOLV_CONF)
+      .map(|c| parse_resolv_conf(&c))
+      .unwrap_or_default();
+    Self::new(servers)
+  }
+}
+
+impl SystemDnsResolver {
+  /// Create resolver querying the name servers in order
+  pub fn new(servers: Vec<SocketAddr>) -> Self {
+    Self {
+      servers,
+      timeout: Duration::from_secs(5),
+    }
+  }
+
+  /// Set timeout of each query
+  pub fn set_timeout(&mut self, timeout: Duration) {
+    self.timeout = timeout;
+  }
+
+  fn query(&self, server: &SocketAddr, id: u16, query: &[u8]) -> Result<Vec<String>> {
+    let local: SocketAddr = match server {
+      SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+      SocketAddr::V6(_) => ([0u16; 8], 0).into(),
+    };
+    let socket = UdpSocket::bind(local)?;
+    socket.set_read_timeout(Some(self.timeout))?;
+    socket.connect(server)?;
+    socket.send(query)?;
+    let mut buf = [0u8; 4096];
+    let len = socket.recv(&mut buf)?;
+    let res = &buf[..len];
+    // truncated response is queried again over TCP
+    if res.get(2).map(|f| f & 0x02 != 0).unwrap_or_default() {
+      let mut stream = TcpStream::connect_timeout(server, self.timeout)?;
+      stream.set_read_timeout(Some(self.timeout))?;
+      stream.write_all(&(query.len() as u16).to_be_bytes())?;
+      stream.write_all(query)?;
+      let mut len = [0u8; 2];
+      stream.read_exact(&mut len)?;
+      let mut res = vec![0u8; u16::from_be_bytes(len) as usize];
+      stream.read_exact(&mut res)?;
+      return parse_txt_response(id, &res);
+    }
+    parse_txt_response(id, res)
+  }
+}
+
+impl DnsResolver for SystemDnsResolver {
+  fn txt(&self, name: &str) -> Result<Vec<String>> {
+    let id = OsRng.next_u32() as u16;
+    let query = txt_query(id, name)?;
+    let mut error = anyhow!("no name server in {}", RESOLV_CONF);
+    for server in self.servers.iter() {
+      match self.query(server, id, &query) {
+        Ok(txt) => return Ok(txt),
+        Err(e) => error = e,
+      }
+    }
+    Err(error)
+  }
+}
+
+fn parse_resolv_conf(conf: &str) -> Vec<SocketAddr> {
+  conf
+    .lines()
+    .filter_map(|line| {
+      let mut words = line.split_whitespace();
+      match (words.next(), words.next()) {
+        // zone index of IPv6 link local addresses is not supported
+        (Some("nameserver"), Some(addr)) => addr.parse::<IpAddr>().ok(),
+        _ => None,
+      }
+    })
+    .map(|ip| SocketAddr::new(ip, 53))
+    .collect()
+}
+
+fn txt_query(id: u16, name: &str) -> Result<Vec<u8>> {
+  let mut query = id.to_be_bytes().to_vec();
+  // recursion desired and one question
+  query.extend([0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+  for label in name.trim_end_matches('.').split('.') {
+    if label.is_empty() || label.len() > 63 {
+      bail!("invalid DNS name : {}", name);
+    }
+    query.push(label.len() as u8);
+    query.extend(label.as_bytes());
+  }
+  query.push(0);
+  query.extend(DNS_TYPE_TXT.to_be_bytes());
+  // class IN
+  query.extend([0x00, 0x01]);
+  Ok(query)
+}
+
+fn read_u16(msg: &[u8], pos: usize) -> Result<u16> {
+  match msg.get(pos..pos + 2) {
+    Some(b) => Ok(u16::from_be_bytes([b[0], b[1]])),
+    None => bail!("truncated DNS message"),
+  }
+}
+
+/// Returns the position after the name, which may end with a compression pointer
+fn skip_name(msg: &[u8], mut pos: usize) -> Result<usize> {
+  loop {
+    match msg.get(pos) {
+      Some(0) => return Ok(pos + 1),
+      Some(l) if l & 0xc0 == 0xc0 => return Ok(pos + 2),
+      Some(l) => pos += 1 + *l as usize,
+      None => bail!("truncated DNS message"),
+    }
+  }
+}
+
+fn parse_txt_response(id: u16, msg: &[u8]) -> Result<Vec<String>> {
+  let flags = read_u16(msg, 2)?;
+  if read_u16(msg, 0)? != id || flags & 0x8000 == 0 {
+    bail!("unexpected DNS response");
+  }
+  match flags & 0x000f {
+    0 => (),
+    // NXDOMAIN
+    3 => return Ok(Vec::new()),
+    rcode => bail!("DNS response code {}", rcode),
+  }
+  let mut pos = 12;
+  for _ in 0..read_u16(msg, 4)? {
+    pos = skip_name(msg, pos)? + 4;
+  }
+  let mut ret = Vec::new();
+  for _ in 0..read_u16(msg, 6)? {
+    pos = skip_name(msg, pos)?;
+    let record_type = read_u16(msg, pos)?;
+    let len = read_u16(msg, pos + 8)? as usize;
+    let Some(mut data) = msg.get(pos + 10..pos + 10 + len) else {
+      bail!("truncated DNS message");
+    };
+    pos += 10 + len;
+    if record_type != DNS_TYPE_TXT {
+      continue;
+    }
+    // a TXT record is split into strings of up to 255 bytes
+    let mut text = Vec::new();
+    while let Some((len, rest)) = data.split_first() {
+      let Some(s) = rest.get(..*len as usize) else {
+        bail!("truncated DNS message");
+      };
+      text.extend(s);
+      data = &rest[*len as usize..];
+    }
+    ret.push(String::from_utf8_lossy(&text).to_string());
+  }
+  Ok(ret)
+}
+
+/// DNS over HTTPS resolver using JSON API
+///
+/// Looked up names are sent to the provider of the endpoint, Cloudflare by default.
+/// It is used only if set by `IdentityResolver::set_dns_resolver`.
+#[derive(Clone)]
+pub struct DohResolver {
+  url: String,
+  transport: Arc<dyn HttpTransport>,
+}
+
+impl Default for DohResolver {
+  fn default() -> Self {
+    Self::new("https://cloudflare-dns.com/dns-query")
+  }
+}
+
+impl DohResolver {
+  /// Create resolver querying the DNS over HTTPS endpoint
+  pub fn new<T: ToString>(url: T) -> Self {
+    Self {
+      url: url.to_string(),
+      transport: Arc::new(default_agent()),
+    }
+  }
+
+  /// Set transport to send HTTP requests instead of ureq
+  pub fn set_transport<T: HttpTransport + 'static>(&mut self, transport: T) {
+    self.transport = Arc::new(transport);
+  }
+}
+
+impl DnsResolver for DohResolver {
+  fn txt(&self, name: &str) -> Result<Vec<String>> {
+    let req = HttpRequest::get(&self.url)
+      .set("Accept", "application/dns-json")
+      .query_pairs([("name", name), ("type", "TXT")]);
+    let res: DohResponse = self.transport.send(req)?.error_for_status()?.into_json()?;
+    Ok(
+      res
+        .answer
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|a| a.record_type == 16)
+        .map(|a| a.data.split('"').skip(1).step_by(2).collect::<String>())
+        .collect(),
+    )
+  }
+}
+
+/// Resolver of DID documents and handles dispatching on the DID method
+#[derive(Clone)]
+pub struct IdentityResolver {
+  plc: Plc,
+  web_scheme: String,
+  web_port: Option<u16>,
+  dns: Arc<dyn DnsResolver>,
+  transport: Arc<dyn HttpTransport>,
+}
+
+impl Default for IdentityResolver {
+  fn default() -> Self {
+    Self {
+      plc: Plc::default(),
+      web_scheme: String::from("https"),
+      web_port: None,
+      dns: Arc::new(SystemDnsResolver::default()),
+      transport: Arc::new(default_agent()),
+    }
+  }
+}
+
+impl IdentityResolver {
+  /// Create resolver using the PLC directory
+  pub fn new(plc: Plc) -> Self {
+    Self {
+      plc,
+      ..Default::default()
+    }
+  }
+
+  /// Set scheme to fetch did:web documents, "https" by default
+  pub fn set_web_scheme<T: ToString>(&mut self, scheme: T) {
+    self.web_scheme = scheme.to_string();
+  }
+
+  /// Set port to fetch did:web documents instead of the one in the DID
+  pub fn set_web_port(&mut self, port: Option<u16>) {
+    self.web_port = port;
+  }
+
+  /// Set backend to look up _atproto TXT records of handles, the system name servers by default
+  pub fn set_dns_resolver<D: DnsResolver + 'static>(&mut self, dns: D) {
+    self.dns = Arc::new(dns);
+  }
+
+  /// Set transport to fetch /.well-known/atproto-did of handles, did:web documents
+  /// and did:plc documents from the PLC directory instead of ureq
+  pub fn set_transport<T: HttpTransport + 'static>(&mut self, transport: T) {
+    self.transport = Arc::new(transport);
+    self.plc.set_transport(Arc::clone(&self.transport));
+  }
+
+  /// Returns PLC directory client
+  pub fn plc(&self) -> &Plc {
+    &self.plc
+  }
+
+  /// Resolve did:plc or did:web to its DID document
+  pub fn resolve_did(&self, did: &str) -> Result<DidDoc> {
+    let diddoc = match did.split(':').nth(1) {
+      Some("plc") => self.plc.resolve_did(did)?,
+      Some("web") => self.resolve_did_web(did)?,
+      _ => bail!("unsupported DID method : {}", did),
+    };
+    if diddoc.id != did {
+      bail!("DID document of {} has id {}", did, diddoc.id);
+    }
+    Ok(diddoc)
+  }
+
+  fn get(&self, url: &Url) -> Result<HttpResponse> {
+    self
+      .transport
+      .send(HttpRequest::get(url.as_str()))?
+      .error_for_status()
+  }
+
+  fn web_url(&self, host: &str, path: &str) -> Result<Url> {
+    let mut url = Url::parse(&format!("{}://{}{}", self.web_scheme, host, path))?;
+    if let Some(port) = self.web_port {
+      url
+        .set_port(Some(port))
+        .map_err(|_| anyhow!("invalid host : {}", host))?;
+    }
+    Ok(url)
+  }
+
+  fn resolve_did_web(&self, did: &str) -> Result<DidDoc> {
+    let Some(host) = did.strip_prefix("did:web:") else {
+      bail!("invalid did:web : {}", did);
+    };
+    if host.contains(':') {
+      bail!("did:web with path is not supported : {}", did);
+    }
+    let host = host.replace("%3A", ":").replace("%3a", ":");
+    let url = self.web_url(&host, "/.well-known/did.json")?;
+    self.get(&url)?.into_json()
+  }
+
+  fn lookup_dns(&self, handle: &str) -> Result<String> {
+    let dids = self
+      .dns
+      .txt(&format!("_atproto.{}", handle))?
+      .into_iter()
+      .filter_map(|t| t.strip_prefix("did=").map(|d| d.trim().to_string()))
+      .collect::<Vec<_>>();
+    match dids.as_slice() {
+      [did] => Ok(did.clone()),
+      [] => bail!("no _atproto TXT record of {}", handle),
+      _ => bail!("multiple _atproto TXT records of {}", handle),
+    }
+  }
+
+  fn lookup_well_known(&self, handle: &str) -> Result<String> {
+    let url = self.web_url(handle, "/.well-known/atproto-did")?;
+    let body = self.get(&url)?.into_string()?;
+    let did = body.lines().next().unwrap_or_default().trim();
+    if !did.starts_with("did:") {
+      bail!("invalid atproto-did of {}", handle);
+    }
+    Ok(did.to_string())
+  }
+
+  /// Resolve the handle to DID via DNS or HTTPS without checking the DID document
+  pub fn lookup_handle(&self, handle: &str) -> Result<String> {
+    let handle = handle.trim_start_matches('@').to_lowercase();
+    match self.lookup_dns(&handle) {
+      Ok(did) => Ok(did),
+      Err(e) => {
+        log::debug!("DNS lookup failed : {}", e);
+        self.lookup_well_known(&handle)
+      }
+    }
+  }
+
+  /// Resolve the handle to DID and check that the DID document claims the handle
+  pub fn resolve_handle(&self, handle: &str) -> Result<String> {
+    let handle = handle.trim_start_matches('@').to_lowercase();
+    let did = self.lookup_handle(&handle)?;
+    let diddoc = self.resolve_did(&did)?;
+    let aka = format!("at://{}", handle);
+    if !diddoc.also_known_as.iter().any(|a| a.to_lowercase() == aka) {
+      bail!("DID document of {} does not claim {}", did, handle);
+    }
+    Ok(did)
+  }
+
+  /// Resolve the DID and returns host of its PDS
+  pub fn resolve_pds(&self, did: &str) -> Result<String> {
+    let diddoc = self.resolve_did(did)?;
+    diddoc
+      .pds_endpoint()
+      .and_then(|e| Url::parse(&e).ok())
+      .and_then(|u| {
+        u.host_str().map(|h| match u.port() {
+          Some(p) => format!("{}:{}", h, p),
+          None => h.to_string(),
+        })
+      })
+      .ok_or_else(|| anyhow!("no PDS of {}", did))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use serde_json::json;
+
+  use super::*;
+  use crate::MockTransport;
+
+  #[derive(Default)]
+  struct StubDns(HashMap<String, Vec<String>>);
+
+  impl DnsResolver for StubDns {
+    fn txt(&self, name: &str) -> Result<Vec<String>> {
+      Ok(self.0.get(name).cloned().unwrap_or_default())
+    }
+  }
+
+  fn did_web_doc(transport: &MockTransport, host: &str, handle: &str) {
+    transport.push_json(
+      "/.well-known/did.json",
+      200,
+      json!({
+        "@context": ["https://www.w3.org/ns/did/v1"],
+        "id": format!("did:web:{}", host),
+        "alsoKnownAs": [format!("at://{}", handle)],
+        "verificationMethod": [],
+        "service": [{
+          "id": "#atproto_pds",
+          "type": "AtprotoPersonalDataServer",
+          "serviceEndpoint": format!("https://pds.{}", host),
+        }],
+      }),
+    );
+  }
+
+  fn resolver(dns: StubDns, transport: &MockTransport) -> IdentityResolver {
+    let mut resolver = IdentityResolver::default();
+    resolver.set_dns_resolver(dns);
+    resolver.set_transport(transport.clone());
+    resolver
+  }
+
+  fn txt_response(query: &[u8], rcode: u8, answers: &[&[&str]]) -> Vec<u8> {
+    let mut res = query[..2].to_vec();
+    res.extend([
+      0x81,
+      0x80 | rcode,
+      0x00,
+      0x01,
+      0x00,
+      answers.len() as u8,
+      0,
+      0,
+      0,
+      0,
+    ]);
+    res.extend(&query[12..]);
+    for strings in answers {
+      let data = strings
+        .iter()
+        .flat_map(|s| [&[s.len() as u8][..], s.as_bytes()].concat())
+        .collect::<Vec<_>>();
+      // pointer to the name in the question, TXT, IN and TTL
+      res.extend([0xc0, 0x0c, 0x00, 0x10, 0x00, 0x01, 0, 0, 0x0e, 0x10]);
+      res.extend((data.len() as u16).to_be_bytes());
+      res.extend(data);
+    }
+    res
+  }
+
+  #[test]
+  fn system_dns_txt_records() {
+    let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    let handle = std::thread::spawn(move || {
+      let mut buf = [0u8; 512];
+      let (len, peer) = server.recv_from(&mut buf).unwrap();
+      let query = &buf[..len];
+      assert_eq!(
+        &query[12..],
+        b"\x08_atproto\x05alice\x04test\x00\x00\x10\x00\x01"
+      );
+      let res = txt_response(query, 0, &[&["did=did:plc:", "alice"], &["v=spf1"]]);
+      server.send_to(&res, peer).unwrap();
+    });
+    let dns = SystemDnsResolver::new(vec![addr]);
+    assert_eq!(
+      dns.txt("_atproto.alice.test").unwrap(),
+      vec![String::from("did=did:plc:alice"), String::from("v=spf1")]
+    );
+    handle.join().unwrap();
+  }
+
+  #[test]
+  fn parse_dns_responses() {
+    let query = txt_query(7, "_atproto.bob.test").unwrap();
+    assert!(parse_txt_response(7, &txt_response(&query, 3, &[]))
+      .unwrap()
+      .is_empty());
+    assert!(parse_txt_response(7, &txt_response(&query, 2, &[])).is_err());
+    assert!(parse_txt_response(8, &txt_response(&query, 0, &[])).is_err());
+    let res = txt_response(&query, 0, &[&["did=did:plc:bob"]]);
+    assert!(parse_txt_response(7, &res[..res.len() - 1]).is_err());
+    assert_eq!(
+      parse_resolv_conf("# comment\nnameserver 127.0.0.53\nnameserver ::1\noptions edns0\n"),
+      vec![
+        SocketAddr::from(([127, 0, 0, 53], 53)),
+        SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, 1], 53))
+      ]
+    );
+  }
+
+  #[test]
+  fn resolve_handle_by_dns() {
+    let dns = StubDns(HashMap::from([(
+      String::from("_atproto.alice.test"),
+      vec![String::from("did=did:web:alice.test")],
+    )]));
+    let transport = MockTransport::new();
+    did_web_doc(&transport, "alice.test", "alice.test");
+    let resolver = resolver(dns, &transport);
+    assert_eq!(
+      resolver.resolve_handle("@Alice.test").unwrap(),
+      "did:web:alice.test"
+    );
+    assert_eq!(
+      transport.get_requests()[0].url(),
+      "https://alice.test/.well-known/did.json"
+    );
+  }
+
+  #[test]
+  fn resolve_handle_by_well_known() {
+    let transport = MockTransport::new();
+    transport.push_response(
+      "/.well-known/atproto-did",
+      HttpResponse::new("/", 200, b"did:web:bob.test\n".to_vec()),
+    );
+    did_web_doc(&transport, "bob.test", "bob.test");
+    let resolver = resolver(StubDns::default(), &transport);
+    assert_eq!(
+      resolver.resolve_handle("bob.test").unwrap(),
+      "did:web:bob.test"
+    );
+    assert_eq!(
+      transport.get_requests()[0].url(),
+      "https://bob.test/.well-known/atproto-did"
+    );
+  }
+
+  #[test]
+  fn reject_also_known_as_mismatch() {
+    let dns = StubDns(HashMap::from([(
+      String::from("_atproto.carol.test"),
+      vec![String::from("did=did:web:carol.test")],
+    )]));
+    let transport = MockTransport::new();
+    did_web_doc(&transport, "carol.test", "other.test");
+    let resolver = resolver(dns, &transport);
+    assert_eq!(
+      resolver.lookup_handle("carol.test").unwrap(),
+      "did:web:carol.test"
+    );
+    assert!(resolver.resolve_handle("carol.test").is_err());
+  }
+
+  #[test]
+  fn resolve_did_web_document() {
+    let transport = MockTransport::new();
+    did_web_doc(&transport, "dave.test", "dave.test");
+    let resolver = resolver(StubDns::default(), &transport);
+    let doc = resolver.resolve_did("did:web:dave.test").unwrap();
+    assert_eq!(doc.handles(), vec![String::from("dave.test")]);
+    assert_eq!(
+      resolver.resolve_pds("did:web:dave.test").unwrap(),
+      "pds.dave.test"
+    );
+    // the document of dave.test does not have the id of eve.test
+    assert!(resolver.resolve_did("did:web:eve.test").is_err());
+  }
+
+  #[test]
+  fn resolve_did_plc_through_transport() {
+    let transport = MockTransport::new();
+    transport.push_json(
+      "/did:plc:frank",
+      200,
+      json!({
+        "@context": ["https://www.w3.org/ns/did/v1"],
+        "id": "did:plc:frank",
+        "alsoKnownAs": ["at://frank.test"],
+        "verificationMethod": [],
+        "service": [],
+      }),
+    );
+    let resolver = resolver(StubDns::default(), &transport);
+    let doc = resolver.resolve_did("did:plc:frank").unwrap();
+    assert_eq!(doc.handles(), vec![String::from("frank.test")]);
+    assert_eq!(
+      transport.get_requests()[0].url(),
+      "https://plc.directory/did:plc:frank"
+    );
+  }
+}
@@ -0,0 +1,325 @@
+//! Inter-service authentication tokens
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use libipld::multibase::Base;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+  crypto::{Keypair, PublicKey},
+  IdentityResolver,
+};
+
+/// Seconds until minted service JWTs expire by default
+pub const SERVICE_JWT_EXPIRATION_SECONDS: i64 = 60;
+
+/// Seconds until the signing key of an issuer may be resolved again
+pub const SIGNING_KEY_RESOLVE_INTERVAL_SECONDS: u64 = 60;
+
+const MAX_CACHED_ISSUERS: usize = 10000;
+
+/// Claims of an inter-service JWT
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ServiceJwtClaims {
+  pub iss: String,
+  pub aud: String,
+  pub exp: i64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub iat: Option<i64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub lxm: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub jti: Option<String>,
+}
+
+impl ServiceJwtClaims {
+  /// Returns DID of the issuer without service fragment
+  pub fn issuer_did(&self) -> &str {
+    self.iss.split('#').next().unwrap_or_default()
+  }
+}
+
+/// Resolve the key signing JWTs of the issuer, #atproto_labeler uses #atproto_label key
+pub fn resolve_signing_key(resolver: &IdentityResolver, iss: &str) -> Result<PublicKey> {
+  let (did, fragment) = match iss.split_once('#') {
+    Some((did, "atproto_labeler")) => (did, "atproto_label"),
+    Some((did, _)) => (did, "atproto"),
+    None => (iss, "atproto"),
+  };
+  let diddoc = resolver.resolve_did(did)?;
+  match diddoc.get_verification_method(fragment) {
+    Some(v) => v.public_key(),
+    None => bail!("no {} verification method in {}", fragment, did),
+  }
+}
+
+/// Signing key of an issuer, None if the resolution failed
+#[derive(Clone)]
+struct CachedKey {
+  key: Option<PublicKey>,
+  resolved_at: Instant,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwtHeader {
+  alg: String,
+  typ: Option<String>,
+}
+
+/// Verifier of inter-service JWTs with signing keys resolved from DID documents
+#[derive(Clone)]
+pub struct ServiceJwtVerifier {
+  resolver: IdentityResolver,
+  keys: Arc<Mutex<HashMap<String, CachedKey>>>,
+  resolve_interval: Duration,
+}
+
+impl ServiceJwtVerifier {
+  /// Create verifier resolving issuers with the resolver
+  pub fn new(resolver: IdentityResolver) -> Self {
+    Self {
+      resolver,
+      keys: Arc::new(Mutex::new(HashMap::new())),
+      resolve_interval: Duration::from_secs(SIGNING_KEY_RESOLVE_INTERVAL_SECONDS),
+    }
+  }
+
+  /// Set minimum interval to resolve the signing key of the same issuer again
+  pub fn set_resolve_interval(&mut self, interval: Duration) {
+    self.resolve_interval = interval;
+  }
+
+  /// Resolve the signing key and cache the result even if it fails
+  fn resolve_key(&self, iss: &str) -> Option<PublicKey> {
+    let key = match resolve_signing_key(&self.resolver, iss) {
+      Ok(key) => Some(key),
+      Err(e) => {
+        log::warn!("failed to resolve signing key of {} : {}", iss, e);
+        None
+      }
+    };
+    if let Ok(mut keys) = self.keys.lock() {
+      if keys.len() >= MAX_CACHED_ISSUERS {
+        keys.retain(|_, c| c.resolved_at.elapsed() < self.resolve_interval);
+      }
+      keys.insert(
+        iss.to_string(),
+        CachedKey {
+          key: key.clone(),
+          resolved_at: Instant::now(),
+        },
+      );
+    }
+    key
+  }
+
+  /// Verify signature, audience, method and expiration of the JWT, returns its claims
+  pub fn verify(&self, jwt: &str, aud: &str, lxm: Option<&str>) -> Result<ServiceJwtClaims> {
+    let jwt = jwt.trim_start_matches("Bearer ").trim();
+    let parts = jwt.split('.').collect::<Vec<_>>();
+    let [encoded_header, payload, sig] = parts.as_slice() else {
+      bail!("malformed JWT");
+    };
+    let header: JwtHeader = serde_json::from_slice(&Base::Base64Url.decode(encoded_header)?)?;
+    if matches!(
+      header.typ.as_deref(),
+      Some("at+jwt" | "refresh+jwt" | "dpop+jwt")
+    ) {
+      bail!("not a service JWT : {}", header.typ.unwrap_or_default());
+    }
+    let claims: ServiceJwtClaims = serde_json::from_slice(&Base::Base64Url.decode(payload)?)?;
+    if claims.aud != aud {
+      bail!("JWT audience mismatch : {} != {}", claims.aud, aud);
+    }
+    // tokens issued before lxm was introduced are accepted for any method
+    if let (Some(expected), Some(actual)) = (lxm, &claims.lxm) {
+      if expected != actual {
+        bail!("JWT method mismatch : {} != {}", actual, expected);
+      }
+    }
+    if claims.exp <= Utc::now().timestamp() {
+      bail!("JWT expired");
+    }
+    let input = format!("{}.{}", encoded_header, payload);
+    let sig = Base::Base64Url.decode(sig)?;
+    let cached = self
+      .keys
+      .lock()
+      .ok()
+      .and_then(|k| k.get(&claims.iss).cloned());
+    let verify = |key: &PublicKey| {
+      if key.algorithm().jwt_algorithm() != header.alg {
+        bail!("JWT algorithm mismatch : {}", header.alg);
+      }
+      key.verify(input.as_bytes(), &sig)
+    };
+    let key = match cached {
+      Some(CachedKey { key: Some(key), .. }) if verify(&key).is_ok() => return Ok(claims),
+      // the signing key may be rotated since it is cached, but resolve at most once per interval
+      Some(cached) if cached.resolved_at.elapsed() < self.resolve_interval => cached.key,
+      _ => self.resolve_key(&claims.iss),
+    };
+    match key {
+      Some(key) => verify(&key)?,
+      None => bail!("no signing key of {}", claims.iss),
+    }
+    Ok(claims)
+  }
+
+  /// Verify the JWT and returns DID of the issuer
+  pub fn verify_did(&self, jwt: &str, aud: &str, lxm: Option<&str>) -> Result<String> {
+    Ok(self.verify(jwt, aud, lxm)?.issuer_did().to_string())
+  }
+}
+
+/// Signer of inter-service JWTs with the signing key of the service
+#[derive(Debug, Clone)]
+pub struct ServiceJwtSigner {
+  iss: String,
+  key: Keypair,
+  expiration: i64,
+}
+
+impl ServiceJwtSigner {
+  /// Create signer issuing tokens as the DID, optionally with service fragment like #atproto_labeler
+  pub fn new<T: ToString>(iss: T, key: Keypair) -> Self {
+    Self {
+      iss: iss.to_string(),
+      key,
+      expiration: SERVICE_JWT_EXPIRATION_SECONDS,
+    }
+  }
+
+  /// Set seconds until the tokens expire
+  pub fn set_expiration(&mut self, seconds: i64) {
+    self.expiration = seconds;
+  }
+
+  pub fn get_iss(&self) -> String {
+    self.iss.clone()
+  }
+
+  /// Check that the DID document of the issuer has the public key of the signer
+  pub fn check_key(&self, resolver: &IdentityResolver) -> Result<()> {
+    if resolve_signing_key(resolver, &self.iss)? != self.key.public_key() {
+      bail!("signing key of {} does not match", self.iss);
+    }
+    Ok(())
+  }
+
+  /// Create token for the audience service DID, limited to the method if given
+  pub fn sign(&self, aud: &str, lxm: Option<&str>) -> Result<String> {
+    let now = Utc::now().timestamp();
+    let mut jti = [0u8; 16];
+    OsRng.fill_bytes(&mut jti);
+    let claims = ServiceJwtClaims {
+      iss: self.iss.clone(),
+      aud: aud.to_string(),
+      exp: now + self.expiration,
+      iat: Some(now),
+      lxm: lxm.map(String::from),
+      jti: Some(Base::Base64Url.encode(jti)),
+    };
+    self
+      .key
+      .sign_jwt(&json!({ "typ": "JWT" }), &serde_json::to_value(claims)?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{KeyAlgorithm, PlcOpBuilder, PlcServer};
+
+  const AUD: &str = "did:web:feed.example.com";
+  const LXM: &str = "app.bsky.feed.getFeedSkeleton";
+
+  fn setup(server: &PlcServer) -> (ServiceJwtVerifier, ServiceJwtSigner) {
+    let key = Keypair::generate(KeyAlgorithm::Secp256k1);
+    let (did, op) = PlcOpBuilder::new()
+      .rotation_key(key.did_key())
+      .atproto_signing_key(key.did_key())
+      .handle("alice.test")
+      .genesis(&key)
+      .unwrap();
+    let plc = server.plc();
+    plc.create_plc_op(&did, &op).unwrap();
+    let verifier = ServiceJwtVerifier::new(IdentityResolver::new(plc));
+    (verifier, ServiceJwtSigner::new(did, key))
+  }
+
+  #[test]
+  fn verify_valid_token() {
+    let server = PlcServer::start("127.0.0.1:0").unwrap();
+    let (verifier, signer) = setup(&server);
+    let jwt = signer.sign(AUD, Some(LXM)).unwrap();
+    let did = verifier.verify_did(&jwt, AUD, Some(LXM)).unwrap();
+    assert_eq!(did, signer.get_iss());
+  }
+
+  #[test]
+  fn reject_forged_signature() {
+    let server = PlcServer::start("127.0.0.1:0").unwrap();
+    let (verifier, signer) = setup(&server);
+    let forger =
+      ServiceJwtSigner::new(signer.get_iss(), Keypair::generate(KeyAlgorithm::Secp256k1));
+    let jwt = forger.sign(AUD, Some(LXM)).unwrap();
+    assert!(verifier.verify(&jwt, AUD, Some(LXM)).is_err());
+  }
+
+  #[test]
+  fn reject_expired_token() {
+    let server = PlcServer::start("127.0.0.1:0").unwrap();
+    let (verifier, mut signer) = setup(&server);
+    signer.set_expiration(-1);
+    let jwt = signer.sign(AUD, Some(LXM)).unwrap();
+    let e = verifier.verify(&jwt, AUD, Some(LXM)).unwrap_err();
+    assert!(e.to_string().contains("expired"));
+  }
+
+  #[test]
+  fn reject_wrong_audience() {
+    let server = PlcServer::start("127.0.0.1:0").unwrap();
+    let (verifier, signer) = setup(&server);
+    let jwt = signer.sign("did:web:other.example.com", Some(LXM)).unwrap();
+    let e = verifier.verify(&jwt, AUD, Some(LXM)).unwrap_err();
+    assert!(e.to_string().contains("audience"));
+  }
+
+  #[test]
+  fn reject_wrong_method() {
+    let server = PlcServer::start("127.0.0.1:0").unwrap();
+    let (verifier, signer) = setup(&server);
+    let jwt = signer.sign(AUD, Some("app.bsky.feed.getTimeline")).unwrap();
+    let e = verifier.verify(&jwt, AUD, Some(LXM)).unwrap_err();
+    assert!(e.to_string().contains("method"));
+  }
+
+  #[test]
+  fn cache_failed_resolution() {
+    let server = PlcServer::start("127.0.0.1:0").unwrap();
+    let (verifier, _) = setup(&server);
+    let key = Keypair::generate(KeyAlgorithm::Secp256k1);
+    let (did, op) = PlcOpBuilder::new()
+      .rotation_key(key.did_key())
+      .atproto_signing_key(key.did_key())
+      .genesis(&key)
+      .unwrap();
+    let signer = ServiceJwtSigner::new(&did, key);
+    let jwt = signer.sign(AUD, Some(LXM)).unwrap();
+    assert!(verifier.verify(&jwt, AUD, Some(LXM)).is_err());
+    // registered after the failure, but not resolved again within the interval
+    server.plc().create_plc_op(&did, &op).unwrap();
+    assert!(verifier.verify(&jwt, AUD, Some(LXM)).is_err());
+    let mut verifier = verifier;
+    verifier.set_resolve_interval(Duration::ZERO);
+    assert!(verifier.verify(&jwt, AUD, Some(LXM)).is_ok());
+  }
+}
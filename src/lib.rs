@@ -99,17 +99,45 @@
 /// Atproto API from lexicions
 pub mod api;
 pub mod aturi;
+pub mod backup;
+pub mod blob;
+pub mod cache;
+pub mod chat;
 pub mod client;
+pub mod crypto;
 pub mod event;
 pub mod feedgenerator;
 pub mod feedpost;
 pub mod filter;
+pub mod identity;
+pub mod jwt;
+pub mod oauth;
+pub mod paginate;
 pub mod plc;
+pub mod plcserver;
+pub mod repo;
+pub mod session;
+pub mod transport;
+pub mod xrpc;
 
 pub use aturi::AtUri;
+pub use backup::Backup;
+pub use blob::BlobFetcher;
+pub use cache::IdentityCache;
+pub use chat::{ChatMessage, MessagePoller};
 pub use client::{Client, Repo};
+pub use crypto::{KeyAlgorithm, Keypair, PublicKey};
 pub use event::{Blocks, Event, Header};
 pub use feedgenerator::{Algorithm, FeedGenerator, Subscription};
 pub use feedpost::{Cursor, FeedPost, FeedPosts};
 pub use filter::{Filter, Filters, Keywords, Subscribes};
-pub use plc::Plc;
+pub use identity::{DnsResolver, DohResolver, IdentityResolver, SystemDnsResolver};
+pub use jwt::{ServiceJwtClaims, ServiceJwtSigner, ServiceJwtVerifier};
+pub use oauth::{OAuthAgent, OAuthClient, OAuthSession, OAuthState};
+pub use paginate::Paginator;
+pub use plc::{validate_audit_log, Plc, PlcExport, PlcMirror, PlcOp, PlcOpBuilder};
+pub use plcserver::PlcServer;
+pub use repo::{LocalRepo, RepoWrite, Tid};
+pub use session::{FileSessionStore, Session, SessionStore};
+pub use transport::{HttpRequest, HttpResponse, HttpTransport, MockTransport};
+pub use xrpc::{RateLimit, RateLimiter, RetryPolicy, XrpcError, XrpcErrorName};
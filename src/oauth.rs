@@ -0,0 +1,758 @@
+//! OAuth client with PAR, PKCE and DPoP bound tokens
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex, RwLock},
+};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use libipld::multibase::Base;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::{
+  api::JWT_REFRESH_MARGIN_SECONDS,
+  crypto::{KeyAlgorithm, Keypair},
+  transport::{default_agent, HttpRequest, HttpResponse, HttpTransport},
+  IdentityResolver,
+};
+
+/// Scope requested by default
+pub const OAUTH_DEFAULT_SCOPE: &str = "atproto transition:generic";
+
+fn random_string(len: usize) -> String {
+  let mut bytes = vec![0u8; len];
+  OsRng.fill_bytes(&mut bytes);
+  Base::Base64Url.encode(bytes)
+}
+
+fn origin(url: &str) -> String {
+  Url::parse(url)
+    .map(|u| u.origin().ascii_serialization())
+    .unwrap_or_default()
+}
+
+/// Metadata of the resource server, /.well-known/oauth-protected-resource of the PDS
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtectedResourceMetadata {
+  pub resource: String,
+  #[serde(default)]
+  pub authorization_servers: Vec<String>,
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+/// Metadata of the authorization server, /.well-known/oauth-authorization-server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorizationServerMetadata {
+  pub issuer: String,
+  pub authorization_endpoint: String,
+  pub token_endpoint: String,
+  pub pushed_authorization_request_endpoint: String,
+  #[serde(default)]
+  pub dpop_signing_alg_values_supported: Vec<String>,
+  #[serde(default)]
+  pub scopes_supported: Vec<String>,
+  #[serde(default)]
+  pub authorization_response_iss_parameter_supported: bool,
+  #[serde(flatten)]
+  pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParResponse {
+  request_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+  access_token: String,
+  token_type: String,
+  refresh_token: Option<String>,
+  expires_in: Option<i64>,
+  scope: Option<String>,
+  sub: String,
+}
+
+/// Private key and server nonces to create DPoP proofs
+#[derive(Debug)]
+pub struct Dpop {
+  key: Keypair,
+  nonces: Mutex<HashMap<String, String>>,
+}
+
+impl Dpop {
+  /// Create DPoP with the key bound to the tokens
+  pub fn new(key: Keypair) -> Self {
+    Self {
+      key,
+      nonces: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Create DPoP with new ES256 key
+  pub fn generate() -> Self {
+    Self::new(Keypair::generate(KeyAlgorithm::P256))
+  }
+
+  /// Returns the private key
+  pub fn key(&self) -> &Keypair {
+    &self.key
+  }
+
+  /// Create proof of the request, with ath claim if the access token is given
+  pub fn proof(&self, method: &str, url: &str, access_token: Option<&str>) -> Result<String> {
+    let mut htu = Url::parse(url)?;
+    htu.set_query(None);
+    htu.set_fragment(None);
+    let mut claims = json!({
+      "jti": random_string(16),
+      "htm": method,
+      "htu": htu.as_str(),
+      "iat": Utc::now().timestamp(),
+    });
+    if let Some(nonce) = self
+      .nonces
+      .lock()
+      .ok()
+      .and_then(|n| n.get(&origin(url)).cloned())
+    {
+      claims["nonce"] = Value::from(nonce);
+    }
+    if let Some(token) = access_token {
+      claims["ath"] = Value::from(Base::Base64Url.encode(Sha256::digest(token.as_bytes())));
+    }
+    let header = json!({
+      "typ": "dpop+jwt",
+      "jwk": self.key.public_key().to_jwk(),
+    });
+    self.key.sign_jwt(&header, &claims)
+  }
+
+  /// Remember DPoP-Nonce header of the response from the URL, returns true if it is changed
+  pub fn update_nonce(&self, url: &str, nonce: Option<&str>) -> bool {
+    let (Some(nonce), Ok(mut nonces)) = (nonce, self.nonces.lock()) else {
+      return false;
+    };
+    nonces.insert(origin(url), nonce.to_string()) != Some(nonce.to_string())
+  }
+}
+
+/// Pending authorization to be completed by the redirect callback
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthState {
+  pub state: String,
+  pub code_verifier: String,
+  pub issuer: String,
+  /// The redirect must have iss parameter of the issuer
+  #[serde(default)]
+  pub iss_required: bool,
+  pub token_endpoint: String,
+  pub did: Option<String>,
+  pub pds: Option<String>,
+  pub dpop_key: String,
+  pub authorization_url: String,
+}
+
+/// Tokens issued by the authorization server, bound to the DPoP key
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthSession {
+  pub did: String,
+  pub pds: String,
+  pub issuer: String,
+  pub token_endpoint: String,
+  pub client_id: String,
+  pub scope: String,
+  pub access_token: String,
+  pub refresh_token: Option<String>,
+  pub expires_at: Option<DateTime<Utc>>,
+  pub dpop_key: String,
+}
+
+impl OAuthSession {
+  /// Returns the private key bound to the tokens
+  pub fn get_dpop_key(&self) -> Result<Keypair> {
+    Keypair::from_bytes(KeyAlgorithm::P256, &Base::Base64Url.decode(&self.dpop_key)?)
+  }
+
+  /// Returns host and port of the PDS
+  pub fn get_host(&self) -> Result<String> {
+    let url = Url::parse(&self.pds)?;
+    let host = url
+      .host_str()
+      .ok_or_else(|| anyhow!("invalid PDS : {}", self.pds))?;
+    Ok(match url.port() {
+      Some(p) => format!("{}:{}", host, p),
+      None => host.to_string(),
+    })
+  }
+}
+
+/// OAuth public client
+#[derive(Clone)]
+pub struct OAuthClient {
+  client_id: String,
+  redirect_uri: String,
+  scope: String,
+  resolver: IdentityResolver,
+  transport: Arc<dyn HttpTransport>,
+}
+
+impl OAuthClient {
+  /// Create client with URL of its client metadata document
+  pub fn new<T1: ToString, T2: ToString>(client_id: T1, redirect_uri: T2) -> Self {
+    Self {
+      client_id: client_id.to_string(),
+      redirect_uri: redirect_uri.to_string(),
+      scope: String::from(OAUTH_DEFAULT_SCOPE),
+      resolver: IdentityResolver::default(),
+      transport: Arc::new(default_agent()),
+    }
+  }
+
+  /// Create development client without metadata document, redirected to the loopback address
+  pub fn loopback<T: ToString>(redirect_uri: T) -> Self {
+    let redirect_uri = redirect_uri.to_string();
+    let query = url::form_urlencoded::Serializer::new(String::new())
+      .append_pair("redirect_uri", &redirect_uri)
+      .append_pair("scope", OAUTH_DEFAULT_SCOPE)
+      .finish();
+    Self::new(format!("http://localhost?{}", query), redirect_uri)
+  }
+
+  /// Set space separated scopes to request
+  pub fn set_scope<T: ToString>(&mut self, scope: T) {
+    self.scope = scope.to_string();
+  }
+
+  /// Set resolver to find the PDS of the accounts
+  pub fn set_identity_resolver(&mut self, resolver: IdentityResolver) {
+    self.resolver = resolver;
+  }
+
+  /// Set transport to send HTTP requests to the servers and of identity lookups instead of ureq
+  pub fn set_transport<T: HttpTransport + 'static>(&mut self, transport: T) {
+    self.transport = Arc::new(transport);
+    self.resolver.set_transport(Arc::clone(&self.transport));
+  }
+
+  pub fn get_client_id(&self) -> String {
+    self.client_id.clone()
+  }
+
+  fn get(&self, url: &Url) -> Result<HttpResponse> {
+    self
+      .transport
+      .send(HttpRequest::get(url))?
+      .error_for_status()
+  }
+
+  /// Fetch metadata of the PDS
+  pub fn get_protected_resource(&self, pds: &str) -> Result<ProtectedResourceMetadata> {
+    let url = Url::parse(pds)?.join("/.well-known/oauth-protected-resource")?;
+    self.get(&url)?.into_json()
+  }
+
+  /// Fetch metadata of the authorization server and check its issuer
+  pub fn get_authorization_server(&self, issuer: &str) -> Result<AuthorizationServerMetadata> {
+    let url = Url::parse(issuer)?.join("/.well-known/oauth-authorization-server")?;
+    let metadata: AuthorizationServerMetadata = self.get(&url)?.into_json()?;
+    if metadata.issuer.trim_end_matches('/') != issuer.trim_end_matches('/') {
+      bail!("issuer mismatch : {} != {}", metadata.issuer, issuer);
+    }
+    if !metadata.dpop_signing_alg_values_supported.is_empty()
+      && !metadata
+        .dpop_signing_alg_values_supported
+        .iter()
+        .any(|a| a == "ES256")
+    {
+      bail!("ES256 DPoP is not supported by {}", metadata.issuer);
+    }
+    Ok(metadata)
+  }
+
+  /// Find the authorization server of the PDS
+  pub fn discover(&self, pds: &str) -> Result<AuthorizationServerMetadata> {
+    let resource = self.get_protected_resource(pds)?;
+    let Some(issuer) = resource.authorization_servers.first() else {
+      bail!("no authorization server of {}", pds);
+    };
+    self.get_authorization_server(issuer)
+  }
+
+  /// Start authorization of the handle or DID, returns state with URL to open in the browser
+  pub fn authorize(&self, identity: &str) -> Result<OAuthState> {
+    let identity = identity.trim_start_matches('@');
+    let did = match identity.starts_with("did:") {
+      true => identity.to_string(),
+      false => self.resolver.resolve_handle(identity)?,
+    };
+    let pds = self
+      .resolver
+      .resolve_did(&did)?
+      .pds_endpoint()
+      .ok_or_else(|| anyhow!("no PDS of {}", did))?;
+    let metadata = self.discover(&pds)?;
+    self.push_authorization(&metadata, Some(identity), Some(did), Some(pds))
+  }
+
+  /// Start authorization on the PDS or authorization server without selecting account
+  pub fn authorize_server(&self, url: &str) -> Result<OAuthState> {
+    let metadata = match self.discover(url) {
+      Ok(m) => m,
+      Err(e) => {
+        log::debug!("protected resource discovery failed : {}", e);
+        self.get_authorization_server(url)?
+      }
+    };
+    self.push_authorization(&metadata, None, None, None)
+  }
+
+  fn post(&self, url: &str, dpop: &Dpop, form: &[(&str, &str)]) -> Result<HttpResponse> {
+    let send = || {
+      let req = HttpRequest::post(url)
+        .set("DPoP", &dpop.proof("POST", url, None)?)
+        .form(form);
+      let res = self.transport.send(req)?;
+      let updated = dpop.update_nonce(res.get_url(), res.header("DPoP-Nonce"));
+      Ok::<_, anyhow::Error>((res, updated))
+    };
+    let (mut res, updated) = send()?;
+    // retry once with the nonce the server requires
+    if res.status() >= 400 && updated {
+      res = send()?.0;
+    }
+    if res.status() >= 400 {
+      let status = res.status();
+      bail!("{} returned {} : {}", url, status, res.into_string()?)
+    }
+    Ok(res)
+  }
+
+  fn push_authorization(
+    &self,
+    metadata: &AuthorizationServerMetadata,
+    login_hint: Option<&str>,
+    did: Option<String>,
+    pds: Option<String>,
+  ) -> Result<OAuthState> {
+    let dpop = Dpop::generate();
+    let state = random_string(16);
+    let code_verifier = random_string(32);
+    let code_challenge = Base::Base64Url.encode(Sha256::digest(code_verifier.as_bytes()));
+    let mut form = vec![
+      ("client_id", self.client_id.as_str()),
+      ("response_type", "code"),
+      ("code_challenge", &code_challenge),
+      ("code_challenge_method", "S256"),
+      ("redirect_uri", &self.redirect_uri),
+      ("scope", &self.scope),
+      ("state", &state),
+    ];
+    if let Some(hint) = login_hint {
+      form.push(("login_hint", hint));
+    }
+    let res: ParResponse = self
+      .post(
+        &metadata.pushed_authorization_request_endpoint,
+        &dpop,
+        &form,
+      )?
+      .into_json()?;
+    let mut authorization_url = Url::parse(&metadata.authorization_endpoint)?;
+    authorization_url
+      .query_pairs_mut()
+      .append_pair("client_id", &self.client_id)
+      .append_pair("request_uri", &res.request_uri);
+    Ok(OAuthState {
+      state,
+      code_verifier,
+      issuer: metadata.issuer.clone(),
+      iss_required: metadata.authorization_response_iss_parameter_supported,
+      token_endpoint: metadata.token_endpoint.clone(),
+      did,
+      pds,
+      dpop_key: Base::Base64Url.encode(dpop.key().to_bytes()),
+      authorization_url: authorization_url.to_string(),
+    })
+  }
+
+  fn to_session(
+    &self,
+    res: TokenResponse,
+    issuer: &str,
+    token_endpoint: &str,
+    pds: String,
+    dpop: &Dpop,
+  ) -> Result<OAuthSession> {
+    if !res.token_type.eq_ignore_ascii_case("DPoP") {
+      bail!("token type is not DPoP : {}", res.token_type);
+    }
+    let scope = res.scope.unwrap_or_default();
+    if !scope.split(' ').any(|s| s == "atproto") {
+      bail!("atproto scope is not granted : {}", scope);
+    }
+    Ok(OAuthSession {
+      did: res.sub,
+      pds,
+      issuer: issuer.to_string(),
+      token_endpoint: token_endpoint.to_string(),
+      client_id: self.client_id.clone(),
+      scope,
+      access_token: res.access_token,
+      refresh_token: res.refresh_token,
+      expires_at: res.expires_in.map(|e| Utc::now() + Duration::seconds(e)),
+      dpop_key: Base::Base64Url.encode(dpop.key().to_bytes()),
+    })
+  }
+
+  /// Complete the authorization with query of the redirected URL
+  pub fn callback(&self, state: &OAuthState, redirected_url: &str) -> Result<OAuthSession> {
+    let url = Url::parse(redirected_url)?;
+    let queries = url.query_pairs().collect::<HashMap<_, _>>();
+    if let Some(error) = queries.get("error") {
+      bail!(
+        "authorization error : {} {}",
+        error,
+        queries
+          .get("error_description")
+          .cloned()
+          .unwrap_or_default()
+      );
+    }
+    if queries.get("state").map(|s| s.as_ref()) != Some(state.state.as_str()) {
+      bail!("state mismatch");
+    }
+    match queries.get("iss") {
+      Some(iss) if iss != &state.issuer => {
+        bail!("issuer mismatch : {} != {}", iss, state.issuer)
+      }
+      None if state.iss_required => bail!("no iss parameter from {}", state.issuer),
+      _ => (),
+    }
+    let Some(code) = queries.get("code") else {
+      bail!("no authorization code");
+    };
+    let dpop = Dpop::new(Keypair::from_bytes(
+      KeyAlgorithm::P256,
+      &Base::Base64Url.decode(&state.dpop_key)?,
+    )?);
+    let form = [
+      ("grant_type", "authorization_code"),
+      ("code", code),
+      ("redirect_uri", &self.redirect_uri),
+      ("code_verifier", &state.code_verifier),
+      ("client_id", &self.client_id),
+    ];
+    let res: TokenResponse = self
+      .post(&state.token_endpoint, &dpop, &form)?
+      .into_json()?;
+    let pds = match (&state.did, &state.pds) {
+      (Some(did), Some(pds)) => {
+        if &res.sub != did {
+          bail!("authorized account mismatch : {} != {}", res.sub, did);
+        }
+        pds.clone()
+      }
+      _ => {
+        let pds = self
+          .resolver
+          .resolve_did(&res.sub)?
+          .pds_endpoint()
+          .ok_or_else(|| anyhow!("no PDS of {}", res.sub))?;
+        let issuer = self.discover(&pds)?.issuer;
+        if issuer != state.issuer {
+          bail!("{} is not authorized by {}", res.sub, state.issuer);
+        }
+        pds
+      }
+    };
+    self.to_session(res, &state.issuer, &state.token_endpoint, pds, &dpop)
+  }
+
+  fn refresh_with(&self, session: &OAuthSession, dpop: &Dpop) -> Result<OAuthSession> {
+    let Some(refresh_token) = &session.refresh_token else {
+      bail!("no refresh token");
+    };
+    let form = [
+      ("grant_type", "refresh_token"),
+      ("refresh_token", refresh_token),
+      ("client_id", &self.client_id),
+    ];
+    let res: TokenResponse = self
+      .post(&session.token_endpoint, dpop, &form)?
+      .into_json()?;
+    if res.sub != session.did {
+      bail!(
+        "refreshed account mismatch : {} != {}",
+        res.sub,
+        session.did
+      );
+    }
+    let mut refreshed = self.to_session(
+      res,
+      &session.issuer,
+      &session.token_endpoint,
+      session.pds.clone(),
+      dpop,
+    )?;
+    if refreshed.refresh_token.is_none() {
+      refreshed.refresh_token = session.refresh_token.clone();
+    }
+    Ok(refreshed)
+  }
+
+  /// Renew the tokens of the session
+  pub fn refresh(&self, session: &OAuthSession) -> Result<OAuthSession> {
+    self.refresh_with(session, &Dpop::new(session.get_dpop_key()?))
+  }
+}
+
+pub type OAuthRefreshCallback = Arc<dyn Fn(&OAuthSession) + Send + Sync>;
+
+/// OAuth session used by API clients, refreshed automatically
+pub struct OAuthAgent {
+  client: OAuthClient,
+  session: RwLock<OAuthSession>,
+  dpop: Dpop,
+  refresh_lock: Mutex<()>,
+  refresh_callback: Option<OAuthRefreshCallback>,
+}
+
+impl OAuthAgent {
+  /// Create agent of the session issued to the client
+  pub fn new(client: OAuthClient, session: OAuthSession) -> Result<Self> {
+    Ok(Self {
+      client,
+      dpop: Dpop::new(session.get_dpop_key()?),
+      session: RwLock::new(session),
+      refresh_lock: Mutex::new(()),
+      refresh_callback: None,
+    })
+  }
+
+  /// Set callback called with new tokens after refresh
+  pub fn set_refresh_callback<F: Fn(&OAuthSession) + Send + Sync + 'static>(
+    &mut self,
+    callback: F,
+  ) {
+    self.refresh_callback = Some(Arc::new(callback));
+  }
+
+  pub fn get_session(&self) -> Option<OAuthSession> {
+    self.session.read().ok().map(|s| s.clone())
+  }
+
+  /// Add DPoP bound Authorization and DPoP proof headers
+  pub fn authorize(&self, req: HttpRequest) -> Result<HttpRequest> {
+    let token = self
+      .get_session()
+      .ok_or_else(|| anyhow!("no OAuth session"))?
+      .access_token;
+    let proof = self.dpop.proof(req.method(), req.url(), Some(&token))?;
+    Ok(
+      req
+        .set("Authorization", &format!("DPoP {}", token))
+        .set("DPoP", &proof),
+    )
+  }
+
+  /// Remember DPoP-Nonce of the resource server, returns true if it is changed
+  pub fn update_nonce(&self, res: &HttpResponse) -> bool {
+    self
+      .dpop
+      .update_nonce(res.get_url(), res.header("DPoP-Nonce"))
+  }
+
+  /// Returns true if the access token expires soon
+  pub fn is_expiring(&self) -> bool {
+    self
+      .get_session()
+      .and_then(|s| s.expires_at)
+      .map(|exp| exp - Utc::now() < Duration::seconds(JWT_REFRESH_MARGIN_SECONDS))
+      .unwrap_or_default()
+  }
+
+  /// Renew the tokens and call the refresh callback
+  pub fn refresh(&self) -> Result<OAuthSession> {
+    // refresh tokens are rotated, so refresh one by one with the latest one
+    let _lock = self
+      .refresh_lock
+      .lock()
+      .map_err(|e| anyhow!("OAuth refresh lock error : {}", e))?;
+    let session = self
+      .get_session()
+      .ok_or_else(|| anyhow!("no OAuth session"))?;
+    let refreshed = self.client.refresh_with(&session, &self.dpop)?;
+    if let Ok(mut write) = self.session.write() {
+      *write = refreshed.clone();
+    }
+    if let Some(callback) = &self.refresh_callback {
+      callback(&refreshed);
+    }
+    Ok(refreshed)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::thread::scope;
+
+  use super::*;
+  use crate::{MockTransport, Plc};
+
+  const DID: &str = "did:plc:alice";
+  const ISSUER: &str = "https://as.test";
+
+  fn token(access: &str, refresh: &str) -> Value {
+    json!({
+      "access_token": access,
+      "token_type": "DPoP",
+      "refresh_token": refresh,
+      "expires_in": 3600,
+      "scope": "atproto transition:generic",
+      "sub": DID,
+    })
+  }
+
+  fn setup() -> (OAuthClient, MockTransport) {
+    let directory = MockTransport::new();
+    directory.push_json(
+      &format!("/{}", DID),
+      200,
+      json!({
+        "@context": ["https://www.w3.org/ns/did/v1"],
+        "id": DID,
+        "alsoKnownAs": ["at://alice.test"],
+        "verificationMethod": [],
+        "service": [{
+          "id": "#atproto_pds",
+          "type": "AtprotoPersonalDataServer",
+          "serviceEndpoint": "https://pds.test",
+        }],
+      }),
+    );
+    let mut plc = Plc::new("plc.test", "https");
+    plc.set_transport(directory);
+    let servers = MockTransport::new();
+    servers.push_json(
+      "/.well-known/oauth-protected-resource",
+      200,
+      json!({ "resource": "https://pds.test", "authorization_servers": [ISSUER] }),
+    );
+    servers.push_json(
+      "/.well-known/oauth-authorization-server",
+      200,
+      json!({
+        "issuer": ISSUER,
+        "authorization_endpoint": "https://as.test/oauth/authorize",
+        "token_endpoint": "https://as.test/oauth/token",
+        "pushed_authorization_request_endpoint": "https://as.test/oauth/par",
+        "dpop_signing_alg_values_supported": ["ES256"],
+        "authorization_response_iss_parameter_supported": true,
+      }),
+    );
+    servers.push_response(
+      "/oauth/par",
+      HttpResponse::new(
+        "https://as.test/oauth/par",
+        400,
+        json!({ "error": "use_dpop_nonce" })
+          .to_string()
+          .into_bytes(),
+      )
+      .with_header("DPoP-Nonce", "nonce1"),
+    );
+    servers.push_json(
+      "/oauth/par",
+      201,
+      json!({ "request_uri": "urn:ietf:params:oauth:request_uri:1", "expires_in": 60 }),
+    );
+    servers.push_json("/oauth/token", 200, token("access1", "refresh1"));
+    servers.push_json("/oauth/token", 200, token("access2", "refresh2"));
+    servers.push_json("/oauth/token", 200, token("access3", "refresh3"));
+    let mut client = OAuthClient::new(
+      "https://app.test/client-metadata.json",
+      "https://app.test/callback",
+    );
+    client.set_transport(servers.clone());
+    client.set_identity_resolver(IdentityResolver::new(plc));
+    (client, servers)
+  }
+
+  fn requests(transport: &MockTransport, path: &str) -> Vec<HttpRequest> {
+    transport
+      .get_requests()
+      .into_iter()
+      .filter(|r| r.url().ends_with(path))
+      .collect()
+  }
+
+  fn dpop_claims(req: &HttpRequest) -> Value {
+    let proof = req.header("DPoP").unwrap_or_default();
+    let payload = proof.split('.').nth(1).unwrap_or_default();
+    serde_json::from_slice(&Base::Base64Url.decode(payload).unwrap()).unwrap()
+  }
+
+  fn form(req: &HttpRequest) -> HashMap<String, String> {
+    url::form_urlencoded::parse(req.body.as_deref().unwrap_or_default())
+      .into_owned()
+      .collect()
+  }
+
+  #[test]
+  fn authorize_with_nonce_retry() {
+    let (client, servers) = setup();
+    let state = client.authorize(DID).unwrap();
+    assert!(state.iss_required);
+    assert_eq!(state.pds.as_deref(), Some("https://pds.test"));
+    assert!(state
+      .authorization_url
+      .contains("request_uri=urn%3Aietf%3Aparams%3Aoauth%3Arequest_uri%3A1"));
+    let par = requests(&servers, "/oauth/par");
+    assert_eq!(par.len(), 2);
+    assert_eq!(dpop_claims(&par[0]).get("nonce"), None);
+    assert_eq!(dpop_claims(&par[1])["nonce"], "nonce1");
+    let body = form(&par[1]);
+    assert_eq!(body["login_hint"], DID);
+    assert_eq!(body["code_challenge_method"], "S256");
+  }
+
+  #[test]
+  fn exchange_code_and_refresh() {
+    let (client, servers) = setup();
+    let state = client.authorize(DID).unwrap();
+    let redirected = format!("https://app.test/callback?code=code1&state={}", state.state);
+    let e = client.callback(&state, &redirected).unwrap_err();
+    assert!(e.to_string().contains("no iss"));
+    let redirected = format!("{}&iss={}", redirected, ISSUER);
+    let session = client.callback(&state, &redirected).unwrap();
+    assert_eq!(session.did, DID);
+    assert_eq!(session.pds, "https://pds.test");
+    assert_eq!(session.access_token, "access1");
+    let exchange = requests(&servers, "/oauth/token");
+    assert_eq!(exchange.len(), 1);
+    assert_eq!(form(&exchange[0])["code_verifier"], state.code_verifier);
+
+    let agent = OAuthAgent::new(client, session).unwrap();
+    scope(|s| {
+      for _ in 0..2 {
+        s.spawn(|| agent.refresh().unwrap());
+      }
+    });
+    let refresh = requests(&servers, "/oauth/token")
+      .iter()
+      .skip(1)
+      .map(|r| form(r)["refresh_token"].clone())
+      .collect::<Vec<_>>();
+    assert_eq!(refresh, vec!["refresh1", "refresh2"]);
+    let session = agent.get_session().unwrap();
+    assert_eq!(session.access_token, "access3");
+    assert_eq!(session.refresh_token.as_deref(), Some("refresh3"));
+  }
+}
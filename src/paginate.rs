@@ -0,0 +1,257 @@
+//! Iterators following cursors of paginated endpoints
+use std::collections::VecDeque;
+
+use anyhow::Result;
+
+use crate::api::{
+  AppBskyActorDefsProfileview, AppBskyFeedDefsFeedviewpost, ChatBskyConvoDefsConvoview,
+  ChatBskyConvoGetmessagesMainOutputMessagesItem, Client, ComAtprotoRepoListrecordsRecord,
+  ComAtprotoSyncListreposRepo,
+};
+
+/// Maximum page size of the list endpoints
+pub const PAGE_SIZE: usize = 100;
+
+type Fetch<'a, T> = Box<dyn FnMut(Option<&str>, i64) -> Result<(Vec<T>, Option<String>)> + 'a>;
+
+/// Iterator over items of a cursor paginated endpoint
+pub struct Paginator<'a, T> {
+  fetch: Fetch<'a, T>,
+  buffer: VecDeque<T>,
+  cursor: Option<String>,
+  page_cursor: Option<String>,
+  limit: Option<usize>,
+  page_size: usize,
+  done: bool,
+}
+
+impl<'a, T> Paginator<'a, T> {
+  /// Create iterator calling the endpoint with cursor and page size, returning items and next cursor
+  pub fn new<F: FnMut(Option<&str>, i64) -> Result<(Vec<T>, Option<String>)> + 'a>(
+    fetch: F,
+  ) -> Self {
+    Self {
+      fetch: Box::new(fetch),
+      buffer: VecDeque::new(),
+      cursor: None,
+      page_cursor: None,
+      limit: None,
+      page_size: PAGE_SIZE,
+      done: false,
+    }
+  }
+
+  /// Stop after the number of items
+  pub fn limit(mut self, limit: usize) -> Self {
+    self.limit = Some(limit);
+    self
+  }
+
+  /// Set number of items requested per page
+  pub fn page_size(mut self, page_size: usize) -> Self {
+    self.page_size = page_size.max(1);
+    self
+  }
+
+  /// Start from the cursor of the previous iteration
+  pub fn cursor<T2: ToString>(mut self, cursor: T2) -> Self {
+    self.cursor = Some(cursor.to_string());
+    self
+  }
+
+  /// Returns cursor to resume later without missing items
+  ///
+  /// While items of the fetched page remain, this is the cursor of that page,
+  /// so resuming fetches the page again and repeats the items already returned from it.
+  pub fn get_cursor(&self) -> Option<String> {
+    match self.buffer.is_empty() {
+      true => self.cursor.clone(),
+      false => self.page_cursor.clone(),
+    }
+  }
+
+  fn fetch_page(&mut self) -> Result<()> {
+    let page_size = match self.limit {
+      Some(limit) => self.page_size.min(limit),
+      None => self.page_size,
+    };
+    let (items, cursor) = (self.fetch)(self.cursor.as_deref(), page_size as i64)?;
+    // empty pages with a new cursor happen when the server filters items out
+    if cursor.is_none() || cursor == self.cursor {
+      self.done = true;
+    }
+    self.page_cursor = std::mem::replace(&mut self.cursor, cursor);
+    self.buffer.extend(items);
+    Ok(())
+  }
+}
+
+impl<T> Iterator for Paginator<'_, T> {
+  type Item = Result<T>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.limit == Some(0) {
+      return None;
+    }
+    while self.buffer.is_empty() && !self.done {
+      if let Err(e) = self.fetch_page() {
+        self.done = true;
+        return Some(Err(e));
+      }
+    }
+    let item = self.buffer.pop_front()?;
+    if let Some(limit) = self.limit.as_mut() {
+      *limit -= 1;
+    }
+    Some(Ok(item))
+  }
+}
+
+impl Client {
+  /// Iterate accounts followed by the actor
+  pub fn app_bsky_graph_getfollows_iter(
+    &self,
+    actor: &str,
+  ) -> Paginator<'_, AppBskyActorDefsProfileview> {
+    let actor = actor.to_string();
+    Paginator::new(move |cursor, limit| {
+      let page = self.app_bsky_graph_getfollows(&actor, Some(limit), cursor)?;
+      Ok((page.follows, page.cursor))
+    })
+  }
+
+  /// Iterate accounts following the actor
+  pub fn app_bsky_graph_getfollowers_iter(
+    &self,
+    actor: &str,
+  ) -> Paginator<'_, AppBskyActorDefsProfileview> {
+    let actor = actor.to_string();
+    Paginator::new(move |cursor, limit| {
+      let page = self.app_bsky_graph_getfollowers(&actor, Some(limit), cursor)?;
+      Ok((page.followers, page.cursor))
+    })
+  }
+
+  /// Iterate posts and reposts of the actor
+  pub fn app_bsky_feed_getauthorfeed_iter(
+    &self,
+    actor: &str,
+    filter: Option<&str>,
+  ) -> Paginator<'_, AppBskyFeedDefsFeedviewpost> {
+    let actor = actor.to_string();
+    let filter = filter.map(String::from);
+    Paginator::new(move |cursor, limit| {
+      let page =
+        self.app_bsky_feed_getauthorfeed(&actor, Some(limit), cursor, filter.as_deref())?;
+      Ok((page.feed, page.cursor))
+    })
+  }
+
+  /// Iterate conversations of the account
+  pub fn chat_bsky_convo_listconvos_iter(&self) -> Paginator<'_, ChatBskyConvoDefsConvoview> {
+    Paginator::new(move |cursor, limit| {
+      let page = self.chat_bsky_convo_listconvos(Some(limit), cursor)?;
+      Ok((page.convos, page.cursor))
+    })
+  }
+
+  /// Iterate messages of the conversation from the newest
+  pub fn chat_bsky_convo_getmessages_iter(
+    &self,
+    convo_id: &str,
+  ) -> Paginator<'_, ChatBskyConvoGetmessagesMainOutputMessagesItem> {
+    let convo_id = convo_id.to_string();
+    Paginator::new(move |cursor, limit| {
+      let page = self.chat_bsky_convo_getmessages(&convo_id, Some(limit), cursor)?;
+      Ok((page.messages, page.cursor))
+    })
+  }
+
+  /// Iterate records of the collection in the repository
+  pub fn com_atproto_repo_listrecords_iter(
+    &self,
+    repo: &str,
+    collection: &str,
+    reverse: Option<bool>,
+  ) -> Paginator<'_, ComAtprotoRepoListrecordsRecord> {
+    let repo = repo.to_string();
+    let collection = collection.to_string();
+    Paginator::new(move |cursor, limit| {
+      let page = self.com_atproto_repo_listrecords(
+        &repo,
+        &collection,
+        Some(limit),
+        cursor,
+        None,
+        None,
+        reverse,
+      )?;
+      Ok((page.records, page.cursor))
+    })
+  }
+
+  /// Iterate repositories hosted by the server
+  pub fn com_atproto_sync_listrepos_iter(&self) -> Paginator<'_, ComAtprotoSyncListreposRepo> {
+    Paginator::new(move |cursor, limit| {
+      let page = self.com_atproto_sync_listrepos(Some(limit), cursor)?;
+      Ok((page.repos, page.cursor))
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Paginator over the pages, each page is returned for the cursor of its index
+  fn pages(pages: Vec<(Vec<i64>, Option<&'static str>)>) -> Paginator<'static, i64> {
+    Paginator::new(move |cursor, _| {
+      let index = cursor.map(|c| c.parse::<usize>().unwrap()).unwrap_or(0);
+      let (items, cursor) = pages[index].clone();
+      Ok((items, cursor.map(String::from)))
+    })
+  }
+
+  #[test]
+  fn skip_empty_pages_with_cursor() {
+    let items = pages(vec![
+      (vec![1, 2], Some("1")),
+      (vec![], Some("2")),
+      (vec![3], Some("3")),
+      (vec![], None),
+    ]);
+    assert_eq!(items.map(|i| i.unwrap()).collect::<Vec<_>>(), vec![1, 2, 3]);
+  }
+
+  #[test]
+  fn stop_on_repeated_cursor() {
+    let items = pages(vec![(vec![1], Some("1")), (vec![2], Some("1"))]);
+    assert_eq!(items.map(|i| i.unwrap()).collect::<Vec<_>>(), vec![1, 2]);
+  }
+
+  #[test]
+  fn resume_without_missing_items() {
+    let source = vec![
+      (vec![1, 2], Some("1")),
+      (vec![3, 4], Some("2")),
+      (vec![], None),
+    ];
+    let mut items = pages(source.clone());
+    assert_eq!(items.next().unwrap().unwrap(), 1);
+    assert_eq!(items.next().unwrap().unwrap(), 2);
+    assert_eq!(items.next().unwrap().unwrap(), 3);
+    // 4 is still buffered, resuming refetches its page
+    let cursor = items.get_cursor().unwrap();
+    assert_eq!(cursor, "1");
+    let resumed = pages(source).cursor(cursor);
+    assert_eq!(resumed.map(|i| i.unwrap()).collect::<Vec<_>>(), vec![3, 4]);
+    assert_eq!(items.next().unwrap().unwrap(), 4);
+    assert_eq!(items.get_cursor().as_deref(), Some("2"));
+  }
+
+  #[test]
+  fn limit_items() {
+    let items = pages(vec![(vec![1, 2], Some("1")), (vec![3, 4], None)]).limit(3);
+    assert_eq!(items.map(|i| i.unwrap()).collect::<Vec<_>>(), vec![1, 2, 3]);
+  }
+}
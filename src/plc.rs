@@ -1,12 +1,28 @@
-use std::collections::HashMap;
+use std::{
+  collections::{HashMap, HashSet, VecDeque},
+  fs::{File, OpenOptions},
+  io::{BufRead, BufReader, Write},
+  path::Path,
+  sync::Arc,
+};
 
-use anyhow::Result;
-use chrono::{DateTime, Utc};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use libipld::{
+  cbor::DagCborCodec, multibase::Base, multihash::Multihash, prelude::Codec, Cid, Ipld,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use ureq::{Agent, Proxy};
+use sha2::{Digest, Sha256};
 
-use crate::api::{CidString, DidDoc};
+use crate::{
+  api::{json_to_ipld, CidString, DidDoc, DAG_CBOR_CODEC, SHA2_256},
+  crypto::{Keypair, PublicKey},
+  transport::{default_agent, HttpRequest, HttpResponse, HttpTransport},
+};
+
+/// Period in which a higher priority rotation key can nullify operations
+pub const PLC_RECOVERY_WINDOW_HOURS: i64 = 72;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -19,6 +35,59 @@ pub struct PlcOperation {
   pub sig: String,
 }
 
+impl PlcOperation {
+  /// Returns public key to verify repository commits
+  pub fn atproto_signing_key(&self) -> Result<PublicKey> {
+    match self.verification_methods.get("atproto") {
+      Some(k) => PublicKey::from_did_key(k),
+      None => bail!("no atproto verification method"),
+    }
+  }
+
+  /// Returns public keys allowed to sign the next operation in order of priority
+  pub fn rotation_public_keys(&self) -> Result<Vec<PublicKey>> {
+    self
+      .rotation_keys
+      .iter()
+      .map(|k| PublicKey::from_did_key(k))
+      .collect()
+  }
+
+  /// Convert to DID document of the DID
+  pub fn to_did_doc(&self, did: &str) -> Result<DidDoc> {
+    let mut verification_methods = self.verification_methods.iter().collect::<Vec<_>>();
+    verification_methods.sort();
+    let mut services = self.services.iter().collect::<Vec<_>>();
+    services.sort_by_key(|(id, _)| id.as_str());
+    Ok(serde_json::from_value(serde_json::json!({
+      "@context": [
+        "https://www.w3.org/ns/did/v1",
+        "https://w3id.org/security/multikey/v1",
+        "https://w3id.org/security/suites/secp256k1-2019/v1",
+      ],
+      "id": did,
+      "alsoKnownAs": self.also_known_as,
+      "verificationMethod": verification_methods
+        .into_iter()
+        .map(|(id, key)| serde_json::json!({
+          "id": format!("{}#{}", did, id),
+          "type": "Multikey",
+          "controller": did,
+          "publicKeyMultibase": key.strip_prefix("did:key:").unwrap_or(key),
+        }))
+        .collect::<Vec<_>>(),
+      "service": services
+        .into_iter()
+        .map(|(id, service)| serde_json::json!({
+          "id": format!("#{}", id),
+          "type": service.get("type"),
+          "serviceEndpoint": service.get("endpoint"),
+        }))
+        .collect::<Vec<_>>(),
+    }))?)
+  }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct PlcTombstone {
@@ -51,6 +120,335 @@ impl Default for PlcOp {
   }
 }
 
+impl PlcOp {
+  fn to_ipld(&self, signed: bool) -> Result<Ipld> {
+    let mut ipld = json_to_ipld(&serde_json::to_value(self)?);
+    if !signed {
+      if let Ipld::Map(m) = &mut ipld {
+        m.remove("sig");
+      }
+    }
+    Ok(ipld)
+  }
+
+  /// Returns DAG-CBOR of the operation without the signature
+  pub fn unsigned_bytes(&self) -> Result<Vec<u8>> {
+    DagCborCodec.encode(&self.to_ipld(false)?)
+  }
+
+  /// Returns DAG-CBOR of the signed operation
+  pub fn signed_bytes(&self) -> Result<Vec<u8>> {
+    DagCborCodec.encode(&self.to_ipld(true)?)
+  }
+
+  /// Returns CID of the signed operation, used as prev of the next operation
+  pub fn cid(&self) -> Result<Cid> {
+    Ok(Cid::new_v1(
+      DAG_CBOR_CODEC,
+      Multihash::wrap(SHA2_256, &Sha256::digest(self.signed_bytes()?))?,
+    ))
+  }
+
+  /// Returns did:plc created by the genesis operation
+  pub fn did(&self) -> Result<String> {
+    if self.prev().is_some() {
+      bail!("not a genesis operation");
+    }
+    let hash = Base::Base32Lower.encode(Sha256::digest(self.signed_bytes()?));
+    Ok(format!("did:plc:{}", &hash[..24]))
+  }
+
+  /// Returns CID of the previous operation
+  pub fn prev(&self) -> Option<&str> {
+    match self {
+      Self::PlcOperation(op) => op.prev.as_deref(),
+      Self::PlcTombstone(op) => Some(&op.prev),
+      Self::Create(op) => op.prev.as_deref(),
+    }
+  }
+
+  /// Returns base64url encoded signature
+  pub fn sig(&self) -> &str {
+    match self {
+      Self::PlcOperation(op) => &op.sig,
+      Self::PlcTombstone(op) => &op.sig,
+      Self::Create(op) => &op.sig,
+    }
+  }
+
+  /// Returns state of the DID after the operation, legacy create is normalized
+  pub fn to_operation(&self) -> Option<PlcOperation> {
+    match self {
+      Self::PlcOperation(op) => Some(op.clone()),
+      Self::PlcTombstone(_) => None,
+      Self::Create(op) => Some(PlcOperation {
+        rotation_keys: vec![op.recovery_key.clone(), op.signing_key.clone()],
+        verification_methods: HashMap::from([(String::from("atproto"), op.signing_key.clone())]),
+        also_known_as: vec![format!("at://{}", op.handle)],
+        services: HashMap::from([(
+          String::from("atproto_pds"),
+          HashMap::from([
+            (
+              String::from("type"),
+              String::from("AtprotoPersonalDataServer"),
+            ),
+            (String::from("endpoint"), op.service.clone()),
+          ]),
+        )]),
+        prev: op.prev.clone(),
+        sig: op.sig.clone(),
+      }),
+    }
+  }
+
+  /// Returns index of the rotation key which signed the operation
+  pub fn find_signer(&self, rotation_keys: &[String]) -> Result<usize> {
+    let data = self.unsigned_bytes()?;
+    let sig = Base::Base64Url.decode(self.sig())?;
+    rotation_keys
+      .iter()
+      .position(|k| {
+        PublicKey::from_did_key(k)
+          .and_then(|k| k.verify(&data, &sig))
+          .is_ok()
+      })
+      .ok_or_else(|| anyhow!("signature is not made by the rotation keys"))
+  }
+
+  /// Sign the operation with the rotation key
+  pub fn sign(&mut self, key: &Keypair) -> Result<()> {
+    let sig = Base::Base64Url.encode(key.sign(&self.unsigned_bytes()?));
+    match self {
+      Self::PlcOperation(op) => op.sig = sig,
+      Self::PlcTombstone(op) => op.sig = sig,
+      Self::Create(op) => op.sig = sig,
+    }
+    Ok(())
+  }
+
+  /// Create tombstone operation deactivating the DID
+  pub fn tombstone<T: ToString>(prev: T, key: &Keypair) -> Result<Self> {
+    let mut op = Self::PlcTombstone(PlcTombstone {
+      prev: prev.to_string(),
+      sig: String::new(),
+    });
+    op.sign(key)?;
+    Ok(op)
+  }
+}
+
+struct ValidOp<'a> {
+  entry: &'a LogEntry,
+  cid: String,
+  rotation_keys: Vec<String>,
+  signer: usize,
+}
+
+fn validate_entry<'a>(
+  did: &str,
+  history: &mut Vec<ValidOp<'a>>,
+  entry: &'a LogEntry,
+) -> Result<Vec<String>> {
+  let op = &entry.operation;
+  let cid = op.cid()?.to_string();
+  if entry.did != did {
+    bail!("DID {} does not match", entry.did);
+  }
+  if entry.cid.to_string() != cid {
+    bail!("CID {} does not match the operation {}", entry.cid, cid);
+  }
+  let rotation_keys = op
+    .to_operation()
+    .map(|o| o.rotation_keys)
+    .unwrap_or_default();
+  let Some(prev) = op.prev() else {
+    if !history.is_empty() {
+      bail!("genesis operation after genesis");
+    }
+    if op.did()? != did {
+      bail!("genesis operation does not create {}", did);
+    }
+    let signer = op.find_signer(&rotation_keys)?;
+    history.push(ValidOp {
+      entry,
+      cid,
+      rotation_keys,
+      signer,
+    });
+    return Ok(Vec::new());
+  };
+  let Some(index) = history.iter().position(|h| h.cid == prev) else {
+    bail!("previous operation {} is not found", prev);
+  };
+  if history[index].rotation_keys.is_empty() {
+    bail!("operation after tombstone");
+  }
+  let signer = op.find_signer(&history[index].rotation_keys)?;
+  let nullified = history.split_off(index + 1);
+  if let Some(first) = nullified.first() {
+    if signer >= first.signer {
+      bail!("rotation key has no priority to nullify {}", first.cid);
+    }
+    if entry.created_at - first.entry.created_at > Duration::hours(PLC_RECOVERY_WINDOW_HOURS) {
+      bail!("recovery window of {} has passed", first.cid);
+    }
+  }
+  history.push(ValidOp {
+    entry,
+    cid,
+    rotation_keys,
+    signer,
+  });
+  Ok(nullified.into_iter().map(|n| n.cid).collect())
+}
+
+/// Replay the log ignoring nullified flags, returns the current operation and CIDs to be nullified
+pub(crate) fn replay_audit_log(did: &str, log: &[LogEntry]) -> Result<(PlcOp, Vec<String>)> {
+  let mut entries = log.iter().collect::<Vec<_>>();
+  entries.sort_by_key(|e| e.created_at);
+  let mut history = Vec::new();
+  let mut nullified = Vec::new();
+  for entry in entries.into_iter() {
+    let n = validate_entry(did, &mut history, entry)
+      .map_err(|e| anyhow!("operation {} : {}", entry.cid, e))?;
+    nullified.extend(n);
+  }
+  let Some(last) = history.last() else {
+    bail!("no operation of {}", did);
+  };
+  Ok((last.entry.operation.clone(), nullified))
+}
+
+/// Replay the audit log from genesis, returns the current DID document or None if tombstoned
+pub fn validate_audit_log(did: &str, log: &[LogEntry]) -> Result<Option<DidDoc>> {
+  let (last, nullified) = replay_audit_log(did, log)?;
+  for entry in log.iter() {
+    if entry.nullified != nullified.contains(&entry.cid.to_string()) {
+      bail!("nullified flag of {} is wrong", entry.cid);
+    }
+  }
+  match last.to_operation() {
+    Some(op) => Ok(Some(op.to_did_doc(did)?)),
+    None => Ok(None),
+  }
+}
+
+/// Builder of signed genesis and update operations
+#[derive(Debug, Clone, Default)]
+pub struct PlcOpBuilder {
+  rotation_keys: Vec<String>,
+  verification_methods: HashMap<String, String>,
+  also_known_as: Vec<String>,
+  services: HashMap<String, HashMap<String, String>>,
+}
+
+impl From<&PlcOperation> for PlcOpBuilder {
+  fn from(value: &PlcOperation) -> Self {
+    Self {
+      rotation_keys: value.rotation_keys.clone(),
+      verification_methods: value.verification_methods.clone(),
+      also_known_as: value.also_known_as.clone(),
+      services: value.services.clone(),
+    }
+  }
+}
+
+impl PlcOpBuilder {
+  /// Create empty builder
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Add rotation key as did:key, the first one has the highest priority
+  pub fn rotation_key<T: ToString>(mut self, did_key: T) -> Self {
+    self.rotation_keys.push(did_key.to_string());
+    self
+  }
+
+  /// Replace all rotation keys
+  pub fn rotation_keys(mut self, did_keys: Vec<String>) -> Self {
+    self.rotation_keys = did_keys;
+    self
+  }
+
+  /// Set verification method as did:key
+  pub fn verification_method<T1: ToString, T2: ToString>(mut self, id: T1, did_key: T2) -> Self {
+    self
+      .verification_methods
+      .insert(id.to_string(), did_key.to_string());
+    self
+  }
+
+  /// Set atproto repository signing key as did:key
+  pub fn atproto_signing_key<T: ToString>(self, did_key: T) -> Self {
+    self.verification_method("atproto", did_key)
+  }
+
+  /// Replace alsoKnownAs with the handle
+  pub fn handle<T: ToString>(mut self, handle: T) -> Self {
+    self.also_known_as = vec![format!("at://{}", handle.to_string())];
+    self
+  }
+
+  /// Add URI to alsoKnownAs
+  pub fn also_known_as<T: ToString>(mut self, uri: T) -> Self {
+    self.also_known_as.push(uri.to_string());
+    self
+  }
+
+  /// Set service endpoint
+  pub fn service<T1: ToString, T2: ToString, T3: ToString>(
+    mut self,
+    id: T1,
+    service_type: T2,
+    endpoint: T3,
+  ) -> Self {
+    self.services.insert(
+      id.to_string(),
+      HashMap::from([
+        (String::from("type"), service_type.to_string()),
+        (String::from("endpoint"), endpoint.to_string()),
+      ]),
+    );
+    self
+  }
+
+  /// Set atproto PDS endpoint
+  pub fn atproto_pds<T: ToString>(self, endpoint: T) -> Self {
+    self.service("atproto_pds", "AtprotoPersonalDataServer", endpoint)
+  }
+
+  fn build(self, prev: Option<String>, key: &Keypair) -> Result<PlcOp> {
+    if self.rotation_keys.is_empty() {
+      bail!("no rotation key");
+    }
+    let mut op = PlcOp::PlcOperation(PlcOperation {
+      rotation_keys: self.rotation_keys,
+      verification_methods: self.verification_methods,
+      also_known_as: self.also_known_as,
+      services: self.services,
+      prev,
+      sig: String::new(),
+    });
+    op.sign(key)?;
+    Ok(op)
+  }
+
+  /// Create genesis operation signed with the rotation key, returns did:plc and the operation
+  pub fn genesis(self, key: &Keypair) -> Result<(String, PlcOp)> {
+    if !self.rotation_keys.contains(&key.did_key()) {
+      bail!("genesis operation must be signed with one of its rotation keys");
+    }
+    let op = self.build(None, key)?;
+    Ok((op.did()?, op))
+  }
+
+  /// Create operation following the previous one, signed with the rotation key
+  pub fn update<T: ToString>(self, prev: T, key: &Keypair) -> Result<PlcOp> {
+    self.build(Some(prev.to_string()), key)
+  }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct LogEntry {
@@ -61,99 +459,120 @@ pub struct LogEntry {
   pub created_at: DateTime<Utc>,
 }
 
+#[derive(Clone)]
 pub struct Plc {
   host: String,
-  agent: Agent,
+  scheme: String,
+  transport: Arc<dyn HttpTransport>,
 }
 
 impl Default for Plc {
   fn default() -> Self {
-    match std::env::var("HTTPS_PROXY")
-      .ok()
-      .or_else(|| std::env::var("https_proxy").ok())
-    {
-      Some(proxy) => Self {
-        host: String::from("plc.directory"),
-        agent: ureq::builder().proxy(Proxy::new(proxy).unwrap()).build(),
-      },
-      None => Self {
-        host: String::from("plc.directory"),
-        agent: ureq::agent(),
-      },
-    }
+    Self::new("plc.directory", "https")
   }
 }
 
 impl Plc {
+  /// Create client of the PLC directory at the host, scheme is "https" or "http"
+  pub fn new<T1: ToString, T2: ToString>(host: T1, scheme: T2) -> Self {
+    Self {
+      host: host.to_string(),
+      scheme: scheme.to_string(),
+      transport: Arc::new(default_agent()),
+    }
+  }
+
+  /// Set transport to send HTTP requests instead of ureq
+  pub fn set_transport<T: HttpTransport + 'static>(&mut self, transport: T) {
+    self.transport = Arc::new(transport);
+  }
+
+  fn call(&self, req: HttpRequest) -> Result<HttpResponse> {
+    self.transport.send(req)?.error_for_status()
+  }
+
+  /// Returns host of the PLC directory
+  pub fn get_host(&self) -> String {
+    self.host.clone()
+  }
+
+  /// Returns scheme of the PLC directory
+  pub fn get_scheme(&self) -> String {
+    self.scheme.clone()
+  }
+
   pub fn resolve_did(&self, did: &str) -> Result<DidDoc> {
-    Ok(
-      self
-        .agent
-        .get(&format!("https://{}/{}", self.host, did))
-        .call()?
-        .into_json()?,
-    )
+    self
+      .call(HttpRequest::get(format!(
+        "{}://{}/{}",
+        self.scheme, self.host, did
+      )))?
+      .into_json()
   }
 
   pub fn create_plc_op(&self, did: &str, op: &PlcOp) -> Result<Value> {
-    Ok(
-      self
-        .agent
-        .post(&format!("https://{}/{}", self.host, did))
-        .send_json(op)?
-        .into_json()?,
-    )
+    let res = self
+      .call(HttpRequest::post(format!("{}://{}/{}", self.scheme, self.host, did)).json(op)?)?
+      .into_string()?;
+    match res.trim().is_empty() {
+      true => Ok(Value::Null),
+      false => Ok(serde_json::from_str(&res)?),
+    }
   }
 
   pub fn get_plc_op_log(&self, did: &str) -> Result<Vec<PlcOp>> {
-    Ok(
-      self
-        .agent
-        .get(&format!("https://{}/{}/log", self.host, did))
-        .call()?
-        .into_json()?,
-    )
+    self
+      .call(HttpRequest::get(format!(
+        "{}://{}/{}/log",
+        self.scheme, self.host, did
+      )))?
+      .into_json()
   }
 
   pub fn get_plc_audit_log(&self, did: &str) -> Result<Vec<LogEntry>> {
-    Ok(
-      self
-        .agent
-        .get(&format!("https://{}/{}/log/audit", self.host, did))
-        .call()?
-        .into_json()?,
-    )
+    self
+      .call(HttpRequest::get(format!(
+        "{}://{}/{}/log/audit",
+        self.scheme, self.host, did
+      )))?
+      .into_json()
+  }
+
+  /// Fetch and validate the audit log, returns the current DID document or None if tombstoned
+  pub fn audit(&self, did: &str) -> Result<Option<DidDoc>> {
+    validate_audit_log(did, &self.get_plc_audit_log(did)?)
   }
 
   pub fn get_last_op(&self, did: &str) -> Result<LogEntry> {
-    Ok(
-      self
-        .agent
-        .get(&format!("https://{}/{}/log/last", self.host, did))
-        .call()?
-        .into_json()?,
-    )
+    self
+      .call(HttpRequest::get(format!(
+        "{}://{}/{}/log/last",
+        self.scheme, self.host, did
+      )))?
+      .into_json()
   }
 
   pub fn get_plc_data(&self, did: &str) -> Result<Value> {
-    Ok(
-      self
-        .agent
-        .get(&format!("https://{}/{}/data", self.host, did))
-        .call()?
-        .into_json()?,
-    )
+    self
+      .call(HttpRequest::get(format!(
+        "{}://{}/{}/data",
+        self.scheme, self.host, did
+      )))?
+      .into_json()
   }
 
   pub fn export(&self, count: Option<i64>, after: Option<DateTime<Utc>>) -> Result<Vec<LogEntry>> {
-    let mut req = self.agent.get(&format!("https://{}/export", self.host));
+    let mut req = HttpRequest::get(format!("{}://{}/export", self.scheme, self.host));
     if let Some(c) = count.as_ref() {
       req = req.query("count", c.to_string().as_str());
     }
     if let Some(a) = after.as_ref() {
-      req = req.query("after", a.to_string().as_str());
+      req = req.query(
+        "after",
+        a.to_rfc3339_opts(SecondsFormat::Millis, true).as_str(),
+      );
     }
-    let res = req.call()?.into_string()?;
+    let res = self.call(req)?.into_string()?;
     Ok(
       res
         .lines()
@@ -161,4 +580,516 @@ impl Plc {
         .collect(),
     )
   }
+
+  /// Iterate all operations created after the time, polling for new ones after catching up
+  pub fn export_iter(&self, after: Option<DateTime<Utc>>) -> PlcExport {
+    PlcExport {
+      plc: self.clone(),
+      after,
+      buffer: VecDeque::new(),
+      seen: HashSet::new(),
+      poll_interval: Some(std::time::Duration::from_secs(10)),
+    }
+  }
+}
+
+/// Number of operations requested in a page of the export
+pub const PLC_EXPORT_PAGE_SIZE: i64 = 1000;
+
+/// Iterator over the operations of the PLC directory in order of createdAt
+pub struct PlcExport {
+  plc: Plc,
+  after: Option<DateTime<Utc>>,
+  buffer: VecDeque<LogEntry>,
+  seen: HashSet<String>,
+  poll_interval: Option<std::time::Duration>,
+}
+
+impl PlcExport {
+  /// Set interval to poll after catching up, None to stop iteration instead
+  pub fn set_poll_interval(&mut self, interval: Option<std::time::Duration>) {
+    self.poll_interval = interval;
+  }
+
+  /// Returns createdAt of the last returned operation
+  pub fn get_after(&self) -> Option<DateTime<Utc>> {
+    self.after
+  }
+
+  fn fetch(&mut self) -> Result<bool> {
+    // after is exclusive, so ask from a millisecond earlier not to lose operations
+    // sharing createdAt with the last one, returned ones are skipped by CID
+    let after = match self.seen.is_empty() {
+      true => self.after,
+      false => self.after.map(|a| a - Duration::milliseconds(1)),
+    };
+    let page = self.plc.export(Some(PLC_EXPORT_PAGE_SIZE), after)?;
+    let full = page.len() as i64 >= PLC_EXPORT_PAGE_SIZE;
+    let mut added = false;
+    for entry in page.into_iter() {
+      let cid = entry.cid.to_string();
+      if Some(entry.created_at) < self.after || self.seen.contains(&cid) {
+        continue;
+      }
+      if self.after != Some(entry.created_at) {
+        self.seen.clear();
+      }
+      self.after = Some(entry.created_at);
+      self.seen.insert(cid);
+      self.buffer.push_back(entry);
+      added = true;
+    }
+    Ok(full && added)
+  }
+}
+
+impl Iterator for PlcExport {
+  type Item = Result<LogEntry>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while self.buffer.is_empty() {
+      match self.fetch() {
+        Ok(true) => (),
+        Ok(false) if self.buffer.is_empty() => match self.poll_interval {
+          Some(interval) => std::thread::sleep(interval),
+          None => return None,
+        },
+        Ok(false) => (),
+        Err(e) => return Some(Err(e)),
+      }
+    }
+    self.buffer.pop_front().map(Ok)
+  }
+}
+
+/// Local copy of the PLC directory answering resolution without network
+#[derive(Default)]
+pub struct PlcMirror {
+  file: Option<File>,
+  ops: HashMap<String, Vec<LogEntry>>,
+  cids: HashSet<String>,
+  last: Option<DateTime<Utc>>,
+}
+
+impl PlcMirror {
+  /// Create mirror in memory
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Open mirror persisted in the JSON Lines file, created if not exists
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+    let mut mirror = Self::default();
+    if let Ok(file) = File::open(&path) {
+      for line in BufReader::new(file).lines() {
+        mirror.apply(serde_json::from_str(&line?)?);
+      }
+    }
+    mirror.file = Some(OpenOptions::new().create(true).append(true).open(path)?);
+    Ok(mirror)
+  }
+
+  fn apply(&mut self, entry: LogEntry) -> bool {
+    if !self.cids.insert(entry.cid.to_string()) {
+      return false;
+    }
+    self.last = self.last.max(Some(entry.created_at));
+    let ops = self.ops.entry(entry.did.clone()).or_default();
+    if let Some(prev) = entry.operation.prev() {
+      if let Some(index) = ops.iter().position(|o| o.cid.to_string() == prev) {
+        ops
+          .iter_mut()
+          .skip(index + 1)
+          .for_each(|o| o.nullified = true);
+      }
+    }
+    ops.push(entry);
+    true
+  }
+
+  /// Add an operation from the export, returns false if already stored
+  pub fn insert(&mut self, entry: LogEntry) -> Result<bool> {
+    let line = format!("{}\n", serde_json::to_string(&entry)?);
+    if !self.apply(entry) {
+      return Ok(false);
+    }
+    if let Some(file) = self.file.as_mut() {
+      file.write_all(line.as_bytes())?;
+    }
+    Ok(true)
+  }
+
+  /// Download the operations created after the last stored one until catching up
+  pub fn sync(&mut self, plc: &Plc) -> Result<usize> {
+    // operations sharing createdAt with the last one may be added after it was stored,
+    // so ask from a millisecond earlier and skip stored ones by CID
+    let mut export = plc.export_iter(self.last.map(|l| l - Duration::milliseconds(1)));
+    export.set_poll_interval(None);
+    let mut count = 0;
+    for entry in export {
+      if self.insert(entry?)? {
+        count += 1;
+      }
+    }
+    Ok(count)
+  }
+
+  /// Returns createdAt of the newest stored operation
+  pub fn get_last_created_at(&self) -> Option<DateTime<Utc>> {
+    self.last
+  }
+
+  /// Returns stored operations of the DID including nullified ones
+  pub fn get_plc_audit_log(&self, did: &str) -> Vec<LogEntry> {
+    self.ops.get(did).cloned().unwrap_or_default()
+  }
+
+  pub fn get_last_op(&self, did: &str) -> Result<LogEntry> {
+    self
+      .ops
+      .get(did)
+      .and_then(|ops| ops.iter().rev().find(|o| !o.nullified))
+      .cloned()
+      .ok_or_else(|| anyhow!("{} is not found in the mirror", did))
+  }
+
+  pub fn resolve_did(&self, did: &str) -> Result<DidDoc> {
+    match self.get_last_op(did)?.operation.to_operation() {
+      Some(op) => op.to_did_doc(did),
+      None => bail!("{} is tombstoned", did),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::{xrpc::XrpcError, KeyAlgorithm, MockTransport, PlcOpBuilder};
+
+  fn mock_plc(transport: &MockTransport) -> Plc {
+    let mut plc = Plc::new("plc.test", "https");
+    plc.set_transport(transport.clone());
+    plc
+  }
+
+  fn genesis() -> (String, PlcOp) {
+    let key = Keypair::generate(KeyAlgorithm::Secp256k1);
+    PlcOpBuilder::new()
+      .rotation_key(key.did_key())
+      .atproto_signing_key(key.did_key())
+      .handle("alice.test")
+      .atproto_pds("https://pds.test")
+      .genesis(&key)
+      .unwrap()
+  }
+
+  #[test]
+  fn mock_resolve_did() {
+    let (did, op) = genesis();
+    let doc = op.to_operation().unwrap().to_did_doc(&did).unwrap();
+    let transport = MockTransport::new();
+    transport.push_json(
+      &format!("/{}", did),
+      200,
+      serde_json::to_value(&doc).unwrap(),
+    );
+    let resolved = mock_plc(&transport).resolve_did(&did).unwrap();
+    assert_eq!(resolved.id, did);
+    assert_eq!(resolved.pds_endpoint().as_deref(), Some("https://pds.test"));
+  }
+
+  #[test]
+  fn mock_typed_error() {
+    let transport = MockTransport::new();
+    transport.push_json(
+      "/did:plc:unknown/log/last",
+      404,
+      json!({ "message": "DID not registered: did:plc:unknown" }),
+    );
+    let e = mock_plc(&transport)
+      .get_last_op("did:plc:unknown")
+      .unwrap_err();
+    let e = e.downcast_ref::<XrpcError>().unwrap();
+    assert_eq!(e.status(), Some(404));
+    assert_eq!(e.message(), Some("DID not registered: did:plc:unknown"));
+  }
+
+  #[test]
+  fn mock_export_lines() {
+    let (did, op) = genesis();
+    let entry = LogEntry {
+      did: did.clone(),
+      cid: CidString::from(op.cid().unwrap()),
+      operation: op,
+      nullified: false,
+      created_at: DateTime::from_timestamp(1700000000, 0).unwrap(),
+    };
+    let line = serde_json::to_string(&entry).unwrap();
+    let transport = MockTransport::new();
+    transport.push_response(
+      "/export",
+      HttpResponse::new("/export", 200, format!("{}\n{}\n", line, line).into_bytes()),
+    );
+    let after = DateTime::from_timestamp(1600000000, 0);
+    let entries = mock_plc(&transport).export(Some(10), after).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].did, did);
+    let requests = transport.get_requests();
+    assert_eq!(
+      requests[0].url(),
+      "https://plc.test/export?count=10&after=2020-09-13T12%3A26%3A40.000Z"
+    );
+  }
+
+  #[test]
+  fn mock_export_iter_same_created_at_across_pages() {
+    let (did, op) = genesis();
+    let t0 = DateTime::from_timestamp(1700000000, 0).unwrap();
+    let entries = (0..=PLC_EXPORT_PAGE_SIZE)
+      .map(|i| LogEntry {
+        did: did.clone(),
+        cid: CidString::from(Cid::new_v1(
+          DAG_CBOR_CODEC,
+          Multihash::wrap(SHA2_256, &Sha256::digest(i.to_be_bytes())).unwrap(),
+        )),
+        operation: op.clone(),
+        nullified: false,
+        // the last two operations share createdAt
+        created_at: t0 + Duration::milliseconds(i.min(PLC_EXPORT_PAGE_SIZE - 1)),
+      })
+      .collect::<Vec<_>>();
+    let lines = |entries: &[LogEntry]| {
+      let lines = entries
+        .iter()
+        .map(|e| serde_json::to_string(e).unwrap())
+        .collect::<Vec<_>>();
+      HttpResponse::new("/export", 200, lines.join("\n").into_bytes())
+    };
+    let transport = MockTransport::new();
+    let page_size = PLC_EXPORT_PAGE_SIZE as usize;
+    transport.push_response("/export", lines(&entries[..page_size]));
+    transport.push_response("/export", lines(&entries[page_size - 1..]));
+    let mut export = mock_plc(&transport).export_iter(None);
+    export.set_poll_interval(None);
+    let cids = export
+      .map(|e| e.unwrap().cid.to_string())
+      .collect::<Vec<_>>();
+    assert_eq!(cids.len(), entries.len());
+    assert_eq!(cids.last(), Some(&entries[page_size].cid.to_string()));
+    let requests = transport.get_requests();
+    assert!(requests[1]
+      .url()
+      .ends_with("after=2023-11-14T22%3A13%3A20.998Z"));
+  }
+
+  #[test]
+  fn mock_mirror_resume_same_created_at() {
+    let (did, op) = genesis();
+    let created_at = DateTime::from_timestamp(1700000000, 0).unwrap();
+    let entry = |i: u8| LogEntry {
+      did: did.clone(),
+      cid: CidString::from(Cid::new_v1(
+        DAG_CBOR_CODEC,
+        Multihash::wrap(SHA2_256, &Sha256::digest([i])).unwrap(),
+      )),
+      operation: op.clone(),
+      nullified: false,
+      created_at,
+    };
+    let lines = |entries: &[LogEntry]| {
+      let lines = entries
+        .iter()
+        .map(|e| serde_json::to_string(e).unwrap())
+        .collect::<Vec<_>>();
+      HttpResponse::new("/export", 200, lines.join("\n").into_bytes())
+    };
+    let path = std::env::temp_dir().join(format!("aerostream-plc-{}.jsonl", std::process::id()));
+    let transport = MockTransport::new();
+    let plc = mock_plc(&transport);
+    transport.push_response("/export", lines(&[entry(0)]));
+    let mut mirror = PlcMirror::open(&path).unwrap();
+    assert_eq!(mirror.sync(&plc).unwrap(), 1);
+    drop(mirror);
+
+    // the second operation with the same createdAt is exported after the first sync
+    transport.clear();
+    transport.push_response("/export", lines(&[entry(0), entry(1)]));
+    let mut mirror = PlcMirror::open(&path).unwrap();
+    assert_eq!(mirror.sync(&plc).unwrap(), 1);
+    assert!(transport.get_requests()[0]
+      .url()
+      .ends_with("after=2023-11-14T22%3A13%3A19.999Z"));
+    drop(mirror);
+
+    let mirror = PlcMirror::open(&path).unwrap();
+    assert_eq!(mirror.get_plc_audit_log(&did).len(), 2);
+    assert_eq!(mirror.get_last_created_at(), Some(created_at));
+    drop(mirror);
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  // computed independently with Python cryptography and a hand-written DAG-CBOR encoder
+  const K256_KEY: &str = "293394d3d0322baf6f432e9b7d02ce40517ab7c786e7d8e57df640c51e356a80";
+  const K256_GENESIS: &str = r#"{"type": "plc_operation", "rotationKeys": ["did:key:zQ3shuRjwP2gmCcCiYoN813DgjJAAWvj5ug7yZoGuDjydYftm"], "verificationMethods": {"atproto": "did:key:zQ3shuRjwP2gmCcCiYoN813DgjJAAWvj5ug7yZoGuDjydYftm"}, "alsoKnownAs": ["at://alice.test"], "services": {"atproto_pds": {"type": "AtprotoPersonalDataServer", "endpoint": "https://pds.test"}}, "prev": null, "sig": "XhfGvmh24-6P0pqTViAz5T9NjWmjvKPtWld3rYmIk9UXREZuX5cMUK-idNq12jVuw_wpZs6kcj2YnKZe6PE2QQ"}"#;
+  const K256_DID: &str = "did:plc:2l4il3poxdbwcikzfzfojj65";
+  const K256_CID: &str = "bafyreigs7cc633vyynqscwjojlskpxlc6eh2ldwvxhzm77rlq26nhhbpvq";
+  const P256_KEY: &str = "4a013c32b681e57ee25df5fd273a03ceaf3ec767fef7fc1939041061c1a924bd";
+  const P256_GENESIS: &str = r#"{"type": "plc_operation", "rotationKeys": ["did:key:zDnaeWwucUVq4LZyvYAGoU5PozQdTM37GY66iK9xr4Q3QP3WK"], "verificationMethods": {"atproto": "did:key:zDnaeWwucUVq4LZyvYAGoU5PozQdTM37GY66iK9xr4Q3QP3WK"}, "alsoKnownAs": ["at://alice.test"], "services": {"atproto_pds": {"type": "AtprotoPersonalDataServer", "endpoint": "https://pds.test"}}, "prev": null, "sig": "cF01Fd0YBzQB_qsMqKtBRslDK_UNz6s4u9Si7v09P2dAhMpsciCwYL69sw8CvwQqswYVoGlQpnnwkvYeNKPwMw"}"#;
+  const P256_DID: &str = "did:plc:nipmqmwzz7d4xymztjand5bh";
+  const P256_CID: &str = "bafyreidkd3edfwopy7f6dgm2idi7ij2ygz6vfjwhxwpcc4hzwmuzgbd47e";
+
+  fn check_vector(algorithm: KeyAlgorithm, key: &str, genesis: &str, did: &str, cid: &str) {
+    let key = Keypair::from_bytes(algorithm, &Base::Base16Lower.decode(key).unwrap()).unwrap();
+    let op: PlcOp = serde_json::from_str(genesis).unwrap();
+    assert_eq!(op.did().unwrap(), did);
+    assert_eq!(op.cid().unwrap().to_string(), cid);
+    let rotation_keys = op.to_operation().unwrap().rotation_keys;
+    assert_eq!(rotation_keys, vec![key.did_key()]);
+    assert_eq!(op.find_signer(&rotation_keys).unwrap(), 0);
+    // RFC 6979 signatures are deterministic
+    let mut resigned = op.clone();
+    resigned.sign(&key).unwrap();
+    assert_eq!(resigned.sig(), op.sig());
+  }
+
+  #[test]
+  fn k256_genesis_vector() {
+    check_vector(
+      KeyAlgorithm::Secp256k1,
+      K256_KEY,
+      K256_GENESIS,
+      K256_DID,
+      K256_CID,
+    );
+  }
+
+  #[test]
+  fn p256_genesis_vector() {
+    check_vector(
+      KeyAlgorithm::P256,
+      P256_KEY,
+      P256_GENESIS,
+      P256_DID,
+      P256_CID,
+    );
+  }
+
+  #[test]
+  fn sign_and_verify() {
+    for algorithm in [KeyAlgorithm::Secp256k1, KeyAlgorithm::P256] {
+      let recovery = Keypair::generate(algorithm);
+      let key = Keypair::generate(algorithm);
+      let rotation_keys = vec![recovery.did_key(), key.did_key()];
+      let (_, op) = PlcOpBuilder::new()
+        .rotation_keys(rotation_keys.clone())
+        .handle("alice.test")
+        .genesis(&key)
+        .unwrap();
+      assert_eq!(op.find_signer(&rotation_keys).unwrap(), 1);
+      assert!(op.find_signer(&rotation_keys[..1]).is_err());
+      let mut tampered = op.clone();
+      if let PlcOp::PlcOperation(o) = &mut tampered {
+        o.also_known_as = vec![String::from("at://mallory.test")];
+      }
+      assert!(tampered.find_signer(&rotation_keys).is_err());
+      let tombstone = PlcOp::tombstone(op.cid().unwrap(), &recovery).unwrap();
+      assert_eq!(tombstone.find_signer(&rotation_keys).unwrap(), 0);
+    }
+  }
+
+  fn entry(did: &str, op: PlcOp, created_at: DateTime<Utc>, nullified: bool) -> LogEntry {
+    LogEntry {
+      did: did.to_string(),
+      cid: CidString::from(op.cid().unwrap()),
+      operation: op,
+      nullified,
+      created_at,
+    }
+  }
+
+  /// Genesis and an update signed by the second rotation key, and a fork from genesis signed by the key
+  fn fork(signer: usize, delay: Duration) -> (String, Vec<LogEntry>) {
+    let keys = [
+      Keypair::generate(KeyAlgorithm::Secp256k1),
+      Keypair::generate(KeyAlgorithm::Secp256k1),
+    ];
+    let builder = PlcOpBuilder::new()
+      .rotation_keys(keys.iter().map(|k| k.did_key()).collect())
+      .handle("alice.test");
+    let (did, genesis) = builder.clone().genesis(&keys[1]).unwrap();
+    let prev = genesis.cid().unwrap().to_string();
+    let update = builder
+      .clone()
+      .handle("bob.test")
+      .update(&prev, &keys[1])
+      .unwrap();
+    let recovery = builder
+      .handle("carol.test")
+      .update(&prev, &keys[signer])
+      .unwrap();
+    let t0 = DateTime::from_timestamp(1700000000, 0).unwrap();
+    let log = vec![
+      entry(&did, genesis, t0, false),
+      entry(&did, update, t0 + Duration::hours(1), true),
+      entry(&did, recovery, t0 + Duration::hours(1) + delay, false),
+    ];
+    (did, log)
+  }
+
+  #[test]
+  fn nullify_within_recovery_window() {
+    let (did, log) = fork(0, Duration::hours(PLC_RECOVERY_WINDOW_HOURS));
+    let doc = validate_audit_log(&did, &log).unwrap().unwrap();
+    assert_eq!(doc.handles(), vec![String::from("carol.test")]);
+    let (_, nullified) = replay_audit_log(&did, &log).unwrap();
+    assert_eq!(nullified, vec![log[1].cid.to_string()]);
+    let mut wrong = log.clone();
+    wrong[1].nullified = false;
+    assert!(validate_audit_log(&did, &wrong).is_err());
+  }
+
+  #[test]
+  fn reject_nullification_after_recovery_window() {
+    let delay = Duration::hours(PLC_RECOVERY_WINDOW_HOURS) + Duration::seconds(1);
+    let (did, log) = fork(0, delay);
+    let e = validate_audit_log(&did, &log).unwrap_err();
+    assert!(e.to_string().contains("recovery window"));
+  }
+
+  #[test]
+  fn reject_nullification_without_priority() {
+    let (did, log) = fork(1, Duration::hours(1));
+    let e = validate_audit_log(&did, &log).unwrap_err();
+    assert!(e.to_string().contains("no priority"));
+  }
+
+  #[test]
+  fn reject_nullification_by_lower_priority_key() {
+    let keys = [
+      Keypair::generate(KeyAlgorithm::Secp256k1),
+      Keypair::generate(KeyAlgorithm::Secp256k1),
+    ];
+    let builder = PlcOpBuilder::new().rotation_keys(keys.iter().map(|k| k.did_key()).collect());
+    let (did, genesis) = builder.clone().genesis(&keys[0]).unwrap();
+    let prev = genesis.cid().unwrap().to_string();
+    let update = builder
+      .clone()
+      .handle("bob.test")
+      .update(&prev, &keys[0])
+      .unwrap();
+    let fork = builder
+      .handle("carol.test")
+      .update(&prev, &keys[1])
+      .unwrap();
+    let t0 = DateTime::from_timestamp(1700000000, 0).unwrap();
+    let log = vec![
+      entry(&did, genesis, t0, false),
+      entry(&did, update, t0 + Duration::hours(1), true),
+      entry(&did, fork, t0 + Duration::hours(2), false),
+    ];
+    let e = validate_audit_log(&did, &log).unwrap_err();
+    assert!(e.to_string().contains("no priority"));
+  }
 }
@@ -0,0 +1,319 @@
+//! In-process PLC directory to test identity code without network
+use std::{
+  collections::HashMap,
+  str::FromStr,
+  sync::{Arc, Mutex},
+  thread::{spawn, JoinHandle},
+};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+use serde::Serialize;
+use serde_json::json;
+use tiny_http::{Header, Method, Request, Response, Server};
+use url::Url;
+
+use crate::{
+  api::CidString,
+  plc::{replay_audit_log, LogEntry, PlcOp},
+  Plc,
+};
+
+#[derive(Default)]
+struct Directory {
+  logs: HashMap<String, Vec<LogEntry>>,
+  export: Vec<(String, usize)>,
+}
+
+impl Directory {
+  fn submit(&mut self, did: &str, op: PlcOp) -> Result<()> {
+    let mut log = self.logs.get(did).cloned().unwrap_or_default();
+    let mut created_at = Utc::now().trunc_subsecs(3);
+    if let Some(last) = log.last() {
+      created_at = created_at.max(last.created_at + Duration::milliseconds(1));
+    }
+    log.push(LogEntry {
+      did: did.to_string(),
+      cid: CidString::from(op.cid()?),
+      operation: op,
+      nullified: false,
+      created_at,
+    });
+    let (_, nullified) = replay_audit_log(did, &log)?;
+    for entry in log.iter_mut() {
+      entry.nullified = nullified.contains(&entry.cid.to_string());
+    }
+    self.export.push((did.to_string(), log.len() - 1));
+    self.logs.insert(did.to_string(), log);
+    Ok(())
+  }
+
+  fn get_log(&self, did: &str) -> Result<&Vec<LogEntry>> {
+    self
+      .logs
+      .get(did)
+      .ok_or_else(|| anyhow!("DID not registered: {}", did))
+  }
+
+  fn get_last(&self, did: &str) -> Result<&LogEntry> {
+    self
+      .get_log(did)?
+      .iter()
+      .rev()
+      .find(|e| !e.nullified)
+      .ok_or_else(|| anyhow!("DID not registered: {}", did))
+  }
+
+  fn export(&self, count: usize, after: Option<DateTime<Utc>>) -> Vec<&LogEntry> {
+    self
+      .export
+      .iter()
+      .filter_map(|(did, index)| self.logs.get(did).and_then(|l| l.get(*index)))
+      .filter(|e| after.map(|a| e.created_at > a).unwrap_or(true))
+      .take(count)
+      .collect()
+  }
+}
+
+fn json_response<T: Serialize>(value: &T) -> Response<std::io::Cursor<Vec<u8>>> {
+  let header = Header::from_str("Content-Type: application/json").ok();
+  match serde_json::to_string(value) {
+    Ok(s) => {
+      let response = Response::from_string(s);
+      match header {
+        Some(h) => response.with_header(h),
+        None => response,
+      }
+    }
+    Err(e) => error_response(500, e),
+  }
+}
+
+fn error_response<T: ToString>(status: u16, message: T) -> Response<std::io::Cursor<Vec<u8>>> {
+  Response::from_string(json!({ "message": message.to_string() }).to_string())
+    .with_status_code(status)
+}
+
+fn handle(
+  directory: &Mutex<Directory>,
+  request: &mut Request,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+  let url = match Url::parse("http://localhost").and_then(|u| u.join(request.url())) {
+    Ok(u) => u,
+    Err(e) => return error_response(400, e),
+  };
+  let paths = url
+    .path()
+    .split('/')
+    .filter(|p| !p.is_empty())
+    .collect::<Vec<_>>();
+  let mut directory = match directory.lock() {
+    Ok(d) => d,
+    Err(e) => return error_response(500, e),
+  };
+  match (request.method(), paths.as_slice()) {
+    (Method::Post, [did]) => {
+      let mut body = String::new();
+      if let Err(e) = request.as_reader().read_to_string(&mut body) {
+        return error_response(400, e);
+      }
+      match serde_json::from_str::<PlcOp>(&body)
+        .map_err(anyhow::Error::from)
+        .and_then(|op| directory.submit(did, op))
+      {
+        Ok(_) => Response::from_string(""),
+        Err(e) => error_response(400, e),
+      }
+    }
+    (Method::Get, ["export"]) => {
+      let queries = url.query_pairs().collect::<HashMap<_, _>>();
+      let count = queries
+        .get("count")
+        .and_then(|c| c.parse::<usize>().ok())
+        .unwrap_or(10)
+        .min(1000);
+      let after = queries
+        .get("after")
+        .and_then(|a| DateTime::parse_from_rfc3339(a).ok())
+        .map(|a| a.with_timezone(&Utc));
+      let lines = directory
+        .export(count, after)
+        .into_iter()
+        .filter_map(|e| serde_json::to_string(e).ok())
+        .collect::<Vec<_>>();
+      Response::from_string(lines.join("\n"))
+    }
+    (Method::Get, [did]) => match directory.get_last(did) {
+      Ok(last) => match last.operation.to_operation() {
+        Some(op) => match op.to_did_doc(did) {
+          Ok(doc) => json_response(&doc),
+          Err(e) => error_response(500, e),
+        },
+        None => error_response(410, format!("DID not available: {}", did)),
+      },
+      Err(e) => error_response(404, e),
+    },
+    (Method::Get, [did, "data"]) => match directory.get_last(did) {
+      Ok(last) => match last.operation.to_operation() {
+        Some(op) => json_response(&json!({
+          "did": did,
+          "verificationMethods": op.verification_methods,
+          "rotationKeys": op.rotation_keys,
+          "alsoKnownAs": op.also_known_as,
+          "services": op.services,
+        })),
+        None => error_response(410, format!("DID not available: {}", did)),
+      },
+      Err(e) => error_response(404, e),
+    },
+    (Method::Get, [did, "log"]) => match directory.get_log(did) {
+      Ok(log) => json_response(
+        &log
+          .iter()
+          .filter(|e| !e.nullified)
+          .map(|e| &e.operation)
+          .collect::<Vec<_>>(),
+      ),
+      Err(e) => error_response(404, e),
+    },
+    (Method::Get, [did, "log", "audit"]) => match directory.get_log(did) {
+      Ok(log) => json_response(log),
+      Err(e) => error_response(404, e),
+    },
+    (Method::Get, [did, "log", "last"]) => match directory.get_last(did) {
+      Ok(last) => json_response(last),
+      Err(e) => error_response(404, e),
+    },
+    _ => error_response(404, "not found"),
+  }
+}
+
+/// PLC directory served in a background thread
+pub struct PlcServer {
+  server: Arc<Server>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl PlcServer {
+  /// Start the directory listening on the address like "127.0.0.1:0"
+  pub fn start(addr: &str) -> Result<Self> {
+    let server = Arc::new(match Server::http(addr) {
+      Ok(s) => s,
+      Err(e) => bail!("{}", e),
+    });
+    let directory = Arc::new(Mutex::new(Directory::default()));
+    let thread = {
+      let server = Arc::clone(&server);
+      spawn(move || {
+        for mut request in server.incoming_requests() {
+          let response = handle(&directory, &mut request);
+          if let Err(e) = request.respond(response) {
+            log::warn!("response send error {}", e);
+          }
+        }
+        log::info!("PLC server thread terminate");
+      })
+    };
+    Ok(Self {
+      server,
+      thread: Some(thread),
+    })
+  }
+
+  /// Returns host and port of the directory
+  pub fn get_host(&self) -> String {
+    self.server.server_addr().to_string()
+  }
+
+  /// Create client of the directory
+  pub fn plc(&self) -> Plc {
+    Plc::new(self.get_host(), "http")
+  }
+}
+
+impl Drop for PlcServer {
+  fn drop(&mut self) {
+    self.server.unblock();
+    if let Some(thread) = self.thread.take() {
+      thread.join().ok();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{xrpc::XrpcError, KeyAlgorithm, Keypair, PlcOpBuilder};
+
+  #[test]
+  fn serve_operations() {
+    let server = PlcServer::start("127.0.0.1:0").unwrap();
+    let plc = server.plc();
+    let key = Keypair::generate(KeyAlgorithm::Secp256k1);
+    let builder = PlcOpBuilder::new()
+      .rotation_key(key.did_key())
+      .atproto_signing_key(key.did_key())
+      .handle("alice.test")
+      .atproto_pds("https://pds.test");
+    let (did, genesis) = builder.clone().genesis(&key).unwrap();
+    plc.create_plc_op(&did, &genesis).unwrap();
+    let update = builder
+      .clone()
+      .handle("bob.test")
+      .update(genesis.cid().unwrap(), &key)
+      .unwrap();
+    plc.create_plc_op(&did, &update).unwrap();
+    let doc = plc.resolve_did(&did).unwrap();
+    assert_eq!(doc.handles(), vec![String::from("bob.test")]);
+
+    // signed by a key which is not a rotation key
+    let invalid = builder
+      .handle("mallory.test")
+      .update(
+        update.cid().unwrap(),
+        &Keypair::generate(KeyAlgorithm::Secp256k1),
+      )
+      .unwrap();
+    let e = plc.create_plc_op(&did, &invalid).unwrap_err();
+    assert_eq!(
+      e.downcast_ref::<XrpcError>().and_then(|e| e.status()),
+      Some(400)
+    );
+
+    let tombstone = PlcOp::tombstone(update.cid().unwrap(), &key).unwrap();
+    plc.create_plc_op(&did, &tombstone).unwrap();
+    let e = plc.resolve_did(&did).unwrap_err();
+    assert_eq!(
+      e.downcast_ref::<XrpcError>().and_then(|e| e.status()),
+      Some(410)
+    );
+
+    let audit = plc.get_plc_audit_log(&did).unwrap();
+    let cids = audit.iter().map(|e| e.cid.to_string()).collect::<Vec<_>>();
+    let expected = [&genesis, &update, &tombstone]
+      .iter()
+      .map(|op| op.cid().unwrap().to_string())
+      .collect::<Vec<_>>();
+    assert_eq!(cids, expected);
+    assert!(audit.iter().all(|e| !e.nullified));
+    assert_eq!(plc.audit(&did).unwrap().map(|d| d.id), None);
+
+    let last = plc.get_last_op(&did).unwrap();
+    assert_eq!(last.cid.to_string(), expected[2]);
+    assert!(matches!(last.operation, PlcOp::PlcTombstone(_)));
+
+    let export = plc.export(None, None).unwrap();
+    assert_eq!(
+      export.iter().map(|e| e.cid.to_string()).collect::<Vec<_>>(),
+      expected
+    );
+    let after = plc.export(Some(10), Some(export[0].created_at)).unwrap();
+    assert_eq!(after.len(), 2);
+
+    let e = plc.get_last_op("did:plc:unknown").unwrap_err();
+    assert_eq!(
+      e.downcast_ref::<XrpcError>().and_then(|e| e.status()),
+      Some(404)
+    );
+  }
+}
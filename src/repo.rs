@@ -0,0 +1,519 @@
+//! Local repository with signed commits
+use std::{
+  collections::{BTreeMap, HashMap},
+  fmt::Display,
+  str::FromStr,
+  sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use libipld::{cbor::DagCborCodec, multihash::Multihash, prelude::Codec, Cid, Ipld};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::{
+  api::{Blocks, Node, DAG_CBOR_CODEC, SHA2_256},
+  crypto::Keypair,
+};
+
+const TID_CHARS: &[u8] = b"234567abcdefghijklmnopqrstuvwxyz";
+
+static LAST_TID_MICROS: AtomicU64 = AtomicU64::new(0);
+
+/// Timestamp identifier used as record key and repository revision
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Tid(String);
+
+impl Display for Tid {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+impl Tid {
+  /// Create TID from microseconds since UNIX epoch and 10 bits clock identifier
+  pub fn from_timestamp(micros: u64, clock_id: u16) -> Self {
+    let mut value = ((micros & 0x1f_ffff_ffff_ffff) << 10) | (clock_id as u64 & 0x3ff);
+    let mut chars = [0u8; 13];
+    for c in chars.iter_mut().rev() {
+      *c = TID_CHARS[(value & 0x1f) as usize];
+      value >>= 5;
+    }
+    Self(chars.iter().map(|c| *c as char).collect())
+  }
+
+  /// Create TID of the current time, never smaller than the previous one
+  pub fn now() -> Self {
+    let now = Utc::now().timestamp_micros().max(0) as u64;
+    let mut last = LAST_TID_MICROS.load(Ordering::SeqCst);
+    let micros = loop {
+      let next = now.max(last + 1);
+      match LAST_TID_MICROS.compare_exchange(last, next, Ordering::SeqCst, Ordering::SeqCst) {
+        Ok(_) => break next,
+        Err(l) => last = l,
+      }
+    };
+    Self::from_timestamp(micros, (OsRng.next_u32() & 0x3ff) as u16)
+  }
+
+  /// Create TID later than the previous one, the current time if it is already later
+  pub fn next(prev: &Tid) -> Self {
+    let now = Self::now();
+    match prev.timestamp() {
+      Some(micros) if now <= *prev => {
+        LAST_TID_MICROS.fetch_max(micros + 1, Ordering::SeqCst);
+        Self::from_timestamp(micros + 1, (OsRng.next_u32() & 0x3ff) as u16)
+      }
+      _ => now,
+    }
+  }
+
+  /// Returns microseconds since UNIX epoch, None if not a valid TID
+  pub fn timestamp(&self) -> Option<u64> {
+    if self.0.len() != 13 {
+      return None;
+    }
+    let mut value = 0u64;
+    for c in self.0.bytes() {
+      value = (value << 5) | TID_CHARS.iter().position(|t| *t == c)? as u64;
+    }
+    Some(value >> 10)
+  }
+
+  /// Returns TID as string
+  pub fn as_str(&self) -> &str {
+    &self.0
+  }
+}
+
+/// Write operation to a repository
+#[derive(Debug, Clone)]
+pub enum RepoWrite {
+  Create {
+    collection: String,
+    rkey: Option<String>,
+    record: Ipld,
+  },
+  Update {
+    collection: String,
+    rkey: String,
+    record: Ipld,
+  },
+  Delete {
+    collection: String,
+    rkey: String,
+  },
+}
+
+fn put_block(blocks: &mut HashMap<Cid, Ipld>, ipld: Ipld) -> Result<Cid> {
+  let bytes = DagCborCodec.encode(&ipld)?;
+  let cid = Cid::new_v1(
+    DAG_CBOR_CODEC,
+    Multihash::wrap(SHA2_256, &Sha256::digest(&bytes))?,
+  );
+  blocks.insert(cid, ipld);
+  Ok(cid)
+}
+
+fn key_height(key: &str) -> u32 {
+  let mut zeros = 0;
+  for b in Sha256::digest(key.as_bytes()) {
+    zeros += b.leading_zeros();
+    if b != 0 {
+      break;
+    }
+  }
+  zeros / 2
+}
+
+fn build_subtree(
+  items: &[(&String, Cid, u32)],
+  layer: u32,
+  blocks: &mut HashMap<Cid, Ipld>,
+) -> Result<Option<Cid>> {
+  if items.is_empty() || layer == 0 {
+    return Ok(None);
+  }
+  Ok(Some(build_node(items, layer - 1, blocks)?))
+}
+
+fn build_node(
+  items: &[(&String, Cid, u32)],
+  layer: u32,
+  blocks: &mut HashMap<Cid, Ipld>,
+) -> Result<Cid> {
+  let mut left = None;
+  let mut entries: Vec<(&String, Cid, Option<Cid>)> = Vec::new();
+  let mut start = 0;
+  for (i, (key, cid, height)) in items.iter().enumerate() {
+    if *height != layer {
+      continue;
+    }
+    let subtree = build_subtree(&items[start..i], layer, blocks)?;
+    match entries.last_mut() {
+      Some(last) => last.2 = subtree,
+      None => left = subtree,
+    }
+    entries.push((key, *cid, None));
+    start = i + 1;
+  }
+  let subtree = build_subtree(&items[start..], layer, blocks)?;
+  match entries.last_mut() {
+    Some(last) => last.2 = subtree,
+    None => left = subtree,
+  }
+  let mut prev: &[u8] = &[];
+  let entries = entries
+    .into_iter()
+    .map(|(key, value, tree)| {
+      let key = key.as_bytes();
+      let prefix = prev
+        .iter()
+        .zip(key.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+      prev = key;
+      Ipld::Map(BTreeMap::from([
+        (String::from("p"), Ipld::Integer(prefix as i128)),
+        (String::from("k"), Ipld::Bytes(key[prefix..].to_vec())),
+        (String::from("v"), Ipld::Link(value)),
+        (
+          String::from("t"),
+          tree.map(Ipld::Link).unwrap_or(Ipld::Null),
+        ),
+      ]))
+    })
+    .collect();
+  put_block(
+    blocks,
+    Ipld::Map(BTreeMap::from([
+      (
+        String::from("l"),
+        left.map(Ipld::Link).unwrap_or(Ipld::Null),
+      ),
+      (String::from("e"), Ipld::List(entries)),
+    ])),
+  )
+}
+
+/// Build MST of the keys sorted in order and their values, returns CID of the root node
+fn build_mst(items: &[(&String, Cid)], blocks: &mut HashMap<Cid, Ipld>) -> Result<Cid> {
+  let items = items
+    .iter()
+    .map(|(key, cid)| (*key, *cid, key_height(key)))
+    .collect::<Vec<_>>();
+  let layer = items.iter().map(|(_, _, h)| *h).max().unwrap_or_default();
+  build_node(&items, layer, blocks)
+}
+
+fn walk_node(blocks: &Blocks, cid: &Cid, out: &mut BTreeMap<String, Cid>) -> Result<()> {
+  let ipld = blocks
+    .get(cid)
+    .ok_or_else(|| anyhow!("MST node {} is not found", cid))?;
+  let node = Node::try_from(&ipld)?;
+  if let Some(l) = &node.l {
+    walk_node(blocks, &Cid::from_str(&l.link)?, out)?;
+  }
+  let mut last: Vec<u8> = Vec::new();
+  for entry in node.e.into_iter() {
+    let mut key = last.get(..entry.p as usize).unwrap_or_default().to_vec();
+    key.extend(entry.k);
+    out.insert(
+      String::from_utf8(key.clone())?,
+      Cid::from_str(&entry.v.link)?,
+    );
+    if let Some(t) = &entry.t {
+      walk_node(blocks, &Cid::from_str(&t.link)?, out)?;
+    }
+    last = key;
+  }
+  Ok(())
+}
+
+/// Repository built and signed without a PDS
+pub struct LocalRepo {
+  did: String,
+  keypair: Keypair,
+  records: BTreeMap<String, Ipld>,
+  rev: Option<Tid>,
+  blocks: Blocks,
+}
+
+impl LocalRepo {
+  /// Create empty repository of the DID
+  pub fn new<T: ToString>(did: T, keypair: Keypair) -> Self {
+    Self {
+      did: did.to_string(),
+      keypair,
+      records: BTreeMap::new(),
+      rev: None,
+      blocks: Blocks::default(),
+    }
+  }
+
+  /// Load repository from CAR blocks, the keypair is used for the next commits
+  pub fn load(blocks: Blocks, keypair: Keypair) -> Result<Self> {
+    let commit = blocks
+      .root_commit()
+      .ok_or_else(|| anyhow!("root of the repository is not a commit"))?;
+    let mut keys = BTreeMap::new();
+    walk_node(&blocks, &Cid::from_str(&commit.data.link)?, &mut keys)?;
+    let records = keys
+      .into_iter()
+      .map(|(key, cid)| {
+        blocks
+          .get(&cid)
+          .map(|r| (key.clone(), r))
+          .ok_or_else(|| anyhow!("record {} is not found", key))
+      })
+      .collect::<Result<BTreeMap<_, _>>>()?;
+    Ok(Self {
+      did: commit.did,
+      keypair,
+      records,
+      rev: commit.rev.map(Tid),
+      blocks,
+    })
+  }
+
+  /// Returns DID of the repository
+  pub fn did(&self) -> &str {
+    &self.did
+  }
+
+  /// Returns revision of the last commit
+  pub fn rev(&self) -> Option<&Tid> {
+    self.rev.as_ref()
+  }
+
+  /// Returns a record in the repository
+  pub fn get_record(&self, collection: &str, rkey: &str) -> Option<&Ipld> {
+    self.records.get(&format!("{}/{}", collection, rkey))
+  }
+
+  /// Apply writes to the records and create a signed commit
+  pub fn apply_writes(&mut self, writes: Vec<RepoWrite>) -> Result<Cid> {
+    for write in writes.into_iter() {
+      match write {
+        RepoWrite::Create {
+          collection,
+          rkey,
+          record,
+        } => {
+          let rkey = rkey.unwrap_or_else(|| Tid::now().to_string());
+          let path = format!("{}/{}", collection, rkey);
+          if self.records.contains_key(&path) {
+            bail!("record {} already exists", path);
+          }
+          self.records.insert(path, record);
+        }
+        RepoWrite::Update {
+          collection,
+          rkey,
+          record,
+        } => {
+          let path = format!("{}/{}", collection, rkey);
+          let Some(r) = self.records.get_mut(&path) else {
+            bail!("record {} does not exist", path);
+          };
+          *r = record;
+        }
+        RepoWrite::Delete { collection, rkey } => {
+          let path = format!("{}/{}", collection, rkey);
+          if self.records.remove(&path).is_none() {
+            bail!("record {} does not exist", path);
+          }
+        }
+      }
+    }
+    self.commit()
+  }
+
+  /// Build MST of the current records and create a signed v3 commit
+  pub fn commit(&mut self) -> Result<Cid> {
+    let mut data = HashMap::new();
+    let mut items = Vec::new();
+    for (key, record) in self.records.iter() {
+      items.push((key, put_block(&mut data, record.clone())?));
+    }
+    let root = build_mst(&items, &mut data)?;
+    let rev = match &self.rev {
+      Some(prev) => Tid::next(prev),
+      None => Tid::now(),
+    };
+    let mut commit = BTreeMap::from([
+      (String::from("did"), Ipld::String(self.did.clone())),
+      (String::from("version"), Ipld::Integer(3)),
+      (String::from("data"), Ipld::Link(root)),
+      (String::from("rev"), Ipld::String(rev.to_string())),
+      (String::from("prev"), Ipld::Null),
+    ]);
+    let unsigned = DagCborCodec.encode(&Ipld::Map(commit.clone()))?;
+    commit.insert(
+      String::from("sig"),
+      Ipld::Bytes(self.keypair.sign(&unsigned)),
+    );
+    let commit = put_block(&mut data, Ipld::Map(commit))?;
+    self.blocks = Blocks {
+      header: Ipld::Map(BTreeMap::from([
+        (String::from("version"), Ipld::Integer(1)),
+        (String::from("roots"), Ipld::List(vec![Ipld::Link(commit)])),
+      ])),
+      data,
+    };
+    self.rev = Some(rev);
+    Ok(commit)
+  }
+
+  /// Returns blocks of the last commit
+  pub fn blocks(&self) -> &Blocks {
+    &self.blocks
+  }
+
+  /// Export the last commit as CAR file
+  pub fn to_car(&self) -> Result<Vec<u8>> {
+    if self.rev.is_none() {
+      bail!("repository has no commit");
+    }
+    self.blocks.to_car()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::crypto::KeyAlgorithm;
+
+  // known maps of the MST interop tests in atproto
+  const LEAF: &str = "bafyreie5cvv4h45feadgeuwhbcutmh6t2ceseocckahdoe6uat64zmz454";
+
+  fn mst_root(keys: &[&str]) -> String {
+    let keys = keys.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+    let leaf = Cid::from_str(LEAF).unwrap();
+    let items = keys.iter().map(|k| (k, leaf)).collect::<Vec<_>>();
+    build_mst(&items, &mut HashMap::new()).unwrap().to_string()
+  }
+
+  #[test]
+  fn mst_key_height() {
+    assert_eq!(key_height(""), 0);
+    assert_eq!(key_height("asdf"), 0);
+    assert_eq!(key_height("blue"), 1);
+    assert_eq!(key_height("2653ae71"), 0);
+    assert_eq!(key_height("88bfafc7"), 2);
+    assert_eq!(key_height("2a92d355"), 4);
+    assert_eq!(key_height("884976f5"), 6);
+    assert_eq!(key_height("app.bsky.feed.post/454397e440ec"), 4);
+    assert_eq!(key_height("app.bsky.feed.post/9adeb165882c"), 8);
+  }
+
+  #[test]
+  fn mst_known_roots() {
+    assert_eq!(
+      mst_root(&[]),
+      "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm"
+    );
+    assert_eq!(
+      mst_root(&["com.example.record/3jqfcqzm3fo2j"]),
+      "bafyreibj4lsc3aqnrvphp5xmrnfoorvru4wynt6lwidqbm2623a6tatzdu"
+    );
+    assert_eq!(
+      mst_root(&["com.example.record/3jqfcqzm3fx2j"]),
+      "bafyreih7wfei65pxzhauoibu3ls7jgmkju4bspy4t2ha2qdjnzqvoy33ai"
+    );
+    assert_eq!(
+      mst_root(&[
+        "com.example.record/3jqfcqzm3fp2j",
+        "com.example.record/3jqfcqzm3fr2j",
+        "com.example.record/3jqfcqzm3fs2j",
+        "com.example.record/3jqfcqzm3ft2j",
+        "com.example.record/3jqfcqzm4fc2j",
+      ]),
+      "bafyreicmahysq4n6wfuxo522m6dpiy7z7qzym3dzs756t5n7nfdgccwq7m"
+    );
+  }
+
+  fn post(text: &str) -> Ipld {
+    Ipld::Map(BTreeMap::from([
+      (
+        String::from("$type"),
+        Ipld::String(String::from("app.bsky.feed.post")),
+      ),
+      (String::from("text"), Ipld::String(text.to_string())),
+    ]))
+  }
+
+  fn repo(algorithm: KeyAlgorithm) -> LocalRepo {
+    let mut repo = LocalRepo::new("did:plc:alice", Keypair::generate(algorithm));
+    let writes = (0..20)
+      .map(|i| RepoWrite::Create {
+        collection: String::from("app.bsky.feed.post"),
+        rkey: Some(Tid::from_timestamp(1_700_000_000_000_000 + i * 1_000_000, 0).to_string()),
+        record: post(&format!("post {}", i)),
+      })
+      .collect();
+    repo.apply_writes(writes).unwrap();
+    repo
+  }
+
+  #[test]
+  fn commit_signature_verifies() {
+    for algorithm in [KeyAlgorithm::Secp256k1, KeyAlgorithm::P256] {
+      let repo = repo(algorithm);
+      let root = repo.blocks().root().unwrap();
+      let Some(Ipld::Map(mut commit)) = repo.blocks().get(&root) else {
+        panic!("commit is not a map");
+      };
+      let Some(Ipld::Bytes(sig)) = commit.remove("sig") else {
+        panic!("commit has no signature");
+      };
+      let unsigned = DagCborCodec.encode(&Ipld::Map(commit)).unwrap();
+      let public_key = repo.keypair.public_key();
+      public_key.verify(&unsigned, &sig).unwrap();
+      assert!(public_key.verify(b"other data", &sig).is_err());
+    }
+  }
+
+  #[test]
+  fn load_round_trip() {
+    let mut repo = repo(KeyAlgorithm::Secp256k1);
+    repo
+      .apply_writes(vec![RepoWrite::Delete {
+        collection: String::from("app.bsky.feed.post"),
+        rkey: Tid::from_timestamp(1_700_000_000_000_000, 0).to_string(),
+      }])
+      .unwrap();
+    let blocks = Blocks::from_car(&repo.to_car().unwrap(), true).unwrap();
+    let mut loaded = LocalRepo::load(blocks, repo.keypair.clone()).unwrap();
+    assert_eq!(loaded.did(), "did:plc:alice");
+    assert_eq!(loaded.rev(), repo.rev());
+    assert_eq!(loaded.records, repo.records);
+    assert_eq!(loaded.records.len(), 19);
+    // the same records build the same MST
+    loaded.commit().unwrap();
+    assert!(loaded.rev() > repo.rev());
+    assert_eq!(
+      loaded.blocks().root_commit().unwrap().data.link,
+      repo.blocks().root_commit().unwrap().data.link
+    );
+  }
+
+  #[test]
+  fn tid_timestamp() {
+    let tid = Tid::from_timestamp(1_700_000_000_000_000, 5);
+    assert_eq!(tid.timestamp(), Some(1_700_000_000_000_000));
+    assert_eq!(Tid(String::from("not a tid")).timestamp(), None);
+  }
+
+  #[test]
+  fn next_tid_after_future_revision() {
+    let future = Tid::from_timestamp(Utc::now().timestamp_micros() as u64 + 3_600_000_000, 0);
+    let next = Tid::next(&future);
+    assert!(next > future);
+    assert_eq!(next.timestamp(), future.timestamp().map(|t| t + 1));
+    assert!(Tid::now() > next);
+    let past = Tid::from_timestamp(1_000_000, 0);
+    assert!(Tid::next(&past) > past);
+  }
+}
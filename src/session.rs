@@ -0,0 +1,106 @@
+//! Login session saved between runs
+use std::{
+  fs::{remove_file, File, OpenOptions},
+  path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Tokens and account of a logged in session
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+  pub did: String,
+  pub handle: String,
+  pub host: String,
+  pub access_jwt: String,
+  pub refresh_jwt: String,
+}
+
+/// Storage to save the session after login and token refresh
+pub trait SessionStore: Send + Sync {
+  fn load(&self) -> Result<Option<Session>>;
+  fn save(&self, session: &Session) -> Result<()>;
+  fn clear(&self) -> Result<()>;
+}
+
+/// Session saved in a JSON file
+#[derive(Debug, Clone)]
+pub struct FileSessionStore {
+  path: PathBuf,
+}
+
+impl FileSessionStore {
+  /// Create store saving the session to the file
+  pub fn new<P: AsRef<Path>>(path: P) -> Self {
+    Self {
+      path: path.as_ref().to_path_buf(),
+    }
+  }
+}
+
+impl SessionStore for FileSessionStore {
+  fn load(&self) -> Result<Option<Session>> {
+    match File::open(&self.path) {
+      Ok(file) => Ok(Some(serde_json::from_reader(file)?)),
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(e.into()),
+    }
+  }
+
+  fn save(&self, session: &Session) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // tokens are readable only by the owner from the creation
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::OpenOptionsExt;
+      options.mode(0o600);
+    }
+    let file = options.open(&self.path)?;
+    // the mode is applied only to new files
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    serde_json::to_writer_pretty(file, session)?;
+    Ok(())
+  }
+
+  fn clear(&self) -> Result<()> {
+    match remove_file(&self.path) {
+      Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+      _ => Ok(()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn file_store_round_trip() {
+    let path = std::env::temp_dir().join(format!("aerostream-session-{}.json", std::process::id()));
+    let store = FileSessionStore::new(&path);
+    let session = Session {
+      did: String::from("did:plc:alice"),
+      handle: String::from("alice.test"),
+      host: String::from("pds.test"),
+      access_jwt: String::from("access"),
+      refresh_jwt: String::from("refresh"),
+    };
+    store.save(&session).unwrap();
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+      assert_eq!(mode & 0o777, 0o600);
+    }
+    assert_eq!(store.load().unwrap(), Some(session));
+    store.clear().unwrap();
+    assert_eq!(store.load().unwrap(), None);
+  }
+}
@@ -0,0 +1,295 @@
+//! HTTP transport used by the API and PLC clients
+use std::{
+  collections::{HashMap, VecDeque},
+  io::{Cursor, Read},
+  sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use ureq::{Agent, Proxy};
+use url::Url;
+
+use crate::xrpc::XrpcError;
+
+/// HTTP request passed to the transport
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+  pub method: String,
+  pub url: String,
+  pub headers: Vec<(String, String)>,
+  pub body: Option<Vec<u8>>,
+}
+
+impl HttpRequest {
+  pub fn new<T1: ToString, T2: ToString>(method: T1, url: T2) -> Self {
+    Self {
+      method: method.to_string(),
+      url: url.to_string(),
+      headers: Vec::new(),
+      body: None,
+    }
+  }
+
+  pub fn get<T: ToString>(url: T) -> Self {
+    Self::new("GET", url)
+  }
+
+  pub fn post<T: ToString>(url: T) -> Self {
+    Self::new("POST", url)
+  }
+
+  pub fn method(&self) -> &str {
+    &self.method
+  }
+
+  /// Returns URL including query
+  pub fn url(&self) -> &str {
+    &self.url
+  }
+
+  /// Returns value of the header, names are case insensitive
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self
+      .headers
+      .iter()
+      .find(|(n, _)| n.eq_ignore_ascii_case(name))
+      .map(|(_, v)| v.as_str())
+  }
+
+  /// Set the header replacing the previous value
+  pub fn set(mut self, name: &str, value: &str) -> Self {
+    self.headers.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+    self.headers.push((name.to_string(), value.to_string()));
+    self
+  }
+
+  /// Append the query parameter
+  pub fn query(self, param: &str, value: &str) -> Self {
+    self.query_pairs([(param, value)])
+  }
+
+  /// Append the query parameters
+  pub fn query_pairs<'a, P: IntoIterator<Item = (&'a str, &'a str)>>(mut self, pairs: P) -> Self {
+    if let Ok(mut url) = Url::parse(&self.url) {
+      url.query_pairs_mut().extend_pairs(pairs);
+      if url.query() == Some("") {
+        url.set_query(None);
+      }
+      self.url = url.to_string();
+    }
+    self
+  }
+
+  /// Set the body, Content-Type is kept if already set
+  pub fn bytes(mut self, body: Vec<u8>) -> Self {
+    self.body = Some(body);
+    self
+  }
+
+  /// Set URL encoded form body and its Content-Type
+  pub fn form<'a, P: IntoIterator<Item = &'a (&'a str, &'a str)>>(self, pairs: P) -> Self {
+    let body = url::form_urlencoded::Serializer::new(String::new())
+      .extend_pairs(pairs)
+      .finish();
+    self
+      .set("Content-Type", "application/x-www-form-urlencoded")
+      .bytes(body.into_bytes())
+  }
+
+  /// Set JSON body and its Content-Type
+  pub fn json<T: Serialize>(self, data: &T) -> Result<Self> {
+    let req = match self.header("Content-Type") {
+      Some(_) => self,
+      None => self.set("Content-Type", "application/json"),
+    };
+    Ok(req.bytes(serde_json::to_vec(data)?))
+  }
+}
+
+/// HTTP response returned by the transport, error statuses included
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+  pub url: String,
+  pub status: u16,
+  pub headers: Vec<(String, String)>,
+  pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+  pub fn new<T: ToString>(url: T, status: u16, body: Vec<u8>) -> Self {
+    Self {
+      url: url.to_string(),
+      status,
+      headers: Vec::new(),
+      body,
+    }
+  }
+
+  /// Add the header
+  pub fn with_header(mut self, name: &str, value: &str) -> Self {
+    self.headers.push((name.to_string(), value.to_string()));
+    self
+  }
+
+  pub fn status(&self) -> u16 {
+    self.status
+  }
+
+  /// Returns URL of the request
+  pub fn get_url(&self) -> &str {
+    &self.url
+  }
+
+  /// Returns value of the header, names are case insensitive
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self
+      .headers
+      .iter()
+      .find(|(n, _)| n.eq_ignore_ascii_case(name))
+      .map(|(_, v)| v.as_str())
+  }
+
+  /// Returns XrpcError if the status is 400 or above
+  pub fn error_for_status(self) -> Result<Self> {
+    match self.status >= 400 {
+      true => Err(XrpcError::from_response(self).into()),
+      false => Ok(self),
+    }
+  }
+
+  pub fn into_json<T: DeserializeOwned>(self) -> Result<T> {
+    Ok(serde_json::from_slice(&self.body)?)
+  }
+
+  pub fn into_string(self) -> Result<String> {
+    Ok(String::from_utf8(self.body)?)
+  }
+
+  pub fn into_reader(self) -> Cursor<Vec<u8>> {
+    Cursor::new(self.body)
+  }
+}
+
+/// Backend to send HTTP requests
+pub trait HttpTransport: Send + Sync {
+  /// Send the request, returns the response even if its status is an error
+  fn send(&self, req: HttpRequest) -> Result<HttpResponse>;
+}
+
+/// Agent using HTTPS_PROXY if set
+pub(crate) fn default_agent() -> Agent {
+  match std::env::var("HTTPS_PROXY")
+    .ok()
+    .or_else(|| std::env::var("https_proxy").ok())
+    .and_then(|p| Proxy::new(p).ok())
+  {
+    Some(proxy) => ureq::builder().proxy(proxy).build(),
+    None => ureq::agent(),
+  }
+}
+
+impl<T: HttpTransport + ?Sized> HttpTransport for Arc<T> {
+  fn send(&self, req: HttpRequest) -> Result<HttpResponse> {
+    self.as_ref().send(req)
+  }
+}
+
+impl HttpTransport for Agent {
+  fn send(&self, req: HttpRequest) -> Result<HttpResponse> {
+    let mut request = self.request(&req.method, &req.url);
+    for (name, value) in req.headers.iter() {
+      request = request.set(name, value);
+    }
+    let res = match req.body {
+      Some(body) => request.send_bytes(&body),
+      None => request.call(),
+    };
+    let res = match res {
+      Ok(res) => res,
+      Err(ureq::Error::Status(_, res)) => res,
+      Err(e) => return Err(e.into()),
+    };
+    let mut response = HttpResponse::new(res.get_url(), res.status(), Vec::new());
+    for name in res.headers_names() {
+      if let Some(value) = res.header(&name) {
+        response = response.with_header(&name, value);
+      }
+    }
+    res.into_reader().read_to_end(&mut response.body)?;
+    Ok(response)
+  }
+}
+
+/// In-memory transport for tests, returning queued responses and recording requests
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+  responses: Arc<Mutex<HashMap<String, VecDeque<HttpResponse>>>>,
+  requests: Arc<Mutex<Vec<HttpRequest>>>,
+}
+
+impl MockTransport {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Queue the response to the path like "/xrpc/app.bsky.actor.getProfile", the last one is repeated
+  pub fn push_response(&self, path: &str, res: HttpResponse) {
+    if let Ok(mut responses) = self.responses.lock() {
+      responses
+        .entry(path.to_string())
+        .or_default()
+        .push_back(res);
+    }
+  }
+
+  /// Queue JSON response to the path
+  pub fn push_json(&self, path: &str, status: u16, value: Value) {
+    let res = HttpResponse::new(path, status, value.to_string().into_bytes())
+      .with_header("Content-Type", "application/json");
+    self.push_response(path, res);
+  }
+
+  /// Returns requests sent so far
+  pub fn get_requests(&self) -> Vec<HttpRequest> {
+    self.requests.lock().map(|r| r.clone()).unwrap_or_default()
+  }
+
+  /// Remove recorded requests and queued responses
+  pub fn clear(&self) {
+    if let Ok(mut requests) = self.requests.lock() {
+      requests.clear();
+    }
+    if let Ok(mut responses) = self.responses.lock() {
+      responses.clear();
+    }
+  }
+}
+
+impl HttpTransport for MockTransport {
+  fn send(&self, req: HttpRequest) -> Result<HttpResponse> {
+    let path = Url::parse(&req.url)?.path().to_string();
+    let url = req.url.clone();
+    if let Ok(mut requests) = self.requests.lock() {
+      requests.push(req);
+    }
+    let res = self.responses.lock().ok().and_then(|mut r| {
+      let queue = r.get_mut(&path)?;
+      match queue.len() {
+        1 => queue.front().cloned(),
+        _ => queue.pop_front(),
+      }
+    });
+    Ok(match res {
+      Some(res) => HttpResponse { url, ..res },
+      None => HttpResponse::new(
+        url,
+        404,
+        json!({ "error": "MethodNotImplemented", "message": format!("no response of {}", path) })
+          .to_string()
+          .into_bytes(),
+      ),
+    })
+  }
+}
@@ -0,0 +1,362 @@
+//! Typed errors of XRPC calls
+use std::{
+  collections::HashMap,
+  fmt::Display,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use rand_core::{OsRng, RngCore};
+use serde::Deserialize;
+use url::Url;
+
+pub use crate::api::known_errors;
+use crate::transport::HttpResponse;
+
+macro_rules! error_names {
+  ($($name:ident),* $(,)?) => {
+    /// Error name in the body of failed XRPC responses
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub enum XrpcErrorName {
+      $($name,)*
+      Other(String),
+    }
+
+    impl XrpcErrorName {
+      /// Returns typed error name, unknown names are kept as Other
+      pub fn from_name(name: &str) -> Self {
+        match name {
+          $(stringify!($name) => Self::$name,)*
+          _ => Self::Other(name.to_string()),
+        }
+      }
+
+      pub fn as_str(&self) -> &str {
+        match self {
+          $(Self::$name => stringify!($name),)*
+          Self::Other(name) => name,
+        }
+      }
+    }
+  };
+}
+
+error_names!(
+  InvalidRequest,
+  ExpiredToken,
+  InvalidToken,
+  AuthenticationRequired,
+  AuthMissing,
+  Forbidden,
+  NotFound,
+  PayloadTooLarge,
+  RateLimitExceeded,
+  InternalServerError,
+  MethodNotImplemented,
+  UpstreamFailure,
+  NotEnoughResources,
+  UpstreamTimeout,
+  AccountTakedown,
+  AuthFactorTokenRequired,
+  InvalidSwap,
+  RecordNotFound,
+  RepoNotFound,
+  RepoTakendown,
+  RepoSuspended,
+  RepoDeactivated,
+  BlobNotFound,
+  BlockNotFound,
+  HeadNotFound,
+  HandleNotFound,
+  UnknownFeed,
+  UnknownList,
+  BlockedActor,
+  BlockedByActor,
+  ActorNotFound,
+  BadQueryString,
+  InvalidHandle,
+  InvalidPassword,
+  InvalidInviteCode,
+  HandleNotAvailable,
+  UnsupportedDomain,
+  UnresolvableDid,
+  IncompatibleDidDoc,
+  AccountNotFound,
+  InvalidEmail,
+  TokenRequired,
+  BadExpiration,
+);
+
+impl Display for XrpcErrorName {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+/// Rate limit state given by ratelimit-* response headers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimit {
+  pub limit: Option<u64>,
+  pub remaining: Option<u64>,
+  pub reset: Option<DateTime<Utc>>,
+  pub policy: Option<String>,
+}
+
+impl RateLimit {
+  /// Parse the headers, None if the response has no rate limit
+  pub fn from_response(res: &HttpResponse) -> Option<Self> {
+    let number = |name: &str| res.header(name).and_then(|v| v.trim().parse::<u64>().ok());
+    let rate_limit = Self {
+      limit: number("ratelimit-limit"),
+      remaining: number("ratelimit-remaining"),
+      reset: number("ratelimit-reset")
+        .and_then(|r| i64::try_from(r).ok())
+        .and_then(|r| DateTime::from_timestamp(r, 0)),
+      policy: res.header("ratelimit-policy").map(String::from),
+    };
+    match rate_limit == Self::default() {
+      true => None,
+      false => Some(rate_limit),
+    }
+  }
+}
+
+fn host_of(url: &str) -> String {
+  Url::parse(url)
+    .ok()
+    .and_then(|u| u.host_str().map(String::from))
+    .unwrap_or_default()
+}
+
+/// Rate limit states per host, shared between clones of the client
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+  hosts: Arc<Mutex<HashMap<String, RateLimit>>>,
+}
+
+impl RateLimiter {
+  /// Store rate limit of the response from the URL
+  pub fn update(&self, url: &str, rate_limit: Option<&RateLimit>) {
+    if let (Some(rate_limit), Ok(mut hosts)) = (rate_limit, self.hosts.lock()) {
+      hosts.insert(host_of(url), rate_limit.clone());
+    }
+  }
+
+  /// Returns last rate limit of the host
+  pub fn get(&self, host: &str) -> Option<RateLimit> {
+    self.hosts.lock().ok()?.get(host).cloned()
+  }
+
+  /// Returns time to wait before sending to the host of the URL, None if not exhausted
+  pub fn wait_time(&self, url: &str) -> Option<Duration> {
+    let rate_limit = self.get(&host_of(url))?;
+    if rate_limit.remaining? > 0 {
+      return None;
+    }
+    (rate_limit.reset? - Utc::now()).to_std().ok()
+  }
+}
+
+/// Retry of idempotent requests failed by rate limit, server error or network error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+  /// Retries after the first attempt, 0 to disable
+  pub max_retries: u32,
+  /// Backoff before the first retry, doubled on each retry
+  pub initial_backoff: Duration,
+  /// Upper bound of the backoff
+  pub max_backoff: Duration,
+  /// Longest wait for the rate limit to reset, the request fails if longer
+  pub max_wait: Duration,
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    Self {
+      max_retries: 3,
+      initial_backoff: Duration::from_millis(500),
+      max_backoff: Duration::from_secs(30),
+      max_wait: Duration::from_secs(60),
+    }
+  }
+}
+
+impl RetryPolicy {
+  /// Policy without retry
+  pub fn none() -> Self {
+    Self {
+      max_retries: 0,
+      ..Default::default()
+    }
+  }
+
+  /// Returns jittered backoff before the retry, between half and full of the exponential backoff
+  pub fn backoff(&self, retry: u32) -> Duration {
+    let backoff = self
+      .initial_backoff
+      .saturating_mul(2u32.saturating_pow(retry))
+      .min(self.max_backoff);
+    let half = backoff / 2;
+    half + half.mul_f64(OsRng.next_u32() as f64 / u32::MAX as f64)
+  }
+
+  /// Returns wait before retrying the error, None if it should not be retried
+  pub fn retry_wait(&self, error: &XrpcError, retry: u32) -> Option<Duration> {
+    if retry >= self.max_retries || !error.is_transient() || error.status() == Some(501) {
+      return None;
+    }
+    let backoff = self.backoff(retry);
+    match error.rate_limit().and_then(|r| r.reset) {
+      Some(reset) if error.is_rate_limited() => {
+        let wait = (reset - Utc::now()).to_std().unwrap_or_default();
+        (wait <= self.max_wait).then(|| wait.max(backoff))
+      }
+      _ => Some(backoff),
+    }
+  }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ErrorBody {
+  error: Option<String>,
+  message: Option<String>,
+}
+
+pub(crate) fn method_of(url: &str) -> String {
+  Url::parse(url)
+    .ok()
+    .and_then(|u| {
+      u.path()
+        .strip_prefix("/xrpc/")
+        .map(|m| m.trim_end_matches('/').to_string())
+    })
+    .unwrap_or_default()
+}
+
+/// Failure of an XRPC call, returned inside anyhow::Error by api::Client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XrpcError {
+  /// Error response of the server
+  Status {
+    method: String,
+    status: u16,
+    error: Option<XrpcErrorName>,
+    message: Option<String>,
+    rate_limit: Option<RateLimit>,
+  },
+  /// Connection failure without response
+  Transport { method: String, message: String },
+}
+
+impl XrpcError {
+  /// Read the error body of the response
+  pub fn from_response(res: HttpResponse) -> Self {
+    let method = method_of(res.get_url());
+    let status = res.status();
+    let rate_limit = RateLimit::from_response(&res);
+    let body = res.into_json::<ErrorBody>().unwrap_or_default();
+    Self::Status {
+      method,
+      status,
+      error: body.error.as_deref().map(XrpcErrorName::from_name),
+      message: body.message,
+      rate_limit,
+    }
+  }
+
+  /// Returns method NSID of the call
+  pub fn method(&self) -> &str {
+    match self {
+      Self::Status { method, .. } => method,
+      Self::Transport { method, .. } => method,
+    }
+  }
+
+  /// Returns HTTP status, None if no response
+  pub fn status(&self) -> Option<u16> {
+    match self {
+      Self::Status { status, .. } => Some(*status),
+      Self::Transport { .. } => None,
+    }
+  }
+
+  pub fn error(&self) -> Option<&XrpcErrorName> {
+    match self {
+      Self::Status { error, .. } => error.as_ref(),
+      Self::Transport { .. } => None,
+    }
+  }
+
+  pub fn message(&self) -> Option<&str> {
+    match self {
+      Self::Status { message, .. } => message.as_deref(),
+      Self::Transport { message, .. } => Some(message),
+    }
+  }
+
+  pub fn rate_limit(&self) -> Option<&RateLimit> {
+    match self {
+      Self::Status { rate_limit, .. } => rate_limit.as_ref(),
+      Self::Transport { .. } => None,
+    }
+  }
+
+  /// Returns true if the error name is defined by the lexicon of the method
+  pub fn is_known(&self) -> bool {
+    self
+      .error()
+      .map(|e| known_errors(self.method()).contains(&e.as_str()))
+      .unwrap_or_default()
+  }
+
+  /// Returns true if the access token was missing, expired or refused
+  pub fn is_auth_error(&self) -> bool {
+    self.status() == Some(401)
+      || matches!(
+        self.error(),
+        Some(
+          XrpcErrorName::ExpiredToken
+            | XrpcErrorName::InvalidToken
+            | XrpcErrorName::AuthenticationRequired
+        )
+      )
+  }
+
+  /// Returns true if the call was refused by rate limit
+  pub fn is_rate_limited(&self) -> bool {
+    self.status() == Some(429) || self.error() == Some(&XrpcErrorName::RateLimitExceeded)
+  }
+
+  /// Returns true if the same call may succeed later
+  pub fn is_transient(&self) -> bool {
+    match self {
+      Self::Status { status, .. } => *status == 429 || *status >= 500,
+      Self::Transport { .. } => true,
+    }
+  }
+}
+
+impl Display for XrpcError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Status {
+        method,
+        status,
+        error,
+        message,
+        ..
+      } => write!(
+        f,
+        "{} failed with status {} {} : {}",
+        method,
+        status,
+        error.as_ref().map(|e| e.as_str()).unwrap_or_default(),
+        message.as_deref().unwrap_or_default()
+      ),
+      Self::Transport { method, message } => write!(f, "{} failed : {}", method, message),
+    }
+  }
+}
+
+impl std::error::Error for XrpcError {}